@@ -1,10 +1,19 @@
+use crate::secrets::SECRET_MASK;
 use base64::prelude::*;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use url::Url;
 use uuid::Uuid;
 
+/// Appended to the display text of items whose content was cut short
+const TRUNCATION_MARKER: &str = " [truncated]";
+
+/// Longest preview, in characters, of an `ItemSummary`
+pub const SUMMARY_PREVIEW_CHARS: usize = 100;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ClipboardContentType {
     Text(String),
@@ -13,16 +22,30 @@ pub enum ClipboardContentType {
         format: ImageFormat,
         width: u32,
         height: u32,
+        /// Base64 PNG small enough for a list row, made at capture time so the popup
+        /// doesn't have to decode the full image. Missing for older and imported items
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thumbnail: Option<String>,
     },
     Html {
         html: String,
         plain_text: Option<String>, // Fallback plain text
     },
+    Rtf {
+        rtf: String,
+        plain_text: Option<String>, // Fallback plain text
+    },
     Files(Vec<String>), // File paths
     Other {
         content_type: String,
         data: String, // Base64 encoded for binary data
     },
+    /// Text that looks like a password or key. Only `masked_preview` is displayed or
+    /// searched; `data` is what gets copied back to the clipboard.
+    Secret {
+        masked_preview: String,
+        data: String,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Hash)]
@@ -33,12 +56,273 @@ pub enum ImageFormat {
     Other(String),
 }
 
+/// Clean up user-typed tag text: surrounding whitespace and a leading '#' are dropped.
+/// Returns `None` for an empty tag or one containing whitespace.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#');
+    if tag.is_empty() || tag.chars().any(char::is_whitespace) {
+        None
+    } else {
+        Some(tag.to_string())
+    }
+}
+
+/// Parse `text` as a single web link. Surrounding whitespace is ignored, but text with
+/// anything besides the link (e.g. a sentence or several lines containing one) isn't a
+/// link. A bare "www." address is treated as https.
+pub fn parse_url(text: &str) -> Option<Url> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() || trimmed.contains(char::is_whitespace) {
+        return None;
+    }
+    let url = if trimmed.starts_with("www.") {
+        Url::parse(&format!("https://{trimmed}")).ok()?
+    } else {
+        Url::parse(trimmed).ok()?
+    };
+    (matches!(url.scheme(), "http" | "https" | "ftp") && url.host().is_some()).then_some(url)
+}
+
+/// Restricts search results to one kind of clipboard content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentTypeFilter {
+    #[default]
+    All,
+    Text,
+    Image,
+    Files,
+    Html,
+}
+
+impl ContentTypeFilter {
+    /// Whether `item` has the content type this filter selects
+    pub fn matches(&self, item: &ClipboardItem) -> bool {
+        matches!(
+            (self, &item.content),
+            (ContentTypeFilter::All, _)
+                | (ContentTypeFilter::Text, ClipboardContentType::Text(_))
+                | (ContentTypeFilter::Image, ClipboardContentType::Image { .. })
+                | (ContentTypeFilter::Files, ClipboardContentType::Files(_))
+                | (ContentTypeFilter::Html, ClipboardContentType::Html { .. })
+        )
+    }
+}
+
+/// How loosely two text items must match to count as the same content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupPolicy {
+    /// Only identical text is a duplicate
+    #[default]
+    Exact,
+    /// "foo\n" and "foo" are the same
+    IgnoreTrailingWhitespace,
+    /// Also ignores case, so "Foo" and "foo " are the same
+    IgnoreCase,
+}
+
+impl DedupPolicy {
+    /// The form of `text` that is compared under this policy
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Exact => Cow::Borrowed(text),
+            Self::IgnoreTrailingWhitespace => Cow::Borrowed(text.trim_end()),
+            Self::IgnoreCase => Cow::Owned(text.trim_end().to_lowercase()),
+        }
+    }
+}
+
+/// How the history view is split into groups under headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupMode {
+    /// One flat, newest-first list
+    #[default]
+    None,
+    /// Today / Yesterday / Earlier
+    Date,
+    /// Text / Images / Files / Other
+    ContentType,
+}
+
+/// Header of one group of the history view. Groups are listed in declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GroupHeader {
+    /// The single group of `GroupMode::None`
+    All,
+    Today,
+    Yesterday,
+    Earlier,
+    /// Plain text, HTML and rich text
+    Text,
+    Images,
+    Files,
+    Other,
+}
+
+impl GroupHeader {
+    /// The group `item` belongs to. `today` is the current local date.
+    pub fn of(item: &ClipboardItem, mode: GroupMode, today: NaiveDate) -> Self {
+        match mode {
+            GroupMode::None => GroupHeader::All,
+            GroupMode::Date => {
+                let copied_on = item.timestamp.with_timezone(&Local).date_naive();
+                if copied_on >= today {
+                    GroupHeader::Today
+                } else if today.pred_opt() == Some(copied_on) {
+                    GroupHeader::Yesterday
+                } else {
+                    GroupHeader::Earlier
+                }
+            }
+            GroupMode::ContentType => match item.content {
+                ClipboardContentType::Text(_)
+                | ClipboardContentType::Html { .. }
+                | ClipboardContentType::Rtf { .. }
+                | ClipboardContentType::Secret { .. } => GroupHeader::Text,
+                ClipboardContentType::Image { .. } => GroupHeader::Images,
+                ClipboardContentType::Files(_) => GroupHeader::Files,
+                ClipboardContentType::Other { .. } => GroupHeader::Other,
+            },
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupHeader::All => "All items",
+            GroupHeader::Today => "Today",
+            GroupHeader::Yesterday => "Yesterday",
+            GroupHeader::Earlier => "Earlier",
+            GroupHeader::Text => "Text",
+            GroupHeader::Images => "Images",
+            GroupHeader::Files => "Files",
+            GroupHeader::Other => "Other",
+        }
+    }
+}
+
+/// What a list of the history needs to know about an item, without its content, so
+/// large images and texts aren't copied just to show a row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemSummary {
+    pub id: String,
+    /// Single-line preview of at most `SUMMARY_PREVIEW_CHARS` characters
+    pub preview: String,
+    /// e.g. "Text" or "Image"
+    pub content_type: &'static str,
+    pub timestamp: DateTime<Utc>,
+    /// Size as counted against the content limit
+    pub size_bytes: usize,
+    pub pinned: bool,
+}
+
+impl From<&ClipboardItem> for ItemSummary {
+    fn from(item: &ClipboardItem) -> Self {
+        Self {
+            id: item.id.clone(),
+            preview: item.clean_preview(SUMMARY_PREVIEW_CHARS),
+            content_type: item.content_type_name(),
+            timestamp: item.timestamp,
+            size_bytes: item.get_size_bytes(),
+            pinned: item.pinned,
+        }
+    }
+}
+
+/// Split `items` into groups for `mode`, in `GroupHeader` order. Items keep their order
+/// within a group and empty groups are left out.
+pub fn group_items(
+    items: impl IntoIterator<Item = ClipboardItem>,
+    mode: GroupMode,
+    today: NaiveDate,
+) -> Vec<(GroupHeader, Vec<ClipboardItem>)> {
+    let mut groups: Vec<(GroupHeader, Vec<ClipboardItem>)> = Vec::new();
+    for item in items {
+        let header = GroupHeader::of(&item, mode, today);
+        match groups.iter_mut().find(|(existing, _)| *existing == header) {
+            Some((_, members)) => members.push(item),
+            None => groups.push((header, vec![item])),
+        }
+    }
+    groups.sort_by_key(|(header, _)| *header);
+    groups
+}
+
+/// What the text of an item looks like, beyond its stored content type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DetectedKind {
+    /// A JSON object or array
+    Json,
+    /// A single link, e.g. "https://example.com/page"
+    Url,
+    /// A number, optionally with a sign, decimal point, exponent or thousands separators
+    Numeric,
+    MultiLine,
+    /// Single-line text with non-ASCII characters (accents, CJK, emoji...)
+    Unicode,
+    PlainText,
+    /// Images, files and binary data
+    NonText,
+}
+
+impl DetectedKind {
+    /// Classify `text`, trying the most specific kinds first
+    pub fn of_text(text: &str) -> Self {
+        let trimmed = text.trim();
+        if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+        {
+            DetectedKind::Json
+        } else if parse_url(trimmed).is_some() {
+            DetectedKind::Url
+        } else if trimmed.chars().any(|c| c.is_ascii_digit())
+            && trimmed
+                .chars()
+                .all(|c| c.is_ascii_digit() || ".,+-eE".contains(c))
+            && trimmed.replace(',', "").parse::<f64>().is_ok()
+        {
+            DetectedKind::Numeric
+        } else if trimmed.contains('\n') {
+            DetectedKind::MultiLine
+        } else if !trimmed.is_ascii() {
+            DetectedKind::Unicode
+        } else {
+            DetectedKind::PlainText
+        }
+    }
+
+    /// Short label for display, e.g. "JSON"
+    pub fn label(&self) -> &'static str {
+        match self {
+            DetectedKind::Json => "JSON",
+            DetectedKind::Url => "URL/Link",
+            DetectedKind::Numeric => "Numeric",
+            DetectedKind::MultiLine => "Multi-line",
+            DetectedKind::Unicode => "Unicode",
+            DetectedKind::PlainText => "Text",
+            DetectedKind::NonText => "Non-text",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardItem {
     pub id: String, // Use UUID for better uniqueness
     pub content: ClipboardContentType,
     pub timestamp: DateTime<Utc>,
     pub content_hash: String, // Add content hash for deduplication
+    #[serde(default)]
+    pub last_used: Option<DateTime<Utc>>, // Last time this content was copied again
+    #[serde(default)]
+    pub ocr_text: Option<String>, // Text recognized in image items (with the `ocr` feature)
+    #[serde(default)]
+    pub pinned: bool, // Pinned items are never evicted by the history size limit
+    #[serde(default)]
+    pub tags: Vec<String>, // Freeform labels, stored without the leading '#'
+    #[serde(default)]
+    pub truncated: bool, // Only the start of oversized text was kept
+    #[serde(default)]
+    pub use_count: u32, // Times the item was copied back from the history
 }
 
 impl ClipboardItem {
@@ -50,6 +334,12 @@ impl ClipboardItem {
             content,
             timestamp: Utc::now(),
             content_hash,
+            last_used: None,
+            ocr_text: None,
+            pinned: false,
+            tags: Vec::new(),
+            truncated: false,
+            use_count: 0,
         }
     }
 
@@ -64,6 +354,7 @@ impl ClipboardItem {
             format,
             width,
             height,
+            thumbnail: None,
         })
     }
 
@@ -71,6 +362,10 @@ impl ClipboardItem {
         Self::new(ClipboardContentType::Html { html, plain_text })
     }
 
+    pub fn new_rtf(rtf: String, plain_text: Option<String>) -> Self {
+        Self::new(ClipboardContentType::Rtf { rtf, plain_text })
+    }
+
     pub fn new_files(files: Vec<String>) -> Self {
         Self::new(ClipboardContentType::Files(files))
     }
@@ -79,16 +374,35 @@ impl ClipboardItem {
         Self::new(ClipboardContentType::Other { content_type, data })
     }
 
+    /// A secret found by `SecretDetector`; `kind` is the name of the pattern it matched
+    pub fn new_secret(data: String, kind: &str) -> Self {
+        Self::new(ClipboardContentType::Secret {
+            masked_preview: format!("{kind} {SECRET_MASK}"),
+            data,
+        })
+    }
+
     /// Calculate hash for content deduplication
-    fn calculate_content_hash(content: &ClipboardContentType) -> String {
+    pub fn calculate_content_hash(content: &ClipboardContentType) -> String {
+        Self::calculate_content_hash_with(content, DedupPolicy::Exact)
+    }
+
+    /// Content hash with text normalized according to `policy` first, so near-identical
+    /// text hashes the same. Other content types always hash exactly.
+    pub fn calculate_content_hash_with(
+        content: &ClipboardContentType,
+        policy: DedupPolicy,
+    ) -> String {
         let mut hasher = DefaultHasher::new();
         match content {
-            ClipboardContentType::Text(text) => text.hash(&mut hasher),
+            ClipboardContentType::Text(text) => policy.normalize(text).hash(&mut hasher),
+            // The thumbnail is derived from the image, so it doesn't change its identity
             ClipboardContentType::Image {
                 data,
                 format,
                 width,
                 height,
+                ..
             } => {
                 data.hash(&mut hasher);
                 format.hash(&mut hasher);
@@ -99,11 +413,21 @@ impl ClipboardItem {
                 html.hash(&mut hasher);
                 plain_text.hash(&mut hasher);
             }
+            ClipboardContentType::Rtf { rtf, plain_text } => {
+                "rtf".hash(&mut hasher);
+                rtf.hash(&mut hasher);
+                plain_text.hash(&mut hasher);
+            }
             ClipboardContentType::Files(files) => files.hash(&mut hasher),
             ClipboardContentType::Other { content_type, data } => {
                 content_type.hash(&mut hasher);
                 data.hash(&mut hasher);
             }
+            // The preview is derived from the value
+            ClipboardContentType::Secret { data, .. } => {
+                "secret".hash(&mut hasher);
+                data.hash(&mut hasher);
+            }
         }
         hasher.finish().to_string()
     }
@@ -113,21 +437,168 @@ impl ClipboardItem {
         self.estimate_size()
     }
 
-    /// Get clean preview without type prefix for search and display
+    /// Short human-readable name of the content type
+    pub fn content_type_name(&self) -> &'static str {
+        match &self.content {
+            ClipboardContentType::Text(_) => "Text",
+            ClipboardContentType::Image { .. } => "Image",
+            ClipboardContentType::Html { .. } => "HTML",
+            ClipboardContentType::Rtf { .. } => "RTF",
+            ClipboardContentType::Files(_) => "Files",
+            ClipboardContentType::Other { .. } => "Other",
+            ClipboardContentType::Secret { .. } => "Secret",
+        }
+    }
+
+    /// When the item was copied, in local time, e.g. "2024-05-01 14:03:27"
+    pub fn formatted_timestamp(&self) -> String {
+        self.timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    }
+
+    /// How long ago the item was copied, e.g. "just now", "5 minutes ago" or "yesterday"
+    pub fn relative_timestamp(&self) -> String {
+        self.relative_timestamp_at(Utc::now())
+    }
+
+    /// [`Self::relative_timestamp`] as seen at `now`
+    pub fn relative_timestamp_at(&self, now: DateTime<Utc>) -> String {
+        let elapsed = now.signed_duration_since(self.timestamp);
+        let ago = |count: i64, unit: &str| {
+            if count == 1 {
+                format!("1 {unit} ago")
+            } else {
+                format!("{count} {unit}s ago")
+            }
+        };
+
+        match elapsed.num_seconds() {
+            // Clock adjustments can put items slightly in the future
+            ..=59 => "just now".to_string(),
+            60..=3_599 => ago(elapsed.num_minutes(), "minute"),
+            3_600..=86_399 => ago(elapsed.num_hours(), "hour"),
+            86_400..=172_799 => "yesterday".to_string(),
+            172_800..=604_799 => ago(elapsed.num_days(), "day"),
+            604_800..=2_591_999 => ago(elapsed.num_weeks(), "week"),
+            2_592_000..=31_535_999 => ago(elapsed.num_days() / 30, "month"),
+            _ => ago(elapsed.num_days() / 365, "year"),
+        }
+    }
+
+    /// Plain-text description of the item (not its content), for bug reports and debugging
+    pub fn metadata_summary(&self) -> String {
+        let mut lines = vec![
+            format!("id: {}", self.id),
+            format!("type: {}", self.content_type_name()),
+            format!("kind: {}", self.detected_kind().label()),
+            format!("size: {} bytes", self.get_size_bytes()),
+            format!("timestamp: {}", self.timestamp.to_rfc3339()),
+            format!("content_hash: {}", self.content_hash),
+        ];
+
+        match &self.content {
+            ClipboardContentType::Image {
+                format,
+                width,
+                height,
+                ..
+            } => lines.push(format!("image: {width}x{height} {format:?}")),
+            ClipboardContentType::Files(files) => lines.push(format!("files: {}", files.len())),
+            ClipboardContentType::Other { content_type, .. } => {
+                lines.push(format!("mime: {content_type}"))
+            }
+            ClipboardContentType::Text(_)
+            | ClipboardContentType::Html { .. }
+            | ClipboardContentType::Rtf { .. }
+            | ClipboardContentType::Secret { .. } => {}
+        }
+
+        lines.join("\n")
+    }
+
+    /// What the item's text looks like (JSON, a URL, a number...). HTML and rich text
+    /// are classified by their plain text.
+    pub fn detected_kind(&self) -> DetectedKind {
+        match &self.content {
+            ClipboardContentType::Text(_)
+            | ClipboardContentType::Html { .. }
+            | ClipboardContentType::Rtf { .. } => DetectedKind::of_text(&self.plain_text()),
+            ClipboardContentType::Image { .. }
+            | ClipboardContentType::Files(_)
+            | ClipboardContentType::Other { .. }
+            | ClipboardContentType::Secret { .. } => DetectedKind::NonText,
+        }
+    }
+
+    /// The link this item consists of, if it is a single http(s)/ftp URL.
+    /// HTML and rich text are checked by their plain text.
+    pub fn as_url(&self) -> Option<Url> {
+        match &self.content {
+            ClipboardContentType::Text(_)
+            | ClipboardContentType::Html { .. }
+            | ClipboardContentType::Rtf { .. } => parse_url(&self.plain_text()),
+            _ => None,
+        }
+    }
+
+    /// Single-line preview without type prefix for search and display: runs of
+    /// whitespace (newlines, tabs, indentation) collapse into one space, and the result
+    /// is cut to at most `max_chars` characters, followed by "..." when shortened
     pub fn clean_preview(&self, max_chars: usize) -> String {
         let content_str = self.display_content();
 
-        if content_str.len() <= max_chars {
-            content_str
-        } else {
-            let truncated = content_str.chars().take(max_chars).collect::<String>();
-            format!("{truncated}...")
+        let mut preview = String::new();
+        let mut char_count = 0;
+        let mut pending_space = false;
+        for c in content_str.trim().chars() {
+            if c.is_whitespace() {
+                pending_space = true;
+                continue;
+            }
+            let needed = if pending_space { 2 } else { 1 };
+            if char_count + needed > max_chars {
+                let marker = if self.truncated {
+                    TRUNCATION_MARKER
+                } else {
+                    ""
+                };
+                return format!("{preview}...{marker}");
+            }
+            if pending_space {
+                preview.push(' ');
+                pending_space = false;
+            }
+            preview.push(c);
+            char_count += needed;
         }
+        preview
+    }
+
+    /// Keep only the first `max_chars` characters of a text item and mark it as
+    /// truncated. HTML and rich text are reduced to their plain text, since cutting
+    /// markup would leave it broken. The content hash is left alone, so copying the
+    /// same oversized text again is still recognised as a duplicate.
+    /// Returns `false` (leaving the item unchanged) for images, files and binary data.
+    pub fn truncate_text(&mut self, max_chars: usize) -> bool {
+        let text = match &self.content {
+            ClipboardContentType::Text(_)
+            | ClipboardContentType::Html { .. }
+            | ClipboardContentType::Rtf { .. } => self.plain_text(),
+            _ => return false,
+        };
+        self.content = ClipboardContentType::Text(text.chars().take(max_chars).collect());
+        self.truncated = true;
+        true
     }
 
     /// Get display-friendly content string
     pub fn display_content(&self) -> String {
         match &self.content {
+            ClipboardContentType::Text(text) if self.truncated => {
+                format!("{text}...{TRUNCATION_MARKER}")
+            }
             ClipboardContentType::Text(text) => text.clone(),
             ClipboardContentType::Image {
                 width,
@@ -140,6 +611,10 @@ impl ClipboardItem {
             ClipboardContentType::Html { plain_text, html } => {
                 plain_text.as_ref().unwrap_or(html).clone()
             }
+            // Raw RTF markup is unreadable, so only show it when there is no plain text
+            ClipboardContentType::Rtf { plain_text, rtf } => plain_text
+                .clone()
+                .unwrap_or_else(|| format!("Rich text ({} bytes)", rtf.len())),
             ClipboardContentType::Files(files) => {
                 if files.len() == 1 {
                     format!("File: {}", &files[0])
@@ -152,6 +627,48 @@ impl ClipboardItem {
             ClipboardContentType::Other { content_type, .. } => {
                 format!("Binary data ({content_type})")
             }
+            ClipboardContentType::Secret { masked_preview, .. } => masked_preview.clone(),
+        }
+    }
+
+    /// The item as unformatted text, e.g. for pasting into a terminal: HTML and RTF
+    /// without their markup, file paths one per line, and a description for images.
+    /// Secrets are unmasked, so only use this to copy the item, not to display it.
+    pub fn plain_text(&self) -> String {
+        match &self.content {
+            ClipboardContentType::Text(text) | ClipboardContentType::Secret { data: text, .. } => {
+                text.clone()
+            }
+            ClipboardContentType::Html { html, plain_text } => {
+                plain_text.clone().unwrap_or_else(|| html_to_text(html))
+            }
+            ClipboardContentType::Files(files) => files.join("\n"),
+            ClipboardContentType::Rtf { .. }
+            | ClipboardContentType::Image { .. }
+            | ClipboardContentType::Other { .. } => self.display_content(),
+        }
+    }
+
+    /// Whether the item carries `tag` (compared case-insensitively, with or without '#')
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim_start_matches('#');
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Tags formatted for display, e.g. "#sql #work"
+    pub fn tags_label(&self) -> String {
+        self.tags
+            .iter()
+            .map(|tag| format!("#{tag}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Text that search matches against: the display content plus any OCR text
+    pub fn searchable_text(&self) -> String {
+        match &self.ocr_text {
+            Some(ocr) => format!("{}\n{ocr}", self.display_content()),
+            None => self.display_content(),
         }
     }
 
@@ -163,8 +680,110 @@ impl ClipboardItem {
             ClipboardContentType::Html { html, plain_text } => {
                 html.len() + plain_text.as_ref().map_or(0, |t| t.len())
             }
+            ClipboardContentType::Rtf { rtf, plain_text } => {
+                rtf.len() + plain_text.as_ref().map_or(0, |t| t.len())
+            }
             ClipboardContentType::Files(files) => files.iter().map(|f| f.len()).sum::<usize>(),
             ClipboardContentType::Other { content_type, data } => content_type.len() + data.len(),
+            ClipboardContentType::Secret {
+                masked_preview,
+                data,
+            } => masked_preview.len() + data.len(),
+        }
+    }
+}
+
+/// Strip the markup from an HTML fragment, keeping line breaks for block elements and
+/// decoding common character entities. Script and style contents are dropped.
+pub fn html_to_text(html: &str) -> String {
+    const BLOCK_TAGS: [&str; 12] = [
+        "br", "p", "div", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "pre",
+    ];
+
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut skip_until: Option<&str> = None;
+
+    while let Some(start) = rest.find('<') {
+        if skip_until.is_none() {
+            text.push_str(&decode_entities(&rest[..start]));
+        }
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        rest = &rest[start + end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+
+        match skip_until {
+            Some(skipped) if closing && name == skipped => skip_until = None,
+            Some(_) => {}
+            None if !closing && (name == "script" || name == "style") => {
+                skip_until = Some(if name == "script" { "script" } else { "style" });
+            }
+            None if BLOCK_TAGS.contains(&name.as_str()) && (closing || name == "br") => {
+                text.push('\n');
+            }
+            None => {}
+        }
+    }
+    if skip_until.is_none() {
+        text.push_str(&decode_entities(rest));
+    }
+
+    // Drop the indentation and blank lines left behind by the markup
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 8)
+            .map(|end| &rest[1..end + 1]);
+        let replacement = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#')?.parse().ok(),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+
+        match (entity, replacement) {
+            (Some(entity), Some(c)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
         }
     }
+    decoded.push_str(rest);
+    decoded
 }
@@ -1,226 +1,1987 @@
-use crate::clipboard_item::{ClipboardContentType, ClipboardItem};
-use crate::monitor::ClipboardMonitor;
-use crate::storage::Storage;
+use crate::clipboard_item::{
+    normalize_tag, ClipboardContentType, ClipboardItem, ContentTypeFilter, DedupPolicy,
+    ImageFormat, ItemSummary,
+};
+use crate::config::PopupConfig;
+use crate::error::ClipboardError;
+use crate::monitor::{ClipboardEvent, ClipboardMonitor};
+use crate::storage::{self, Storage};
+use crate::system_clipboard::{ArboardClipboard, SystemClipboard};
+use crate::thumbnail::ThumbnailCache;
 use base64::prelude::*;
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
-use std::collections::VecDeque;
-use std::io;
+use regex::Regex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc, watch, Mutex};
+use tokio::task::JoinHandle;
 
-const MAX_HISTORY_SIZE: usize = 1000;
+/// History size limit used when none is configured
+pub const DEFAULT_MAX_HISTORY_SIZE: usize = 1000;
+/// Longest edge, in pixels, of images stored in the history when none is configured
+pub const DEFAULT_MAX_STORED_IMAGE_DIMENSION: u32 = 1920;
 const MAX_CONTENT_SIZE: usize = 10_000_000; // 10MB limit for individual entries
+/// Characters of text kept from items over the size limit under `OversizePolicy::Truncate`
+pub const TRUNCATED_TEXT_LENGTH: usize = 1_000_000;
+const SEARCH_CACHE_CAPACITY: usize = 32; // Number of distinct queries kept per manager
+/// Fuzzy score added per time an item was copied back, so favourites rank higher
+const USE_COUNT_BONUS: i64 = 3;
+/// Copies beyond this many earn no further bonus, so use never outweighs a clearly better match
+const MAX_BONUS_USES: u32 = 10;
+/// Items a manager from `new_streaming` starts out with; the rest follow in batches this size
+pub const LOAD_BATCH_SIZE: usize = 100;
+
+/// Names one history item, by position or by id. Ids stay valid while new items
+/// arrive; positions shift.
+#[derive(Debug, Clone, Copy)]
+enum ItemKey<'a> {
+    Index(usize),
+    Id(&'a str),
+}
+
+impl std::fmt::Display for ItemKey<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemKey::Index(index) => write!(f, "index {index}"),
+            ItemKey::Id(id) => write!(f, "id {id}"),
+        }
+    }
+}
+
+impl ItemKey<'_> {
+    fn position(self, history: &VecDeque<ClipboardItem>) -> Option<usize> {
+        match self {
+            ItemKey::Index(index) => (index < history.len()).then_some(index),
+            ItemKey::Id(id) => history.iter().position(|item| item.id == id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchKind {
+    Exact,
+    Fuzzy,
+}
+
+type SearchKey = (SearchKind, String);
+type CachedMatches = Vec<(String, Option<i64>)>; // (item id, fuzzy score)
+
+/// Small LRU cache mapping a normalized query to the ids (and fuzzy scores) it matched.
+/// Ids are resolved back against the live history on a hit, and the whole cache is
+/// cleared whenever the history is mutated.
+#[derive(Debug, Default)]
+struct SearchCache {
+    entries: VecDeque<(SearchKey, CachedMatches)>,
+}
+
+impl SearchCache {
+    fn get(&mut self, key: &SearchKey) -> Option<CachedMatches> {
+        let position = self.entries.iter().position(|(k, _)| k == key)?;
+        // Move the hit to the front so it is evicted last
+        let entry = self.entries.remove(position)?;
+        let ids = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(ids)
+    }
+
+    fn insert(&mut self, key: SearchKey, ids: CachedMatches) {
+        self.entries.retain(|(k, _)| k != &key);
+        self.entries.push_front((key, ids));
+        self.entries.truncate(SEARCH_CACHE_CAPACITY);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Search text of one item, computed once instead of on every query
+#[derive(Debug)]
+struct IndexedText {
+    text: String,
+    lowercase: String,
+    /// Which ASCII letters and digits occur in the text, one bit each
+    char_mask: u64,
+}
+
+impl IndexedText {
+    fn new(item: &ClipboardItem) -> Self {
+        let text = item.searchable_text();
+        let lowercase = text.to_lowercase();
+        let char_mask = char_mask(&lowercase);
+        Self {
+            text,
+            lowercase,
+            char_mask,
+        }
+    }
+}
+
+/// Bit set of the ASCII letters (case-insensitively) and digits in `text`. A fuzzy match
+/// needs every query character in the item, so an item whose mask lacks a bit of the
+/// query's mask can be skipped without running the matcher.
+fn char_mask(text: &str) -> u64 {
+    text.chars()
+        .fold(0, |mask, c| match c.to_ascii_lowercase() {
+            c @ 'a'..='z' => mask | 1 << (c as u32 - 'a' as u32),
+            c @ '0'..='9' => mask | 1 << (26 + c as u32 - '0' as u32),
+            _ => mask,
+        })
+}
+
+/// Per-item search text keyed by item id. Entries are added lazily for new items,
+/// dropped for removed ones, and invalidated when an item's searchable text changes.
+#[derive(Debug, Default)]
+struct SearchIndex {
+    entries: HashMap<String, IndexedText>,
+}
+
+impl SearchIndex {
+    /// Bring the index in line with `items`, computing text only for unseen items
+    fn sync(&mut self, items: &[ClipboardItem]) {
+        if self.entries.len() > items.len() {
+            let live: HashSet<&str> = items.iter().map(|item| item.id.as_str()).collect();
+            self.entries.retain(|id, _| live.contains(id.as_str()));
+        }
+        for item in items {
+            if !self.entries.contains_key(&item.id) {
+                self.entries.insert(item.id.clone(), IndexedText::new(item));
+            }
+        }
+    }
+
+    fn invalidate(&mut self, id: &str) {
+        self.entries.remove(id);
+    }
+
+    /// Same as `search_snapshot`, using the precomputed lowercase text
+    fn search(&mut self, items: &[ClipboardItem], query: &str) -> Vec<usize> {
+        self.sync(items);
+        let query = query.to_lowercase();
+        items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| self.entries[&item.id].lowercase.contains(&query))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Same as `fuzzy_search_snapshot`, skipping items that lack a character of the query
+    fn fuzzy_search(&mut self, items: &[ClipboardItem], query: &str) -> Vec<(usize, i64)> {
+        self.sync(items);
+        let matcher = SkimMatcherV2::default();
+        let query_mask = char_mask(query);
+
+        let mut fuzzy_matches: Vec<(usize, i64)> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| {
+                let indexed = &self.entries[&item.id];
+                if indexed.char_mask & query_mask != query_mask {
+                    return None;
+                }
+                matcher
+                    .fuzzy_match(&indexed.text, query)
+                    .map(|score| (idx, score + usage_bonus(item)))
+            })
+            .collect();
+
+        sort_by_score(items, &mut fuzzy_matches);
+        fuzzy_matches
+    }
+}
+
+/// Case-insensitive substring search over a snapshot of the history (e.g. from
+/// `get_history`), without touching the manager's lock.
+/// Returns indices into `items`, in their original order.
+pub fn search_snapshot(items: &[ClipboardItem], query: &str) -> Vec<usize> {
+    let query = query.to_lowercase();
+
+    // Search across different content types using searchable_text (without type prefix)
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.searchable_text().to_lowercase().contains(&query))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Score added to an item's fuzzy match score for how often it was copied back
+fn usage_bonus(item: &ClipboardItem) -> i64 {
+    i64::from(item.use_count.min(MAX_BONUS_USES)) * USE_COUNT_BONUS
+}
+
+/// Order `(index into items, score)` matches best first. Equal scores go most recently
+/// copied first, then by id, so results don't reorder between identical searches.
+fn sort_by_score(items: &[ClipboardItem], matches: &mut [(usize, i64)]) {
+    matches.sort_by(|&(a, a_score), &(b, b_score)| {
+        b_score
+            .cmp(&a_score)
+            .then_with(|| items[b].timestamp.cmp(&items[a].timestamp))
+            .then_with(|| items[a].id.cmp(&items[b].id))
+    });
+}
+
+/// Fuzzy search over a snapshot of the history without touching the manager's lock.
+/// Returns `(index into items, score)` pairs, best match first. Frequently copied items
+/// get a small score bonus.
+pub fn fuzzy_search_snapshot(items: &[ClipboardItem], query: &str) -> Vec<(usize, i64)> {
+    fuzzy_search_snapshot_with(items, query, None, None)
+}
+
+/// `fuzzy_search_snapshot` keeping only matches scoring at least `min_score`, and at
+/// most `limit` of them
+pub fn fuzzy_search_snapshot_with(
+    items: &[ClipboardItem],
+    query: &str,
+    min_score: Option<i64>,
+    limit: Option<usize>,
+) -> Vec<(usize, i64)> {
+    let matcher = SkimMatcherV2::default();
+
+    let mut fuzzy_matches: Vec<(usize, i64)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| {
+            matcher
+                .fuzzy_match(&item.searchable_text(), query)
+                .map(|score| (idx, score + usage_bonus(item)))
+                .filter(|&(_, score)| min_score.is_none_or(|min| score >= min))
+        })
+        .collect();
+
+    sort_by_score(items, &mut fuzzy_matches);
+    if let Some(limit) = limit {
+        fuzzy_matches.truncate(limit);
+    }
+    fuzzy_matches
+}
+
+/// The tag of a `#tag` search query, or `None` for any other query
+pub fn tag_query(query: &str) -> Option<&str> {
+    query
+        .strip_prefix('#')
+        .filter(|tag| !tag.is_empty() && !tag.chars().any(char::is_whitespace))
+}
+
+/// Turn a relative time range such as "today", "yesterday", "last 7 days" or
+/// "last 3 hours" into a `[from, to)` range ending at `now`. Day boundaries are
+/// midnight in `now`'s time zone. Returns `None` if the range isn't recognized.
+pub fn parse_time_range<Tz: TimeZone>(
+    spec: &str,
+    now: DateTime<Tz>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let spec = spec.trim().to_lowercase();
+    let words: Vec<&str> = spec.split_whitespace().collect();
+    let start_of_today = || {
+        now.timezone()
+            .from_local_datetime(&now.date_naive().and_hms_opt(0, 0, 0)?)
+            .earliest()
+            .map(|midnight| midnight.with_timezone(&Utc))
+    };
+    let now_utc = now.with_timezone(&Utc);
+
+    match words.as_slice() {
+        ["today"] => Some((start_of_today()?, now_utc)),
+        ["yesterday"] => {
+            let today = start_of_today()?;
+            Some((today - Duration::days(1), today))
+        }
+        ["last", "week"] => Some((now_utc - Duration::days(7), now_utc)),
+        ["last", unit] | ["last", "1", unit] => Some((now_utc - time_unit(unit, 1)?, now_utc)),
+        ["last", count, unit] => {
+            let count: i64 = count.parse().ok().filter(|&count| count > 0)?;
+            Some((now_utc - time_unit(unit, count)?, now_utc))
+        }
+        _ => None,
+    }
+}
+
+/// `count` of a unit named in a relative time range ("hour", "days", ...)
+fn time_unit(unit: &str, count: i64) -> Option<Duration> {
+    match unit.trim_end_matches('s') {
+        "minute" => Duration::try_minutes(count),
+        "hour" => Duration::try_hours(count),
+        "day" => Duration::try_days(count),
+        "week" => Duration::try_weeks(count),
+        _ => None,
+    }
+}
+
+/// Indices of the snapshot items carrying `tag`
+pub fn tag_search_snapshot(items: &[ClipboardItem], tag: &str) -> Vec<usize> {
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.has_tag(tag))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Regex search over a snapshot of the history without touching the manager's lock.
+/// Matches against the same text as `search_snapshot`, so OCR text of images is included.
+pub fn regex_search_snapshot(items: &[ClipboardItem], pattern: &Regex) -> Vec<usize> {
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| pattern.is_match(&item.searchable_text()))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// What happens to items larger than the 10MB content limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OversizePolicy {
+    /// Refuse the item with `ClipboardError::ContentTooLarge`
+    #[default]
+    Reject,
+    /// Keep the first `TRUNCATED_TEXT_LENGTH` characters of text, marked as truncated.
+    /// Oversized images, files and binary data are still rejected.
+    Truncate,
+}
+
+/// Size of the history as it stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageStats {
+    pub item_count: usize,
+    pub pinned_count: usize,
+    /// Combined size of all items, as counted against the content limit
+    pub total_bytes: usize,
+    pub largest_item_bytes: usize,
+}
+
+/// Limits an item or the history as a whole must stay within
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentLimits {
+    pub max_history_size: usize,
+    /// Largest single item accepted, in bytes
+    pub max_content_size: usize,
+}
+
+/// How far a history loaded in the background by `finish_loading` has got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoadState {
+    Loaded,
+    /// Saves are held back meanwhile, as they would drop the part not read yet
+    Loading {
+        save_deferred: bool,
+    },
+    /// The rest couldn't be read, so nothing is saved for the rest of the session
+    Failed,
+}
+
+/// The part of a history `new_streaming_with_limit` hasn't handed out yet
+#[derive(Debug)]
+struct PendingLoad {
+    batches: mpsc::UnboundedReceiver<Vec<ClipboardItem>>,
+    reader: JoinHandle<Result<(), ClipboardError>>,
+}
 
 #[derive(Debug)]
 pub struct ClipboardManager {
     history: Arc<Mutex<VecDeque<ClipboardItem>>>,
-    storage: Storage,
+    storage: Arc<dyn Storage>,
+    search_cache: std::sync::Mutex<SearchCache>,
+    search_index: std::sync::Mutex<SearchIndex>,
+    thumbnails: Arc<ThumbnailCache>,
+    clipboard: Arc<dyn SystemClipboard>,
+    touch_on_duplicate: bool,
+    dedup_entire_history: bool,
+    dedup_policy: DedupPolicy,
+    max_history_size: AtomicUsize,
+    // Total size the history is trimmed to, counted like `ContentLimits::max_content_size`
+    max_history_bytes: Option<usize>,
+    max_stored_image_dimension: u32,
+    oversize_policy: OversizePolicy,
+    // Items older than this are purged by `prune_expired`; `None` keeps them forever
+    max_age: Option<Duration>,
+    event_sender: broadcast::Sender<ClipboardEvent>,
+    // Content hash of what was last copied back to the clipboard, until the monitor sees it
+    own_write: std::sync::Mutex<Option<String>>,
+    // History as it was before the last `clear_history`, until `undo_clear` or the next add
+    cleared: std::sync::Mutex<Option<VecDeque<ClipboardItem>>>,
+    load_state: watch::Sender<LoadState>,
+    pending_load: std::sync::Mutex<Option<PendingLoad>>,
+    // Ids in the history file as of the last load, save or reload. `reload` tells items
+    // removed since (here or by another instance) from ones that are new
+    synced_ids: std::sync::Mutex<HashSet<String>>,
 }
 
 impl ClipboardManager {
-    pub async fn new() -> io::Result<Self> {
-        let storage = Storage::new()?;
-        let history = Arc::new(Mutex::new(storage.load_history().await?));
+    pub async fn new() -> Result<Self, ClipboardError> {
+        Self::new_configured(storage::default_storage()?).await
+    }
+
+    /// Create a manager whose history is kept only in memory and never written to disk
+    pub async fn new_ephemeral() -> Result<Self, ClipboardError> {
+        Self::new_configured(storage::in_memory()).await
+    }
 
-        Ok(Self { history, storage })
+    /// Like `new`, but returns as soon as the newest `LOAD_BATCH_SIZE` items are loaded,
+    /// so a large history doesn't hold up startup. Run `finish_loading` for the rest.
+    pub async fn new_streaming() -> Result<Self, ClipboardError> {
+        // A broken config file is reported by the caller; just use the defaults here
+        let config = PopupConfig::load().unwrap_or_default();
+        let manager =
+            Self::new_streaming_with_limit(storage::default_storage()?, config.max_history_size)
+                .await?;
+        Ok(manager.configured(&config))
     }
 
-    #[cfg(test)]
-    #[allow(dead_code)]
-    pub async fn new_with_storage(storage: Storage) -> io::Result<Self> {
-        let history = Arc::new(Mutex::new(storage.load_history().await?));
-        Ok(Self { history, storage })
+    /// Create a manager over `storage` with the limits from the config file
+    async fn new_configured(storage: Box<dyn Storage>) -> Result<Self, ClipboardError> {
+        // A broken config file is reported by the caller; just use the defaults here
+        let config = PopupConfig::load().unwrap_or_default();
+        let manager = Self::new_with_limits(
+            storage,
+            config.max_history_size,
+            config.max_history_bytes.map(|bytes| bytes as usize),
+        )
+        .await?
+        .configured(&config);
+        manager.prune_expired().await?;
+        Ok(manager)
     }
 
-    // Public method for testing - creates an empty manager
-    #[allow(dead_code)] // Used by tests
+    fn configured(self, config: &PopupConfig) -> Self {
+        self.with_dedup_entire_history(config.dedup_entire_history)
+            .with_dedup_policy(config.dedup_policy)
+            .with_touch_on_duplicate(config.touch_on_duplicate)
+            .with_max_stored_image_dimension(config.max_stored_image_dimension)
+            .with_max_age(config.max_age_days.map(|days| Duration::days(days as i64)))
+            .with_max_history_bytes(config.max_history_bytes.map(|bytes| bytes as usize))
+    }
+
+    /// Create a manager over the given storage with the default history size limit
+    pub async fn new_with_storage(storage: Box<dyn Storage>) -> Result<Self, ClipboardError> {
+        Self::new_with_limit(storage, DEFAULT_MAX_HISTORY_SIZE).await
+    }
+
+    /// Create a manager over the given storage keeping at most `max_history_size` items.
+    /// The loaded history is trimmed to the current limits right away, so lowering a
+    /// limit takes effect on restart rather than on the next clipboard change.
+    pub async fn new_with_limit(
+        storage: Box<dyn Storage>,
+        max_history_size: usize,
+    ) -> Result<Self, ClipboardError> {
+        Self::new_with_limits(storage, max_history_size, None).await
+    }
+
+    /// Like `new_with_limit`, also trimming the history to `max_history_bytes` in total
+    /// (see `with_max_history_bytes`) right away
+    pub async fn new_with_limits(
+        storage: Box<dyn Storage>,
+        max_history_size: usize,
+        max_history_bytes: Option<usize>,
+    ) -> Result<Self, ClipboardError> {
+        let max_history_size = Self::validate_max_history_size(max_history_size)?;
+        let mut loaded = storage.load_history().await?;
+        if Self::enforce_limits(&mut loaded, max_history_size, max_history_bytes) {
+            storage.save_history(&loaded).await?;
+        }
+        Ok(Self::from_parts(storage.into(), loaded, max_history_size)
+            .with_max_history_bytes(max_history_bytes))
+    }
+
+    /// Create a manager over `storage` as soon as its newest `LOAD_BATCH_SIZE` items are
+    /// read. Until `finish_loading` has added the rest, saves are held back and changes
+    /// to the history as a whole wait for it.
+    pub async fn new_streaming_with_limit(
+        storage: Box<dyn Storage>,
+        max_history_size: usize,
+    ) -> Result<Self, ClipboardError> {
+        let max_history_size = Self::validate_max_history_size(max_history_size)?;
+        let storage: Arc<dyn Storage> = storage.into();
+        let (sender, mut batches) = mpsc::unbounded_channel();
+        let reading = Arc::clone(&storage);
+        let runtime = tokio::runtime::Handle::current();
+        // Storage reads block, so the file is read on a thread of its own
+        let reader = tokio::task::spawn_blocking(move || {
+            runtime.block_on(reading.load_history_batched(LOAD_BATCH_SIZE, &mut |batch| {
+                let _ = sender.send(batch);
+            }))
+        });
+
+        let Some(first) = batches.recv().await else {
+            // Nothing was read: the history is empty or couldn't be read at all
+            Self::join_reader(reader).await?;
+            return Ok(Self::from_parts(storage, VecDeque::new(), max_history_size));
+        };
+        let manager = Self::from_parts(storage, first.into(), max_history_size);
+        manager.load_state.send_replace(LoadState::Loading {
+            save_deferred: false,
+        });
+        *manager
+            .pending_load
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            Some(PendingLoad { batches, reader });
+        Ok(manager)
+    }
+
+    // Public method for testing - creates an empty manager that never touches disk
     pub fn new_empty() -> Self {
-        let history = Arc::new(Mutex::new(VecDeque::new()));
-        // Create a dummy storage for testing
-        let storage = Storage::new_with_file(std::path::PathBuf::from("test_history.json"))
-            .unwrap_or_else(|_| {
-                // Fallback to a simple path if that fails
-                Storage::new_with_file(std::path::PathBuf::from("./test.json")).unwrap()
-            });
+        Self::from_parts(
+            storage::in_memory().into(),
+            VecDeque::new(),
+            DEFAULT_MAX_HISTORY_SIZE,
+        )
+    }
+
+    fn from_parts(
+        storage: Arc<dyn Storage>,
+        history: VecDeque<ClipboardItem>,
+        max_history_size: usize,
+    ) -> Self {
+        let synced_ids = history.iter().map(|item| item.id.clone()).collect();
+        Self {
+            history: Arc::new(Mutex::new(history)),
+            storage,
+            search_cache: std::sync::Mutex::new(SearchCache::default()),
+            search_index: std::sync::Mutex::new(SearchIndex::default()),
+            thumbnails: Arc::new(ThumbnailCache::default()),
+            clipboard: Arc::new(ArboardClipboard::default()),
+            touch_on_duplicate: false,
+            dedup_entire_history: true,
+            dedup_policy: DedupPolicy::Exact,
+            max_stored_image_dimension: DEFAULT_MAX_STORED_IMAGE_DIMENSION,
+            oversize_policy: OversizePolicy::Reject,
+            max_age: None,
+            event_sender: broadcast::channel(100).0,
+            own_write: std::sync::Mutex::new(None),
+            cleared: std::sync::Mutex::new(None),
+            max_history_size: AtomicUsize::new(max_history_size),
+            max_history_bytes: None,
+            load_state: watch::Sender::new(LoadState::Loaded),
+            pending_load: std::sync::Mutex::new(None),
+            synced_ids: std::sync::Mutex::new(synced_ids),
+        }
+    }
+
+    async fn join_reader(
+        reader: JoinHandle<Result<(), ClipboardError>>,
+    ) -> Result<(), ClipboardError> {
+        reader
+            .await
+            .map_err(|e| ClipboardError::Io(std::io::Error::other(e)))?
+    }
+
+    /// Add the rest of a history from `new_streaming_with_limit` behind the items already
+    /// there, then apply the limits and save what was held back meanwhile. Does nothing
+    /// if the history was loaded whole.
+    pub async fn finish_loading(&self) -> Result<(), ClipboardError> {
+        let pending = self
+            .pending_load
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        let Some(PendingLoad {
+            mut batches,
+            reader,
+        }) = pending
+        else {
+            return Ok(());
+        };
+
+        while let Some(batch) = batches.recv().await {
+            self.lock_synced_ids()
+                .extend(batch.iter().map(|item| item.id.clone()));
+            self.history.lock().await.extend(batch);
+            self.invalidate_search_cache();
+        }
+        if let Err(e) = Self::join_reader(reader).await {
+            self.load_state.send_replace(LoadState::Failed);
+            return Err(e);
+        }
 
-        Self { history, storage }
+        let mut history = self.history.lock().await;
+        // Copies made while loading were only compared with the items read by then
+        let merged = self.dedup_entire_history && Self::merge_duplicates(&mut history);
+        let trimmed = self.trim_to_limits(&mut history);
+        self.invalidate_search_cache();
+        drop(history);
+
+        let held_back = self.load_state.send_replace(LoadState::Loaded)
+            == LoadState::Loading {
+                save_deferred: true,
+            };
+        if self.prune_expired().await? == 0 && (merged || trimmed || held_back) {
+            self.save_history().await?;
+        }
+        Ok(())
+    }
+
+    /// Whether part of the history is still being read by `finish_loading`
+    pub fn is_loading(&self) -> bool {
+        matches!(*self.load_state.borrow(), LoadState::Loading { .. })
+    }
+
+    /// Wait until `finish_loading` has read the whole history, or given up on it
+    pub async fn wait_until_loaded(&self) {
+        let mut state = self.load_state.subscribe();
+        let _ = state
+            .wait_for(|state| !matches!(state, LoadState::Loading { .. }))
+            .await;
+    }
+
+    /// Fold older copies of the same content into the newest one, as if each had been
+    /// copied again. Returns whether there were any.
+    fn merge_duplicates(history: &mut VecDeque<ClipboardItem>) -> bool {
+        let mut oldest_copy: HashMap<String, usize> = HashMap::new();
+        let mut folded = Vec::new();
+        // Oldest first, so each copy carries along everything older before it is folded in
+        for index in (0..history.len()).rev() {
+            let hash = history[index].content_hash.clone();
+            let Some(older) = oldest_copy.insert(hash, index) else {
+                continue;
+            };
+            let previous = history[older].clone();
+            let item = &mut history[index];
+            item.pinned |= previous.pinned;
+            if item.ocr_text.is_none() {
+                item.ocr_text = previous.ocr_text;
+            }
+            for tag in previous.tags {
+                if !item.tags.contains(&tag) {
+                    item.tags.push(tag);
+                }
+            }
+            item.use_count = item
+                .use_count
+                .saturating_add(previous.use_count)
+                .saturating_add(1);
+            item.last_used = item.last_used.max(Some(item.timestamp));
+            folded.push(older);
+        }
+        // Found newest last; removing from the back keeps the other positions valid
+        folded.sort_unstable_by(|a, b| b.cmp(a));
+        for index in &folded {
+            history.remove(*index);
+        }
+        !folded.is_empty()
+    }
+
+    /// Use a different system clipboard implementation (e.g. a mock in tests)
+    pub fn with_clipboard(mut self, clipboard: Arc<dyn SystemClipboard>) -> Self {
+        self.clipboard = clipboard;
+        self
+    }
+
+    /// Whether re-copying the newest item refreshes its timestamps instead of being ignored
+    pub fn with_touch_on_duplicate(mut self, touch: bool) -> Self {
+        self.touch_on_duplicate = touch;
+        self
+    }
+
+    /// Whether copying content that is already somewhere in the history moves the old
+    /// entry to the front (default) instead of adding a second, chronological copy.
+    /// Re-copying the newest item is always treated as a duplicate.
+    pub fn with_dedup_entire_history(mut self, dedup: bool) -> Self {
+        self.dedup_entire_history = dedup;
+        self
+    }
+
+    /// How loosely new text must match an existing item to count as a duplicate.
+    /// Only affects items added from now on; the text of each item is kept as copied.
+    pub fn with_dedup_policy(mut self, policy: DedupPolicy) -> Self {
+        self.dedup_policy = policy;
+        self
+    }
+
+    /// Downscale captured images whose longer edge exceeds `max_dimension` pixels before
+    /// they are stored. `0` stores images at full size.
+    pub fn with_max_stored_image_dimension(mut self, max_dimension: u32) -> Self {
+        self.max_stored_image_dimension = max_dimension;
+        self
+    }
+
+    /// Whether items over the content size limit are rejected (default) or, for text,
+    /// stored truncated
+    pub fn with_oversize_policy(mut self, policy: OversizePolicy) -> Self {
+        self.oversize_policy = policy;
+        self
+    }
+
+    /// Purge items older than `max_age` (pinned items are kept) whenever
+    /// `prune_expired` runs. `None` keeps items forever.
+    pub fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Trim the oldest unpinned items whenever the history as a whole grows past
+    /// `max_history_bytes`, counted like the per-item content limit. The newest item is
+    /// kept even if it alone is larger. `None` (default) only limits the item count.
+    pub fn with_max_history_bytes(mut self, max_history_bytes: Option<usize>) -> Self {
+        self.max_history_bytes = max_history_bytes;
+        self
+    }
+
+    /// Sender for events about this history. The monitor publishes on the same channel,
+    /// so subscribers see captures and copies in one stream.
+    pub fn event_sender(&self) -> broadcast::Sender<ClipboardEvent> {
+        self.event_sender.clone()
+    }
+
+    /// Whether `captured` is what this manager just copied back to the clipboard.
+    /// Only the first change the monitor sees after a copy is checked, so exactly one
+    /// matching capture is skipped; copying the same content again later is recorded.
+    pub fn is_own_write(&self, captured: &ClipboardItem) -> bool {
+        let own_write = self.own_write.lock().ok().and_then(|mut own| own.take());
+        // The monitor hashes exactly; stored and copied-back hashes follow the dedup policy
+        own_write.is_some_and(|hash| hash == self.policy_hash(&captured.content))
+    }
+
+    /// Content hash under this manager's dedup policy
+    fn policy_hash(&self, content: &ClipboardContentType) -> String {
+        ClipboardItem::calculate_content_hash_with(content, self.dedup_policy)
+    }
+
+    /// Longest edge of stored images, or `0` for no limit
+    pub fn max_stored_image_dimension(&self) -> u32 {
+        self.max_stored_image_dimension
+    }
+
+    /// Maximum number of items kept in the history (pinned items are never trimmed)
+    pub fn max_history_size(&self) -> usize {
+        self.max_history_size.load(Ordering::SeqCst)
     }
 
-    pub async fn add_clipboard_item(&self, item: ClipboardItem) -> io::Result<()> {
-        // Check content size limit
+    /// Change the history size limit. If the history is now over the limit, the oldest
+    /// unpinned items are dropped and the trimmed history is saved immediately.
+    /// Returns whether anything was removed.
+    pub async fn set_max_history_size(
+        &self,
+        max_history_size: usize,
+    ) -> Result<bool, ClipboardError> {
+        let max_history_size = Self::validate_max_history_size(max_history_size)?;
+        self.wait_until_loaded().await;
+        let mut history = self.history.lock().await;
+        self.max_history_size
+            .store(max_history_size, Ordering::SeqCst);
+
+        if !self.trim_to_limits(&mut history) {
+            return Ok(false);
+        }
+        self.invalidate_search_cache();
+        drop(history);
+        self.save_history().await?;
+        Ok(true)
+    }
+
+    /// The file the history is persisted to
+    pub fn data_file_path(&self) -> &std::path::Path {
+        self.storage.get_data_file_path()
+    }
+
+    /// Thumbnails of the image items, kept in step with the history
+    pub fn thumbnails(&self) -> Arc<ThumbnailCache> {
+        Arc::clone(&self.thumbnails)
+    }
+
+    /// The system clipboard this manager copies items back to
+    pub fn clipboard(&self) -> Arc<dyn SystemClipboard> {
+        Arc::clone(&self.clipboard)
+    }
+
+    /// Apply the content size limit according to the oversize policy
+    fn fit_content_size(&self, mut item: ClipboardItem) -> Result<ClipboardItem, ClipboardError> {
         let item_size = item.get_size_bytes();
-        if item_size > MAX_CONTENT_SIZE {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Content too large: {item_size} bytes (max: {MAX_CONTENT_SIZE} bytes)"),
-            ));
+        if item_size <= MAX_CONTENT_SIZE
+            || (self.oversize_policy == OversizePolicy::Truncate
+                && item.truncate_text(TRUNCATED_TEXT_LENGTH))
+        {
+            return Ok(item);
         }
+        Err(ClipboardError::ContentTooLarge {
+            size: item_size,
+            max: MAX_CONTENT_SIZE,
+        })
+    }
+
+    pub async fn add_clipboard_item(&self, item: ClipboardItem) -> Result<(), ClipboardError> {
+        let mut item = self.fit_content_size(item)?;
+        if self.dedup_policy != DedupPolicy::Exact {
+            item.content_hash = self.policy_hash(&item.content);
+        }
+        // Decoded here rather than when the popup first shows the image
+        self.thumbnails.precompute(&item);
 
         let mut history = self.history.lock().await;
 
         // Skip duplicates by comparing content hash
-        if let Some(last) = history.front() {
+        if let Some(last) = history.front_mut() {
             if last.content_hash == item.content_hash {
-                return Ok(());
+                if !self.touch_on_duplicate {
+                    return Ok(());
+                }
+                last.timestamp = item.timestamp;
+                last.last_used = Some(item.timestamp);
+                // Range and recency results depend on the timestamp
+                self.invalidate_search_cache();
+                drop(history);
+                return self.save_history().await;
+            }
+        }
+
+        if self.dedup_entire_history {
+            // Move an older copy of the same content up instead of keeping both
+            if let Some(position) = history
+                .iter()
+                .position(|existing| existing.content_hash == item.content_hash)
+            {
+                if let Some(previous) = history.remove(position) {
+                    item.pinned = previous.pinned;
+                    item.ocr_text = previous.ocr_text;
+                    item.tags = previous.tags;
+                    // Copying it again is another use, for frequency ranking
+                    item.use_count = previous.use_count.saturating_add(1);
+                    item.last_used = Some(item.timestamp);
+                }
             }
         }
 
         history.push_front(item);
+        self.discard_cleared();
 
         // Maintain max size
-        if history.len() > MAX_HISTORY_SIZE {
-            history.pop_back();
+        self.trim_to_limits(&mut history);
+
+        self.invalidate_search_cache();
+        drop(history);
+        self.save_history().await
+    }
+
+    /// Remove every item, pinned ones included, and persist. The removed items are kept
+    /// in memory so `undo_clear` can bring them back until the next item is added.
+    pub async fn clear_history(&self) -> Result<(), ClipboardError> {
+        self.wait_until_loaded().await;
+        let removed = std::mem::take(&mut *self.history.lock().await);
+        // Clearing an already empty history keeps the earlier snapshot
+        if !removed.is_empty() {
+            *self.lock_cleared() = Some(removed);
+        }
+        self.invalidate_search_cache();
+        self.save_history().await
+    }
+
+    /// Restore the history removed by the last `clear_history` and persist it. Returns
+    /// false if there is nothing to restore: nothing was cleared, the clear was already
+    /// undone, or an item has been added since.
+    pub async fn undo_clear(&self) -> Result<bool, ClipboardError> {
+        let mut history = self.history.lock().await;
+        let Some(mut restored) = self.lock_cleared().take() else {
+            return Ok(false);
+        };
+        // Anything that showed up since (e.g. reloaded from another instance) stays on top
+        restored.retain(|item| {
+            !history
+                .iter()
+                .any(|current| current.content_hash == item.content_hash)
+        });
+        history.extend(restored);
+        self.trim_to_limits(&mut history);
+
+        self.invalidate_search_cache();
+        drop(history);
+        self.save_history().await?;
+        Ok(true)
+    }
+
+    fn lock_cleared(&self) -> std::sync::MutexGuard<'_, Option<VecDeque<ClipboardItem>>> {
+        self.cleared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// A new item makes the history meaningful again, so a clear can no longer be undone
+    fn discard_cleared(&self) {
+        self.lock_cleared().take();
+    }
+
+    /// Remove the item at `index` and persist. Returns false if there is no such item.
+    pub async fn delete_item(&self, index: usize) -> Result<bool, ClipboardError> {
+        let mut history = self.history.lock().await;
+        if history.remove(index).is_none() {
+            return Ok(false);
+        }
+
+        self.invalidate_search_cache();
+        drop(history);
+        self.save_history().await?;
+        Ok(true)
+    }
+
+    /// Remove the item with exactly this id and persist. Returns false if there is no
+    /// such item.
+    pub async fn delete_item_by_id(&self, id: &str) -> Result<bool, ClipboardError> {
+        let mut history = self.history.lock().await;
+        let Some(index) = history.iter().position(|item| item.id == id) else {
+            return Ok(false);
+        };
+        history.remove(index);
+
+        self.invalidate_search_cache();
+        drop(history);
+        self.save_history().await?;
+        Ok(true)
+    }
+
+    /// Pin or unpin the item at `index`. Returns false if there is no such item.
+    pub async fn set_pinned(&self, index: usize, pinned: bool) -> Result<bool, ClipboardError> {
+        let mut history = self.history.lock().await;
+        let Some(item) = history.get_mut(index) else {
+            return Ok(false);
+        };
+        item.pinned = pinned;
+
+        drop(history);
+        self.save_history().await?;
+        Ok(true)
+    }
+
+    /// Attach a tag to the item at `index`. Returns false if there is no such item,
+    /// the tag is empty or contains whitespace, or the item already has it.
+    pub async fn add_tag(&self, index: usize, tag: &str) -> Result<bool, ClipboardError> {
+        self.add_tag_to(ItemKey::Index(index), tag).await
+    }
+
+    /// Same as `add_tag`, for the item with exactly this id
+    pub async fn add_tag_by_id(&self, id: &str, tag: &str) -> Result<bool, ClipboardError> {
+        self.add_tag_to(ItemKey::Id(id), tag).await
+    }
+
+    async fn add_tag_to(&self, key: ItemKey<'_>, tag: &str) -> Result<bool, ClipboardError> {
+        let Some(tag) = normalize_tag(tag) else {
+            return Ok(false);
+        };
+        let mut history = self.history.lock().await;
+        let Some(item) = key
+            .position(&history)
+            .and_then(|index| history.get_mut(index))
+        else {
+            return Ok(false);
+        };
+        if item.has_tag(&tag) {
+            return Ok(false);
         }
+        item.tags.push(tag);
+
+        drop(history);
+        self.save_history().await?;
+        Ok(true)
+    }
+
+    /// Remove a tag from the item at `index`. Returns false if the item doesn't have it.
+    pub async fn remove_tag(&self, index: usize, tag: &str) -> Result<bool, ClipboardError> {
+        self.remove_tag_from(ItemKey::Index(index), tag).await
+    }
+
+    /// Same as `remove_tag`, for the item with exactly this id
+    pub async fn remove_tag_by_id(&self, id: &str, tag: &str) -> Result<bool, ClipboardError> {
+        self.remove_tag_from(ItemKey::Id(id), tag).await
+    }
+
+    async fn remove_tag_from(&self, key: ItemKey<'_>, tag: &str) -> Result<bool, ClipboardError> {
+        let mut history = self.history.lock().await;
+        let Some(item) = key
+            .position(&history)
+            .and_then(|index| history.get_mut(index))
+        else {
+            return Ok(false);
+        };
+        let before = item.tags.len();
+        let tag = tag.trim().trim_start_matches('#');
+        item.tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+        if item.tags.len() == before {
+            return Ok(false);
+        }
+
+        drop(history);
+        self.save_history().await?;
+        Ok(true)
+    }
+
+    /// Items carrying `tag`, newest first
+    pub async fn search_by_tag(&self, tag: &str) -> Vec<(usize, ClipboardItem)> {
+        let mut history = self.history.lock().await;
+        tag_search_snapshot(history.make_contiguous(), tag)
+            .into_iter()
+            .map(|idx| (idx, history[idx].clone()))
+            .collect()
+    }
+
+    /// Flip the pinned state of the item at `index`, returning the new state
+    /// (`None` if there is no such item)
+    pub async fn toggle_pinned(&self, index: usize) -> Result<Option<bool>, ClipboardError> {
+        self.toggle_pinned_item(ItemKey::Index(index)).await
+    }
+
+    /// Same as `toggle_pinned`, for the item with exactly this id
+    pub async fn toggle_pinned_by_id(&self, id: &str) -> Result<Option<bool>, ClipboardError> {
+        self.toggle_pinned_item(ItemKey::Id(id)).await
+    }
+
+    async fn toggle_pinned_item(&self, key: ItemKey<'_>) -> Result<Option<bool>, ClipboardError> {
+        let mut history = self.history.lock().await;
+        let Some(item) = key
+            .position(&history)
+            .and_then(|index| history.get_mut(index))
+        else {
+            return Ok(None);
+        };
+        item.pinned = !item.pinned;
+        let pinned = item.pinned;
 
+        drop(history);
+        self.save_history().await?;
+        Ok(Some(pinned))
+    }
+
+    /// Move the item at `index` to the front of the history and mark it as just copied,
+    /// without writing to the system clipboard. Returns false if there is no such item.
+    pub async fn promote_to_front(&self, index: usize) -> Result<bool, ClipboardError> {
+        self.promote_item(ItemKey::Index(index)).await
+    }
+
+    /// Same as `promote_to_front`, for the item with exactly this id
+    pub async fn promote_to_front_by_id(&self, id: &str) -> Result<bool, ClipboardError> {
+        self.promote_item(ItemKey::Id(id)).await
+    }
+
+    async fn promote_item(&self, key: ItemKey<'_>) -> Result<bool, ClipboardError> {
+        let mut history = self.history.lock().await;
+        let Some(mut item) = key
+            .position(&history)
+            .and_then(|index| history.remove(index))
+        else {
+            return Ok(false);
+        };
+        item.timestamp = Utc::now();
+        history.push_front(item);
+        self.invalidate_search_cache();
+
+        drop(history);
+        self.save_history().await?;
+        Ok(true)
+    }
+
+    /// Attach text recognized in an image item so it can be found by search
+    pub async fn set_ocr_text(&self, id: &str, text: String) -> Result<(), ClipboardError> {
+        let mut history = self.history.lock().await;
+        let item = history
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| ClipboardError::NotFound(format!("id {id}")))?;
+        item.ocr_text = Some(text);
+
+        if let Ok(mut index) = self.search_index.lock() {
+            index.invalidate(id);
+        }
+        self.invalidate_search_cache();
         drop(history);
         self.save_history().await
     }
 
+    /// Merge externally sourced items into the history, skipping any whose content
+    /// already exists (either in the history or earlier in the same batch) under the
+    /// dedup policy. Each is placed by its timestamp; existing items keep their order.
+    /// Returns the number of items actually added.
+    pub async fn import_items(&self, items: Vec<ClipboardItem>) -> Result<usize, ClipboardError> {
+        self.wait_until_loaded().await;
+        let mut history = self.history.lock().await;
+        let mut known: HashSet<String> = history
+            .iter()
+            .map(|item| item.content_hash.clone())
+            .collect();
+
+        let mut imported = 0;
+        for item in items {
+            let Ok(mut item) = self.fit_content_size(item) else {
+                continue;
+            };
+            // Compared like a copy of the same content would be
+            item.content_hash = self.policy_hash(&item.content);
+            if !known.insert(item.content_hash.clone()) {
+                continue;
+            }
+            // Imported entries carry their original timestamps
+            Self::insert_by_timestamp(&mut history, item);
+            imported += 1;
+        }
+
+        if imported == 0 {
+            return Ok(0);
+        }
+        self.discard_cleared();
+
+        self.trim_to_limits(&mut history);
+
+        self.invalidate_search_cache();
+        drop(history);
+        self.save_history().await?;
+        Ok(imported)
+    }
+
+    /// Drop unpinned items copied longer than `max_age` ago and persist.
+    /// Returns the number of items removed.
+    pub async fn prune_expired(&self) -> Result<usize, ClipboardError> {
+        let Some(max_age) = self.max_age else {
+            return Ok(0);
+        };
+        let cutoff = Utc::now() - max_age;
+
+        let mut history = self.history.lock().await;
+        let before = history.len();
+        history.retain(|item| item.pinned || item.timestamp >= cutoff);
+        let removed = before - history.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        self.invalidate_search_cache();
+        drop(history);
+        self.save_history().await?;
+        Ok(removed)
+    }
+
+    /// Whether another instance saved the history file since this manager last loaded
+    /// or saved it
+    pub fn history_changed_externally(&self) -> bool {
+        self.storage.changed_externally()
+    }
+
+    /// Merge the changes another instance saved to the same history file into this one.
+    /// Its new items are added, unless the same content is already here, and items it
+    /// removed are removed here too. Items removed here since the last load or save stay
+    /// removed, and items present in both are kept as they are here.
+    /// Returns the number of items added.
+    pub async fn reload(&self) -> Result<usize, ClipboardError> {
+        // The file is still being read into this history anyway
+        if self.is_loading() {
+            return Ok(0);
+        }
+        let external = self.storage.load_history().await?;
+        let external_ids: HashSet<String> = external.iter().map(|item| item.id.clone()).collect();
+        let mut history = self.history.lock().await;
+        let mut synced_ids = self.lock_synced_ids();
+
+        // Saved before but gone from the file: the other instance removed it
+        let before = history.len();
+        history.retain(|item| !synced_ids.contains(&item.id) || external_ids.contains(&item.id));
+        let removed = before - history.len();
+
+        let mut known: HashSet<String> = history
+            .iter()
+            .flat_map(|item| [item.id.clone(), item.content_hash.clone()])
+            .collect();
+        let mut added = 0;
+        for item in external {
+            // Saved before but gone from here: removed here, the file is just behind
+            if known.contains(&item.id)
+                || synced_ids.contains(&item.id)
+                || !known.insert(item.content_hash.clone())
+            {
+                continue;
+            }
+            known.insert(item.id.clone());
+            Self::insert_by_timestamp(&mut history, item);
+            added += 1;
+        }
+        *synced_ids = external_ids;
+
+        if added > 0 {
+            self.trim_to_limits(&mut history);
+        }
+        if added > 0 || removed > 0 {
+            self.invalidate_search_cache();
+        }
+        Ok(added)
+    }
+
+    fn lock_synced_ids(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.synced_ids
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Newest first: slot `item` in after the last newer item, without reordering the rest
+    /// (items moved to the top keep their place above it)
+    fn insert_by_timestamp(history: &mut VecDeque<ClipboardItem>, item: ClipboardItem) {
+        let position = history
+            .iter()
+            .rposition(|existing| existing.timestamp >= item.timestamp)
+            .map_or(0, |newer| newer + 1);
+        history.insert(position, item);
+    }
+
     pub async fn get_history(&self) -> Vec<ClipboardItem> {
         let history = self.history.lock().await;
         history.iter().cloned().collect()
     }
 
-    pub async fn search_history(&self, query: &str) -> Vec<(usize, ClipboardItem)> {
+    /// Up to `limit` items starting at `offset`, newest first; only those are copied
+    pub async fn get_history_page(&self, offset: usize, limit: usize) -> Vec<ClipboardItem> {
         let history = self.history.lock().await;
+        history.iter().skip(offset).take(limit).cloned().collect()
+    }
 
-        // Search across different content types using display_content (without type prefix)
-        let matches: Vec<(usize, ClipboardItem)> = history
+    /// The plain text items, newest first, without copying images or other content
+    pub async fn get_text_items(&self) -> Vec<ClipboardItem> {
+        let history = self.history.lock().await;
+        history
             .iter()
-            .enumerate()
-            .filter(|(_, item)| {
-                let content = item.display_content();
-                content.to_lowercase().contains(&query.to_lowercase())
+            .filter(|item| matches!(item.content, ClipboardContentType::Text(_)))
+            .cloned()
+            .collect()
+    }
+
+    /// Summaries of up to `limit` items starting at `offset`, newest first, without
+    /// copying the items' content
+    pub async fn get_history_previews(&self, offset: usize, limit: usize) -> Vec<ItemSummary> {
+        let history = self.history.lock().await;
+        history
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(ItemSummary::from)
+            .collect()
+    }
+
+    /// Number of items in the history, without copying them
+    pub async fn get_history_count(&self) -> usize {
+        self.history.lock().await.len()
+    }
+
+    pub async fn get_usage_stats(&self) -> UsageStats {
+        let history = self.history.lock().await;
+        let sizes = history.iter().map(ClipboardItem::get_size_bytes);
+        UsageStats {
+            item_count: history.len(),
+            pinned_count: history.iter().filter(|item| item.pinned).count(),
+            total_bytes: sizes.clone().sum(),
+            largest_item_bytes: sizes.max().unwrap_or(0),
+        }
+    }
+
+    pub fn get_content_limits(&self) -> ContentLimits {
+        ContentLimits {
+            max_history_size: self.max_history_size(),
+            max_content_size: MAX_CONTENT_SIZE,
+        }
+    }
+
+    pub async fn search_history(&self, query: &str) -> Vec<(usize, ClipboardItem)> {
+        let mut history = self.history.lock().await;
+        // Exact search is case-insensitive, so lowercase queries share a cache entry
+        let query = query.to_lowercase();
+        let key = (SearchKind::Exact, query.clone());
+
+        if let Some(cached) = self.cached_results(&history, &key) {
+            return cached
+                .into_iter()
+                .map(|(idx, _)| (idx, history[idx].clone()))
+                .collect();
+        }
+
+        let matches: Vec<(usize, ClipboardItem)> = self
+            .indexed_search(history.make_contiguous(), |index, items| {
+                index.search(items, &query)
             })
-            .map(|(idx, item)| (idx, item.clone()))
+            .into_iter()
+            .map(|idx| (idx, history[idx].clone()))
             .collect();
 
+        self.store_results(
+            key,
+            matches
+                .iter()
+                .map(|(_, item)| (item.id.clone(), None))
+                .collect(),
+        );
         matches
     }
 
-    pub async fn fuzzy_search_history(&self, query: &str) -> Vec<(usize, ClipboardItem, i64)> {
+    /// Exact search limited to items of one content type.
+    /// An empty query lists every item of that type.
+    pub async fn search_history_filtered(
+        &self,
+        query: &str,
+        filter: ContentTypeFilter,
+    ) -> Vec<(usize, ClipboardItem)> {
+        self.search_history(query)
+            .await
+            .into_iter()
+            .filter(|(_, item)| filter.matches(item))
+            .collect()
+    }
+
+    /// Search the items copied in `[from, to)`. The range is applied first, then the
+    /// query: fuzzy matches best first, falling back to substring matches. An empty
+    /// query returns every item in the range, newest first.
+    pub async fn search_in_range(
+        &self,
+        query: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<(usize, ClipboardItem)> {
         let history = self.history.lock().await;
-        let matcher = SkimMatcherV2::default();
+        let (indices, in_range): (Vec<usize>, Vec<ClipboardItem>) = history
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| from <= item.timestamp && item.timestamp < to)
+            .map(|(idx, item)| (idx, item.clone()))
+            .unzip();
+
+        let matches: Vec<usize> = if query.is_empty() {
+            (0..in_range.len()).collect()
+        } else {
+            let fuzzy = fuzzy_search_snapshot(&in_range, query);
+            if fuzzy.is_empty() {
+                search_snapshot(&in_range, query)
+            } else {
+                fuzzy.into_iter().map(|(idx, _)| idx).collect()
+            }
+        };
+
+        matches
+            .into_iter()
+            .map(|idx| (indices[idx], in_range[idx].clone()))
+            .collect()
+    }
+
+    /// Search history items matching a regular expression.
+    /// Returns the compile error if `pattern` is not a valid regex.
+    pub async fn regex_search_history(
+        &self,
+        pattern: &str,
+    ) -> Result<Vec<(usize, ClipboardItem)>, regex::Error> {
+        let pattern = Regex::new(pattern)?;
+        let mut history = self.history.lock().await;
+
+        Ok(regex_search_snapshot(history.make_contiguous(), &pattern)
+            .into_iter()
+            .map(|idx| (idx, history[idx].clone()))
+            .collect())
+    }
+
+    pub async fn fuzzy_search_history(&self, query: &str) -> Vec<(usize, ClipboardItem, i64)> {
+        self.fuzzy_search_history_with(query, None, None).await
+    }
+
+    /// Fuzzy search keeping only matches scoring at least `min_score`, and at most
+    /// `limit` of them (best first). Items are only cloned for the matches returned.
+    pub async fn fuzzy_search_history_with(
+        &self,
+        query: &str,
+        min_score: Option<i64>,
+        limit: Option<usize>,
+    ) -> Vec<(usize, ClipboardItem, i64)> {
+        let mut history = self.history.lock().await;
+        // The skim matcher is smart-case, so the query is used verbatim as the cache key
+        let key = (SearchKind::Fuzzy, query.to_string());
+        let wanted = |score: &i64| min_score.is_none_or(|min| *score >= min);
+        let limit = limit.unwrap_or(usize::MAX);
+
+        if let Some(cached) = self.cached_results(&history, &key) {
+            return cached
+                .into_iter()
+                .map(|(idx, score)| (idx, score.unwrap_or_default()))
+                .filter(|(_, score)| wanted(score))
+                .take(limit)
+                .map(|(idx, score)| (idx, history[idx].clone(), score))
+                .collect();
+        }
+
+        // The cache keeps every match, so other thresholds and limits can reuse it
+        let fuzzy_matches = self.indexed_search(history.make_contiguous(), |index, items| {
+            index.fuzzy_search(items, query)
+        });
+        self.store_results(
+            key,
+            fuzzy_matches
+                .iter()
+                .map(|&(idx, score)| (history[idx].id.clone(), Some(score)))
+                .collect(),
+        );
+
+        fuzzy_matches
+            .into_iter()
+            .filter(|(_, score)| wanted(score))
+            .take(limit)
+            .map(|(idx, score)| (idx, history[idx].clone(), score))
+            .collect()
+    }
 
-        let mut fuzzy_matches: Vec<(usize, ClipboardItem, i64)> = history
+    /// Drop the oldest items until the history fits this manager's limits.
+    /// Returns whether anything was removed.
+    fn trim_to_limits(&self, history: &mut VecDeque<ClipboardItem>) -> bool {
+        Self::enforce_limits(history, self.max_history_size(), self.max_history_bytes)
+    }
+
+    /// Drop the oldest items until the history fits the size limit and, if set, the
+    /// byte cap. Returns whether anything was removed.
+    fn enforce_limits(
+        history: &mut VecDeque<ClipboardItem>,
+        max_history_size: usize,
+        max_history_bytes: Option<usize>,
+    ) -> bool {
+        let mut excess = history.len().saturating_sub(max_history_size);
+        let mut total_bytes: usize = match max_history_bytes {
+            Some(_) => history.iter().map(ClipboardItem::get_size_bytes).sum(),
+            None => 0,
+        };
+        let before = history.len();
+
+        // Evict the oldest unpinned items first; pinned items are never trimmed, and the
+        // newest item is kept even if it alone is over the byte cap
+        let mut index = history.len();
+        while index > 0 {
+            index -= 1;
+            let over_bytes = index > 0 && max_history_bytes.is_some_and(|max| total_bytes > max);
+            if excess == 0 && !over_bytes {
+                break;
+            }
+            if !history[index].pinned {
+                if let Some(removed) = history.remove(index) {
+                    total_bytes = total_bytes.saturating_sub(removed.get_size_bytes());
+                }
+                excess = excess.saturating_sub(1);
+            }
+        }
+        history.len() != before
+    }
+
+    /// A zero limit would silently wipe the history on the next copy, so reject it
+    fn validate_max_history_size(max_history_size: usize) -> Result<usize, ClipboardError> {
+        if max_history_size == 0 {
+            return Err(ClipboardError::Config(
+                "max_history_size must be at least 1".to_string(),
+            ));
+        }
+        Ok(max_history_size)
+    }
+
+    /// Resolve a cached query against the current history.
+    /// Must be called while holding the history lock so indices stay valid.
+    fn cached_results(
+        &self,
+        history: &VecDeque<ClipboardItem>,
+        key: &SearchKey,
+    ) -> Option<Vec<(usize, Option<i64>)>> {
+        let cached = self.search_cache.lock().ok()?.get(key)?;
+        let positions: HashMap<&str, usize> = history
             .iter()
             .enumerate()
-            .filter_map(|(idx, item)| {
-                let content = item.display_content();
-                matcher
-                    .fuzzy_match(&content, query)
-                    .map(|score| (idx, item.clone(), score))
-            })
+            .map(|(idx, item)| (item.id.as_str(), idx))
             .collect();
 
-        // Sort by fuzzy match score (higher is better)
-        fuzzy_matches.sort_by(|a, b| b.2.cmp(&a.2));
-        fuzzy_matches
+        cached
+            .into_iter()
+            .map(|(id, score)| {
+                let idx = *positions.get(id.as_str())?;
+                Some((idx, score))
+            })
+            .collect()
+    }
+
+    fn store_results(&self, key: SearchKey, ids: CachedMatches) {
+        if let Ok(mut cache) = self.search_cache.lock() {
+            cache.insert(key, ids);
+        }
+    }
+
+    /// Run a search against the search index, falling back to an unindexed pass over
+    /// the items if the index lock is poisoned
+    fn indexed_search<T>(
+        &self,
+        items: &[ClipboardItem],
+        search: impl FnOnce(&mut SearchIndex, &[ClipboardItem]) -> Vec<T>,
+    ) -> Vec<T> {
+        match self.search_index.lock() {
+            Ok(mut index) => search(&mut index, items),
+            Err(_) => search(&mut SearchIndex::default(), items),
+        }
+    }
+
+    /// Drop all cached search results; called on every history mutation
+    fn invalidate_search_cache(&self) {
+        if let Ok(mut cache) = self.search_cache.lock() {
+            cache.clear();
+        }
     }
 
-    pub async fn copy_item_to_clipboard(&self, index: usize) -> io::Result<bool> {
+    /// Copy the item at `index` back to the clipboard.
+    /// Fails with `NotFound` if there is no such item.
+    pub async fn copy_item_to_clipboard(&self, index: usize) -> Result<(), ClipboardError> {
         let history = self.history.lock().await;
-        if let Some(item) = history.get(index) {
-            let item_clone = item.clone();
-            drop(history);
+        let item = history
+            .get(index)
+            .cloned()
+            .ok_or_else(|| ClipboardError::NotFound(format!("index {index}")))?;
+        drop(history);
+        self.copy_back(index, item).await
+    }
 
-            // Use blocking task for clipboard operation
-            let result = tokio::task::spawn_blocking(move || {
-                let mut clipboard =
-                    arboard::Clipboard::new().map_err(|_| "Failed to access clipboard")?;
+    /// Copy an item back to the clipboard as plain text only, dropping HTML/RTF
+    /// formatting. Images are copied as a short textual description.
+    pub async fn copy_item_to_clipboard_as_text(&self, index: usize) -> Result<(), ClipboardError> {
+        let history = self.history.lock().await;
+        let (id, text) = history
+            .get(index)
+            .map(|item| (item.id.clone(), item.plain_text()))
+            .ok_or_else(|| ClipboardError::NotFound(format!("index {index}")))?;
+        drop(history);
+        self.copy_plain_text(index, id, text).await
+    }
 
-                match &item_clone.content {
-                    ClipboardContentType::Text(text) => {
-                        clipboard
-                            .set_text(text.clone())
-                            .map_err(|_| "Failed to set clipboard text")?;
-                    }
-                    ClipboardContentType::Image { data, width, height, .. } => {
-                        // Decode base64 PNG data and convert back to RGBA for clipboard
-                        if let Ok(png_data) = BASE64_STANDARD.decode(data) {
-                            // Validate that we have valid dimensions
-                            if *width > 0 && *height > 0 {
-                                // Convert PNG back to RGBA format for arboard
-                                match ClipboardMonitor::png_to_rgba(&png_data) {
-                                    Ok(rgba_data) => {
-                                        let img = arboard::ImageData {
-                                            width: *width as usize,
-                                            height: *height as usize,
-                                            bytes: std::borrow::Cow::Owned(rgba_data),
-                                        };
-                                        clipboard
-                                            .set_image(img)
-                                            .map_err(|e| format!("Failed to set clipboard image: {e}"))?;
-                                    }
-                                    Err(e) => {
-                                        return Err(format!("Failed to decode image data: {e}"));
-                                    }
+    /// Same as `copy_item_to_clipboard_as_text`, for the item with exactly this id
+    pub async fn copy_item_as_text_by_id(&self, id: &str) -> Result<(), ClipboardError> {
+        let history = self.history.lock().await;
+        let (index, text) = history
+            .iter()
+            .enumerate()
+            .find(|(_, item)| item.id == id)
+            .map(|(index, item)| (index, item.plain_text()))
+            .ok_or_else(|| ClipboardError::NotFound(format!("id {id}")))?;
+        drop(history);
+        self.copy_plain_text(index, id.to_string(), text).await
+    }
+
+    /// Write the plain text of the item at `index` to the clipboard, telling subscribers
+    /// about it like `copy_back` does
+    async fn copy_plain_text(
+        &self,
+        index: usize,
+        id: String,
+        text: String,
+    ) -> Result<(), ClipboardError> {
+        self.write_own_text(text).await?;
+        self.record_use(&id).await?;
+        let _ = self
+            .event_sender
+            .send(ClipboardEvent::ItemCopied { index, id });
+        Ok(())
+    }
+
+    /// Write text of our own making to the clipboard, marked so the monitor doesn't
+    /// record it as a new copy
+    async fn write_own_text(&self, text: String) -> Result<(), ClipboardError> {
+        self.set_own_write(Some(
+            self.policy_hash(&ClipboardContentType::Text(text.clone())),
+        ));
+        let outcome = self
+            .run_clipboard_write(move |clipboard| clipboard.set_text(text))
+            .await;
+        if outcome.is_err() {
+            self.set_own_write(None);
+        }
+        outcome
+    }
+
+    /// Copy the item with exactly this id back to the clipboard.
+    /// Unlike positional indices, ids stay valid while new items arrive.
+    pub async fn copy_item_by_id(&self, id: &str) -> Result<(), ClipboardError> {
+        let history = self.history.lock().await;
+        let (index, item) = history
+            .iter()
+            .enumerate()
+            .find(|(_, item)| item.id == id)
+            .map(|(index, item)| (index, item.clone()))
+            .ok_or_else(|| ClipboardError::NotFound(format!("id {id}")))?;
+        drop(history);
+        self.copy_back(index, item).await
+    }
+
+    /// Copy the item at `index` back to the clipboard, then remove it from the history.
+    /// The item is only removed once the copy succeeded.
+    pub async fn copy_and_remove(&self, index: usize) -> Result<(), ClipboardError> {
+        let id = self
+            .history
+            .lock()
+            .await
+            .get(index)
+            .map(|item| item.id.clone())
+            .ok_or_else(|| ClipboardError::NotFound(format!("index {index}")))?;
+        self.copy_and_remove_by_id(&id).await
+    }
+
+    /// Same as `copy_and_remove`, for the item with exactly this id
+    pub async fn copy_and_remove_by_id(&self, id: &str) -> Result<(), ClipboardError> {
+        self.copy_item_by_id(id).await?;
+        // By id, since items captured in the meantime shift the indices
+        self.delete_item_by_id(id).await?;
+        Ok(())
+    }
+
+    /// Write a history item to the clipboard, telling subscribers about it and marking it
+    /// so the monitor doesn't record it again as a new copy
+    async fn copy_back(&self, index: usize, item: ClipboardItem) -> Result<(), ClipboardError> {
+        let id = item.id.clone();
+        // Marked before writing, as the monitor may poll before the write returns
+        self.set_own_write(Some(self.read_back_hash(&item)));
+        let copied = self.write_item(item).await;
+
+        if copied.is_err() {
+            self.set_own_write(None);
+            return copied;
+        }
+        self.record_use(&id).await?;
+        let _ = self
+            .event_sender
+            .send(ClipboardEvent::ItemCopied { index, id });
+        Ok(())
+    }
+
+    /// Count a copy of the item with this id towards its search ranking
+    async fn record_use(&self, id: &str) -> Result<(), ClipboardError> {
+        let mut history = self.history.lock().await;
+        let Some(item) = history.iter_mut().find(|item| item.id == id) else {
+            return Ok(());
+        };
+        item.use_count = item.use_count.saturating_add(1);
+        // Cached fuzzy scores include the usage bonus
+        self.invalidate_search_cache();
+        drop(history);
+        self.save_history().await
+    }
+
+    fn set_own_write(&self, content_hash: Option<String>) {
+        if let Ok(mut own) = self.own_write.lock() {
+            *own = content_hash;
+        }
+    }
+
+    /// Content hash the monitor will compute when it reads `item` back from the clipboard.
+    /// Images are read back as a PNG of the stored pixels, whose size can differ from the
+    /// recorded original size when the image was downscaled.
+    fn read_back_hash(&self, item: &ClipboardItem) -> String {
+        if let ClipboardContentType::Image {
+            data,
+            format: ImageFormat::Png,
+            ..
+        } = &item.content
+        {
+            let png_size = BASE64_STANDARD.decode(data).ok().and_then(|png| {
+                image::ImageReader::new(std::io::Cursor::new(png))
+                    .with_guessed_format()
+                    .ok()?
+                    .into_dimensions()
+                    .ok()
+            });
+            if let Some((width, height)) = png_size {
+                return self.policy_hash(&ClipboardContentType::Image {
+                    data: data.clone(),
+                    format: ImageFormat::Png,
+                    width,
+                    height,
+                    thumbnail: None,
+                });
+            }
+        }
+        // Recomputed, as items saved under another policy carry that policy's hash
+        self.policy_hash(&item.content)
+    }
+
+    /// Find the single item whose id starts with `prefix`, returning its current index.
+    /// Fails with `AmbiguousId` if several items match.
+    pub async fn find_by_id_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<(usize, ClipboardItem), ClipboardError> {
+        let history = self.history.lock().await;
+        let mut matches = history
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.id.starts_with(prefix));
+
+        match (matches.next(), matches.count()) {
+            (Some((index, item)), 0) => Ok((index, item.clone())),
+            (Some(_), others) => Err(ClipboardError::AmbiguousId {
+                prefix: prefix.to_string(),
+                matches: others + 1,
+            }),
+            (None, _) => Err(ClipboardError::NotFound(format!("id prefix {prefix}"))),
+        }
+    }
+
+    async fn write_item(&self, item_clone: ClipboardItem) -> Result<(), ClipboardError> {
+        // Use blocking task for clipboard operation
+        self.run_clipboard_write(move |clipboard| {
+            match &item_clone.content {
+                ClipboardContentType::Text(text) => {
+                    clipboard.set_text(text.clone())?;
+                }
+                ClipboardContentType::Image {
+                    data,
+                    width,
+                    height,
+                    ..
+                } => {
+                    // Decode base64 PNG data and convert back to RGBA for clipboard
+                    if let Ok(png_data) = BASE64_STANDARD.decode(data) {
+                        // Validate that we have valid dimensions
+                        if *width > 0 && *height > 0 {
+                            // Convert PNG back to RGBA format for arboard. The stored PNG may
+                            // be downscaled, so its own size is used rather than width/height.
+                            match ClipboardMonitor::png_to_rgba_with_size(&png_data) {
+                                Ok((rgba_data, png_width, png_height)) => {
+                                    let img = arboard::ImageData {
+                                        width: png_width as usize,
+                                        height: png_height as usize,
+                                        bytes: std::borrow::Cow::Owned(rgba_data),
+                                    };
+                                    clipboard.set_image(img)?;
+                                }
+                                Err(e) => {
+                                    return Err(ClipboardError::InvalidImageData(e.to_string()));
                                 }
-                            } else {
-                                return Err("Invalid image dimensions: width and height must be greater than 0".to_string());
                             }
                         } else {
-                            return Err("Invalid base64 image data".to_string());
+                            return Err(ClipboardError::InvalidImageData(
+                                "width and height must be greater than 0".to_string(),
+                            ));
                         }
+                    } else {
+                        return Err(ClipboardError::InvalidImageData(
+                            "invalid base64".to_string(),
+                        ));
                     }
-                    ClipboardContentType::Html { html, plain_text } => {
-                        // Try HTML first, fallback to plain text
+                }
+                ClipboardContentType::Html { html, plain_text } => {
+                    // Try HTML first, fallback to plain text
+                    if let Some(plain) = plain_text {
+                        if clipboard.set_html(html, Some(plain)).is_err() {
+                            clipboard.set_text(plain.clone())?;
+                        }
+                    } else {
+                        clipboard.set_text(html.clone())?;
+                    }
+                }
+                ClipboardContentType::Rtf { rtf, plain_text } => {
+                    // Fall back to plain text where RTF can't be written
+                    if clipboard.set_rtf(rtf, plain_text.as_deref()).is_err() {
                         if let Some(plain) = plain_text {
-                            if clipboard.set_html(html, Some(plain)).is_err() {
-                                clipboard
-                                    .set_text(plain.clone())
-                                    .map_err(|_| "Failed to set clipboard text")?;
-                            }
+                            clipboard.set_text(plain.clone())?;
                         } else {
-                            clipboard
-                                .set_text(html.clone())
-                                .map_err(|_| "Failed to set clipboard text")?;
+                            return Err(ClipboardError::ClipboardUnavailable(
+                                "Failed to set clipboard RTF".to_string(),
+                            ));
                         }
                     }
-                    ClipboardContentType::Files(paths) => {
-                        // Convert string paths to PathBuf
-                        let _path_bufs: Vec<std::path::PathBuf> =
-                            paths.iter().map(std::path::PathBuf::from).collect();
-                        clipboard
-                            .set_text(paths.join("\n"))
-                            .map_err(|_| "Failed to set file paths as text")?;
+                }
+                ClipboardContentType::Files(paths) => {
+                    // Paste as real files where the platform supports it, as a list of
+                    // paths otherwise
+                    let path_bufs: Vec<std::path::PathBuf> =
+                        paths.iter().map(std::path::PathBuf::from).collect();
+                    if clipboard.set_files(&path_bufs).is_err() {
+                        clipboard.set_text(paths.join("\n"))?;
                     }
-                    ClipboardContentType::Other { data, .. } => {
-                        // For other types, try to decode as text or set as base64
-                        if let Ok(decoded) = BASE64_STANDARD.decode(data) {
-                            if let Ok(text) = String::from_utf8(decoded) {
-                                clipboard
-                                    .set_text(text)
-                                    .map_err(|_| "Failed to set clipboard text")?;
-                            } else {
-                                clipboard
-                                    .set_text(data.clone())
-                                    .map_err(|_| "Failed to set clipboard text")?;
-                            }
+                }
+                ClipboardContentType::Secret { data, .. } => clipboard.set_text(data.clone())?,
+                ClipboardContentType::Other { data, .. } => {
+                    // For other types, try to decode as text or set as base64
+                    if let Ok(decoded) = BASE64_STANDARD.decode(data) {
+                        if let Ok(text) = String::from_utf8(decoded) {
+                            clipboard.set_text(text)?;
                         } else {
-                            clipboard
-                                .set_text(data.clone())
-                                .map_err(|_| "Failed to set clipboard text")?;
+                            clipboard.set_text(data.clone())?;
                         }
+                    } else {
+                        clipboard.set_text(data.clone())?;
                     }
                 }
-                Ok(())
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Join the text of the given items (in the given order) with `separator`.
+    /// Non-text items and out-of-range indices are skipped; `None` if nothing is left.
+    pub async fn combine_items(&self, indices: &[usize], separator: &str) -> Option<String> {
+        let keys: Vec<ItemKey> = indices.iter().map(|&index| ItemKey::Index(index)).collect();
+        self.combine_item_keys(&keys, separator).await
+    }
+
+    async fn combine_item_keys(&self, keys: &[ItemKey<'_>], separator: &str) -> Option<String> {
+        let history = self.history.lock().await;
+        let parts: Vec<&str> = keys
+            .iter()
+            .filter_map(|key| history.get(key.position(&history)?))
+            .filter_map(|item| match &item.content {
+                ClipboardContentType::Text(text) => Some(text.as_str()),
+                ClipboardContentType::Html { html, plain_text } => {
+                    Some(plain_text.as_deref().unwrap_or(html))
+                }
+                ClipboardContentType::Rtf { plain_text, .. } => plain_text.as_deref(),
+                _ => None,
             })
-            .await;
+            .collect();
 
-            match result {
-                Ok(Ok(())) => Ok(true),
-                Ok(Err(_)) => Ok(false),
-                Err(_) => Ok(false),
-            }
+        if parts.is_empty() {
+            None
         } else {
-            Ok(false)
+            Some(parts.join(separator))
+        }
+    }
+
+    /// Copy the text of several items, joined with `separator`, to the clipboard.
+    /// Fails with `NotFound` if none of them has any text.
+    pub async fn copy_combined(
+        &self,
+        indices: &[usize],
+        separator: &str,
+    ) -> Result<(), ClipboardError> {
+        let keys: Vec<ItemKey> = indices.iter().map(|&index| ItemKey::Index(index)).collect();
+        self.copy_combined_items(&keys, separator).await
+    }
+
+    /// Same as `copy_combined`, for the items with these ids
+    pub async fn copy_combined_by_id(
+        &self,
+        ids: &[String],
+        separator: &str,
+    ) -> Result<(), ClipboardError> {
+        let keys: Vec<ItemKey> = ids.iter().map(|id| ItemKey::Id(id)).collect();
+        self.copy_combined_items(&keys, separator).await
+    }
+
+    async fn copy_combined_items(
+        &self,
+        keys: &[ItemKey<'_>],
+        separator: &str,
+    ) -> Result<(), ClipboardError> {
+        let combined = self
+            .combine_item_keys(keys, separator)
+            .await
+            .ok_or_else(|| ClipboardError::NotFound("text items to combine".to_string()))?;
+
+        self.write_own_text(combined).await
+    }
+
+    /// Copy a text summary of the item's metadata (not its content) to the clipboard
+    pub async fn copy_item_metadata(&self, index: usize) -> Result<(), ClipboardError> {
+        self.copy_metadata_of(ItemKey::Index(index)).await
+    }
+
+    /// Same as `copy_item_metadata`, for the item with exactly this id
+    pub async fn copy_item_metadata_by_id(&self, id: &str) -> Result<(), ClipboardError> {
+        self.copy_metadata_of(ItemKey::Id(id)).await
+    }
+
+    async fn copy_metadata_of(&self, key: ItemKey<'_>) -> Result<(), ClipboardError> {
+        let history = self.history.lock().await;
+        let summary = key
+            .position(&history)
+            .map(|index| history[index].metadata_summary())
+            .ok_or_else(|| ClipboardError::NotFound(key.to_string()))?;
+        drop(history);
+
+        self.write_own_text(summary).await
+    }
+
+    /// Open the item at `index` in the default browser if it is a link.
+    /// Returns false if there is no such item or it isn't a URL.
+    pub async fn open_item_url(&self, index: usize) -> Result<bool, ClipboardError> {
+        self.open_url_of(ItemKey::Index(index)).await
+    }
+
+    /// Same as `open_item_url`, for the item with exactly this id
+    pub async fn open_item_url_by_id(&self, id: &str) -> Result<bool, ClipboardError> {
+        self.open_url_of(ItemKey::Id(id)).await
+    }
+
+    async fn open_url_of(&self, key: ItemKey<'_>) -> Result<bool, ClipboardError> {
+        let history = self.history.lock().await;
+        let Some(url) = key
+            .position(&history)
+            .and_then(|index| history[index].as_url())
+        else {
+            return Ok(false);
+        };
+        drop(history);
+
+        // Launching the browser can block (e.g. waiting for xdg-open), so keep it off
+        // the async workers
+        tokio::task::spawn_blocking(move || webbrowser::open(url.as_str()))
+            .await
+            .map_err(|e| ClipboardError::Io(std::io::Error::other(e)))??;
+        Ok(true)
+    }
+
+    /// Run a clipboard write on the blocking pool.
+    /// Clipboard backends can panic on malformed data on some platforms; such a panic is
+    /// reported as `ClipboardUnavailable` so the UI can say why the copy failed instead of
+    /// failing silently.
+    async fn run_clipboard_write<F>(&self, write: F) -> Result<(), ClipboardError>
+    where
+        F: FnOnce(&dyn SystemClipboard) -> Result<(), ClipboardError> + Send + 'static,
+    {
+        let clipboard = Arc::clone(&self.clipboard);
+        let result = tokio::task::spawn_blocking(move || {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| write(clipboard.as_ref())))
+        })
+        .await;
+
+        match result {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(panic)) => {
+                let reason = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                Err(ClipboardError::ClipboardUnavailable(format!(
+                    "This item couldn't be copied (internal error: {reason})"
+                )))
+            }
+            Err(e) => Err(ClipboardError::ClipboardUnavailable(format!(
+                "Clipboard task failed: {e}"
+            ))),
         }
     }
 
-    async fn save_history(&self) -> io::Result<()> {
+    async fn save_history(&self) -> Result<(), ClipboardError> {
+        if self.hold_back_save() {
+            return Ok(());
+        }
+        // Merge rather than overwrite what another instance saved in the meantime
+        if self.storage.changed_externally() {
+            self.reload().await?;
+        }
+        let history = self.history.lock().await;
+        self.thumbnails.retain_history(&history);
+        self.storage.save_history(&history).await?;
+        *self.lock_synced_ids() = history.iter().map(|item| item.id.clone()).collect();
+        Ok(())
+    }
+
+    /// Whether saving has to wait for, or was given up with, a history loading in the
+    /// background. A save held back while loading is made once loading is done.
+    fn hold_back_save(&self) -> bool {
+        let mut held_back = false;
+        self.load_state.send_if_modified(|state| {
+            held_back = *state != LoadState::Loaded;
+            if let LoadState::Loading { save_deferred } = state {
+                *save_deferred = true;
+            }
+            false
+        });
+        held_back
+    }
+
+    /// Persist anything the storage has only logged so far (call on shutdown)
+    pub async fn flush(&self) -> Result<(), ClipboardError> {
+        self.wait_until_loaded().await;
+        if self.hold_back_save() {
+            return Ok(());
+        }
         let history = self.history.lock().await;
-        self.storage.save_history(&history).await
+        self.storage.flush(&history).await
     }
 }
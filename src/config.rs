@@ -0,0 +1,245 @@
+//! User configuration, persisted as TOML in `dirs::config_dir()/clipboard-history/config.toml`.
+
+use crate::clipboard_item::{DedupPolicy, GroupMode};
+use crate::clipboard_manager::{DEFAULT_MAX_HISTORY_SIZE, DEFAULT_MAX_STORED_IMAGE_DIMENSION};
+use crate::error::ClipboardError;
+use crate::monitor::{CaptureFormat, DEFAULT_POLL_INTERVAL};
+use crate::secrets::{SecretDetector, SecretPattern};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default for `PopupConfig::max_results`
+pub const DEFAULT_MAX_RESULTS: usize = 50;
+/// Default for `PopupConfig::console_max_results`
+pub const DEFAULT_CONSOLE_MAX_RESULTS: usize = 20;
+/// Default for `PopupConfig::search_timeout_ms`
+pub const DEFAULT_SEARCH_TIMEOUT_MS: u64 = 500;
+
+/// Color scheme of the popup
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    Light,
+    Dark,
+    /// Follow the operating system's dark mode setting, falling back to light
+    #[default]
+    #[serde(alias = "system")]
+    SystemAuto,
+}
+
+/// How the console lists history items
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsoleView {
+    /// One short preview per line, so more items fit on screen
+    Compact,
+    /// Preview and age, plus a line with the type, size, timestamp and tags
+    #[default]
+    Detailed,
+}
+
+impl ConsoleView {
+    /// The other view
+    pub fn toggled(self) -> Self {
+        match self {
+            ConsoleView::Compact => ConsoleView::Detailed,
+            ConsoleView::Detailed => ConsoleView::Compact,
+        }
+    }
+}
+
+/// Configuration for the popup UI and the history it shows
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PopupConfig {
+    pub popup_width: f32,
+    pub popup_height: f32,
+    /// Keep the popup above all other windows
+    pub always_on_top: bool,
+    /// Draw native window decorations (title bar and border).
+    /// On Windows, undecorated eframe windows can render as a black rectangle
+    /// with some GPU drivers, which is why this defaults to `true`.
+    pub decorations: bool,
+    /// Show the title bar close button (only meaningful with decorations)
+    pub show_close_button: bool,
+    /// Global hotkey that opens the popup, e.g. "Ctrl+Shift+V"
+    pub hotkey: String,
+    /// Maximum number of history items kept; pinned items are never trimmed to meet it
+    pub max_history_size: usize,
+    /// Most bytes all items may take up together; the oldest unpinned items are dropped
+    /// to stay under it, on startup too. Unset limits only the number of items
+    pub max_history_bytes: Option<u64>,
+    /// Copying something already in the history moves it to the top instead of
+    /// adding a duplicate entry
+    pub dedup_entire_history: bool,
+    /// How loosely copied text must match an existing item to count as the same:
+    /// exactly, ignoring trailing whitespace, or also ignoring case
+    pub dedup_policy: DedupPolicy,
+    /// Copying the newest item again refreshes its timestamp instead of being ignored
+    pub touch_on_duplicate: bool,
+    /// After picking an item, close the popup and paste it into the previously
+    /// focused window (Windows only; elsewhere the item is just copied)
+    pub auto_paste: bool,
+    /// Remove an item from the history once it has been picked and copied in the popup,
+    /// so the history works like a queue of things to paste once. Copying as plain text
+    /// (Shift+Enter) keeps the item
+    pub pop_on_copy: bool,
+    /// Copied images larger than this many pixels on their longer edge are downscaled
+    /// before being stored; `0` keeps them at full size
+    pub max_stored_image_dimension: u32,
+    /// Reopen the popup on the item that was highlighted when it was last closed
+    /// (for as long as the program keeps running)
+    pub remember_selection: bool,
+    /// Reopen the popup where it was when it last closed instead of at the cursor
+    /// (for as long as the program keeps running). Falls back to the cursor if that
+    /// spot is no longer on a screen
+    pub remember_position: bool,
+    /// How often the clipboard is checked for changes, in milliseconds. Lower values
+    /// record copies sooner; higher values use less power
+    pub poll_interval_ms: u64,
+    /// Which clipboard formats are recorded, most preferred first, when a copy offers
+    /// several: "files", "image", "rtf", "html" and "text". Formats left out are never
+    /// recorded
+    pub capture_priority: Vec<CaptureFormat>,
+    /// Record whatever is already on the clipboard when the program starts. When off,
+    /// only copies made afterwards are recorded
+    pub capture_on_start: bool,
+    /// Copies made while one of these apps is focused are not recorded. Entries match a
+    /// process name (e.g. "KeePassXC") or part of a window title (e.g. "InPrivate").
+    /// Only supported on Windows
+    pub ignored_apps: Vec<String>,
+    /// Items older than this many days are deleted (pinned items are kept). Unset
+    /// keeps the history forever
+    pub max_age_days: Option<u64>,
+    /// Show when each item was copied, right-aligned on its row in the popup
+    pub show_timestamps: bool,
+    /// Show the full content of the highlighted item in a resizable pane under the
+    /// list in the popup
+    pub show_preview_pane: bool,
+    /// Most search results listed in the popup, best first
+    pub max_results: usize,
+    /// How long the popup waits for the history, in milliseconds, before offering to
+    /// retry. Raise it (e.g. to 2000) if a large history often times out. Actions such as
+    /// deleting or pinning an item give up after the same time
+    pub search_timeout_ms: u64,
+    /// Most items listed at once by the console, both in the history and in search
+    /// results
+    pub console_max_results: usize,
+    /// How the console lists items: "detailed" (type, size, timestamp and tags under
+    /// each preview) or "compact" (one short line per item, listing twice as many).
+    /// Toggled with `v` in the console, which saves the choice here
+    pub console_view: ConsoleView,
+    /// Popup color scheme: "light", "dark" or "system_auto"
+    pub theme: Theme,
+    /// Font files (TTF/OTF/TTC) the popup falls back to for characters its built-in
+    /// fonts lack, such as Chinese or Japanese. When empty, a CJK font that ships with
+    /// the OS is used if one is found
+    pub font_paths: Vec<PathBuf>,
+    /// Split the history shown in the popup and console under headers: "none",
+    /// "date" (Today / Yesterday / Earlier) or "content_type" (Text / Images / Files /
+    /// Other). Search results are never grouped
+    pub group_mode: GroupMode,
+    /// Directory for the history files instead of the platform data directory. A
+    /// relative path is taken relative to the executable. `CLIPBOARD_HISTORY_DIR`
+    /// takes precedence
+    pub data_dir: Option<PathBuf>,
+    /// Store copied text that matches one of `secret_patterns` as a secret, shown
+    /// masked in the history. Off by default
+    pub detect_secrets: bool,
+    /// What counts as a secret when `detect_secrets` is on. Defaults to AWS keys, JSON
+    /// web tokens, GitHub tokens, private keys and "password=" assignments
+    pub secret_patterns: Vec<SecretPattern>,
+}
+
+impl Default for PopupConfig {
+    fn default() -> Self {
+        Self {
+            popup_width: 400.0,
+            popup_height: 300.0,
+            always_on_top: true,
+            decorations: true,
+            show_close_button: true,
+            hotkey: "Ctrl+Shift+V".to_string(),
+            max_history_size: DEFAULT_MAX_HISTORY_SIZE,
+            max_history_bytes: None,
+            dedup_entire_history: true,
+            dedup_policy: DedupPolicy::Exact,
+            touch_on_duplicate: false,
+            auto_paste: false,
+            pop_on_copy: false,
+            max_stored_image_dimension: DEFAULT_MAX_STORED_IMAGE_DIMENSION,
+            remember_selection: true,
+            remember_position: false,
+            poll_interval_ms: DEFAULT_POLL_INTERVAL.as_millis() as u64,
+            capture_priority: CaptureFormat::default_priority(),
+            capture_on_start: true,
+            ignored_apps: Vec::new(),
+            max_age_days: None,
+            show_timestamps: true,
+            show_preview_pane: false,
+            max_results: DEFAULT_MAX_RESULTS,
+            search_timeout_ms: DEFAULT_SEARCH_TIMEOUT_MS,
+            console_max_results: DEFAULT_CONSOLE_MAX_RESULTS,
+            console_view: ConsoleView::Detailed,
+            theme: Theme::SystemAuto,
+            font_paths: Vec::new(),
+            group_mode: GroupMode::None,
+            data_dir: None,
+            detect_secrets: false,
+            secret_patterns: SecretPattern::defaults(),
+        }
+    }
+}
+
+impl PopupConfig {
+    /// Default location of the config file
+    pub fn config_file_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("clipboard-history")
+            .join("config.toml")
+    }
+
+    /// Load the config from the default location, using defaults if the file doesn't exist
+    pub fn load() -> Result<Self, ClipboardError> {
+        Self::load_from(&Self::config_file_path())
+    }
+
+    /// Load the config from `path`. Missing settings keep their default values.
+    pub fn load_from(path: &Path) -> Result<Self, ClipboardError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let config: Self = toml::from_str(&content)
+            .map_err(|e| ClipboardError::Config(format!("{}: {e}", path.display())))?;
+        // Report a broken pattern along with the other config errors
+        config.secret_detector()?;
+        Ok(config)
+    }
+
+    /// The detector for `secret_patterns`, or `None` if `detect_secrets` is off
+    pub fn secret_detector(&self) -> Result<Option<SecretDetector>, ClipboardError> {
+        if !self.detect_secrets {
+            return Ok(None);
+        }
+        SecretDetector::new(&self.secret_patterns).map(Some)
+    }
+
+    /// Write the config to the default location
+    pub fn save(&self) -> Result<(), ClipboardError> {
+        self.save_to(&Self::config_file_path())
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), ClipboardError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content =
+            toml::to_string_pretty(self).map_err(|e| ClipboardError::Config(e.to_string()))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! Passphrase-based encryption of the history file (AES-256-GCM, key derived with Argon2id).
+//!
+//! Encrypted files start with a magic header followed by the salt, the nonce and the
+//! ciphertext, so plaintext history files from older versions can still be told apart.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+
+use crate::error::ClipboardError;
+
+/// Environment variable `Storage::new` reads the passphrase from
+pub const PASSPHRASE_ENV_VAR: &str = "CLIPBOARD_HISTORY_PASSPHRASE";
+
+const MAGIC: &[u8] = b"CLIPHIST-ENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Whether `data` looks like an encrypted history file
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// A key derived from the user's passphrase, bound to one salt
+pub struct EncryptionKey {
+    passphrase: String,
+    salt: [u8; SALT_LEN],
+    key: Key<Aes256Gcm>,
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the passphrase or key material
+        f.debug_struct("EncryptionKey").finish_non_exhaustive()
+    }
+}
+
+impl EncryptionKey {
+    /// Derive a key for a new file, with a fresh random salt
+    pub fn new(passphrase: &str) -> Result<Self, ClipboardError> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::with_salt(passphrase, salt)
+    }
+
+    /// Derive a key that can read the given encrypted file, reusing its salt.
+    /// Falls back to a fresh salt if `existing` is not an encrypted file.
+    pub fn for_existing(passphrase: &str, existing: &[u8]) -> Result<Self, ClipboardError> {
+        match Self::header_salt(existing) {
+            Some(salt) => Self::with_salt(passphrase, salt),
+            None => Self::new(passphrase),
+        }
+    }
+
+    fn with_salt(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Self, ClipboardError> {
+        let mut key = Key::<Aes256Gcm>::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| ClipboardError::Encryption(format!("Key derivation failed: {e}")))?;
+
+        Ok(Self {
+            passphrase: passphrase.to_string(),
+            salt,
+            key,
+        })
+    }
+
+    fn header_salt(data: &[u8]) -> Option<[u8; SALT_LEN]> {
+        if !is_encrypted(data) {
+            return None;
+        }
+        data.get(MAGIC.len()..MAGIC.len() + SALT_LEN)?
+            .try_into()
+            .ok()
+    }
+
+    /// Encrypt `plaintext` into the on-disk format, using a fresh nonce
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, ClipboardError> {
+        let cipher = Aes256Gcm::new(&self.key);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| ClipboardError::Encryption("Failed to encrypt history".to_string()))?;
+
+        let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypt a file produced by `encrypt`. A wrong passphrase or a tampered
+    /// file is reported as an error rather than an empty history.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, ClipboardError> {
+        let salt = Self::header_salt(data).ok_or_else(|| {
+            ClipboardError::Encryption("History file is not encrypted or is truncated".to_string())
+        })?;
+
+        // The file may have been written with a different salt (e.g. replaced on disk)
+        let rederived;
+        let key = if salt == self.salt {
+            &self.key
+        } else {
+            rederived = Self::with_salt(&self.passphrase, salt)?;
+            &rederived.key
+        };
+
+        let body = &data[MAGIC.len() + SALT_LEN..];
+        if body.len() < NONCE_LEN {
+            return Err(ClipboardError::Encryption(
+                "Encrypted history file is truncated".to_string(),
+            ));
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+
+        Aes256Gcm::new(key)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                ClipboardError::Encryption(
+                    "Could not decrypt history: wrong passphrase or corrupted file".to_string(),
+                )
+            })
+    }
+}
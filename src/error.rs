@@ -0,0 +1,94 @@
+use std::fmt;
+use std::io;
+
+/// Errors returned by the clipboard history engine
+#[derive(Debug)]
+pub enum ClipboardError {
+    /// Reading or writing the history file failed
+    Io(io::Error),
+    /// The system clipboard could not be accessed or rejected the operation
+    ClipboardUnavailable(String),
+    /// The history could not be serialized or parsed
+    Serialization(serde_json::Error),
+    /// An item exceeded the per-item size limit
+    ContentTooLarge { size: usize, max: usize },
+    /// Stored data (base64, log lines, archives) could not be decoded
+    Decode(String),
+    /// A stored image could not be turned back into pixels for the clipboard
+    InvalidImageData(String),
+    /// Captured content could not be encoded for storage (e.g. an image as PNG)
+    Encode(String),
+    /// The requested item does not exist
+    NotFound(String),
+    /// An id prefix matched more than one item
+    AmbiguousId { prefix: String, matches: usize },
+    /// The SQLite history database rejected an operation
+    Database(rusqlite::Error),
+    /// The config file could not be read or written
+    Config(String),
+    /// The history file could not be encrypted or decrypted (e.g. wrong passphrase)
+    Encryption(String),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardError::Io(e) => write!(f, "I/O error: {e}"),
+            ClipboardError::ClipboardUnavailable(reason) => {
+                write!(f, "Clipboard unavailable: {reason}")
+            }
+            ClipboardError::Serialization(e) => write!(f, "Serialization error: {e}"),
+            ClipboardError::ContentTooLarge { size, max } => {
+                write!(f, "Content too large: {size} bytes (max: {max} bytes)")
+            }
+            ClipboardError::Decode(reason) => write!(f, "Failed to decode item data: {reason}"),
+            ClipboardError::InvalidImageData(reason) => write!(f, "Invalid image data: {reason}"),
+            ClipboardError::Encode(reason) => write!(f, "Failed to encode item data: {reason}"),
+            ClipboardError::NotFound(what) => write!(f, "Item not found: {what}"),
+            ClipboardError::AmbiguousId { prefix, matches } => {
+                write!(f, "Id prefix '{prefix}' matches {matches} items")
+            }
+            ClipboardError::Database(e) => write!(f, "Database error: {e}"),
+            ClipboardError::Config(reason) => write!(f, "Invalid config: {reason}"),
+            ClipboardError::Encryption(reason) => write!(f, "Encryption error: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClipboardError::Io(e) => Some(e),
+            ClipboardError::Serialization(e) => Some(e),
+            ClipboardError::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClipboardError {
+    fn from(e: io::Error) -> Self {
+        ClipboardError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ClipboardError {
+    fn from(e: serde_json::Error) -> Self {
+        ClipboardError::Serialization(e)
+    }
+}
+
+impl From<rusqlite::Error> for ClipboardError {
+    fn from(e: rusqlite::Error) -> Self {
+        ClipboardError::Database(e)
+    }
+}
+
+impl From<ClipboardError> for io::Error {
+    fn from(e: ClipboardError) -> Self {
+        match e {
+            ClipboardError::Io(e) => e,
+            other => io::Error::other(other),
+        }
+    }
+}
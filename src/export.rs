@@ -0,0 +1,160 @@
+//! Portable export and import of the clipboard history.
+//!
+//! A JSON export is a plain array of items. A zip export stores the same array as
+//! `history.json`, but with image data moved out into `images/<id>.<ext>` files so the
+//! archive holds real image files instead of large base64 blobs.
+//!
+//! Text items can also be dumped as JSON lines (`{"id", "timestamp", "text"}`, one per
+//! line) for piping into other tools.
+
+use crate::clipboard_item::{ClipboardContentType, ClipboardItem, ImageFormat};
+use crate::error::ClipboardError;
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const ZIP_HISTORY_ENTRY: &str = "history.json";
+const ZIP_IMAGE_DIR: &str = "images";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// File format written by `export_items`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A single JSON file with images inline as base64
+    Json,
+    /// A zip archive with images stored as separate files
+    Zip,
+}
+
+impl ExportFormat {
+    /// Pick the format from the file extension: `.zip` is a zip bundle, anything else JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("zip") => ExportFormat::Zip,
+            _ => ExportFormat::Json,
+        }
+    }
+}
+
+/// Write `items` to `path` in the given format
+pub fn export_items(
+    items: &[ClipboardItem],
+    path: &Path,
+    format: ExportFormat,
+) -> Result<(), ClipboardError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        ExportFormat::Json => serde_json::to_writer_pretty(&mut writer, items)?,
+        ExportFormat::Zip => write_zip(items, &mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// One line of a text dump
+#[derive(Serialize)]
+struct TextRecord<'a> {
+    id: &'a str,
+    timestamp: DateTime<Utc>,
+    text: &'a str,
+}
+
+/// The JSON-lines record of a plain text item, newline included.
+/// `None` for every other content type, including HTML and rich text.
+pub fn text_jsonl_line(item: &ClipboardItem) -> Result<Option<Vec<u8>>, ClipboardError> {
+    let ClipboardContentType::Text(text) = &item.content else {
+        return Ok(None);
+    };
+    let mut line = serde_json::to_vec(&TextRecord {
+        id: &item.id,
+        timestamp: item.timestamp,
+        text,
+    })?;
+    line.push(b'\n');
+    Ok(Some(line))
+}
+
+/// Read the items of an export made by `export_items`; the format is detected from the content
+pub fn read_export(path: &Path) -> Result<Vec<ClipboardItem>, ClipboardError> {
+    let mut file = File::open(path)?;
+    let mut magic = [0u8; 4];
+    let is_zip = file.read_exact(&mut magic).is_ok() && magic == ZIP_MAGIC;
+
+    let file = File::open(path)?;
+    if is_zip {
+        read_zip(file)
+    } else {
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+}
+
+fn image_entry_name(id: &str, format: &ImageFormat) -> String {
+    let extension = match format {
+        ImageFormat::Png => "png",
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::Bmp => "bmp",
+        ImageFormat::Other(_) => "bin",
+    };
+    format!("{ZIP_IMAGE_DIR}/{id}.{extension}")
+}
+
+fn archive_error(e: zip::result::ZipError) -> ClipboardError {
+    match e {
+        zip::result::ZipError::Io(e) => ClipboardError::Io(e),
+        other => ClipboardError::Decode(format!("Invalid export archive: {other}")),
+    }
+}
+
+fn write_zip<W: Write + std::io::Seek>(
+    items: &[ClipboardItem],
+    writer: W,
+) -> Result<(), ClipboardError> {
+    let mut zip = zip::ZipWriter::new(writer);
+    let options = zip::write::SimpleFileOptions::default();
+    let mut manifest = Vec::with_capacity(items.len());
+
+    for item in items {
+        let mut item = item.clone();
+        if let ClipboardContentType::Image { data, format, .. } = &mut item.content {
+            let bytes = BASE64_STANDARD
+                .decode(data.as_bytes())
+                .map_err(|e| ClipboardError::Decode(format!("Invalid image data: {e}")))?;
+            zip.start_file(image_entry_name(&item.id, format), options)
+                .map_err(archive_error)?;
+            zip.write_all(&bytes)?;
+            // The image lives in its own entry; the manifest only references it by id
+            data.clear();
+        }
+        manifest.push(item);
+    }
+
+    zip.start_file(ZIP_HISTORY_ENTRY, options)
+        .map_err(archive_error)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+    zip.finish().map_err(archive_error)?;
+    Ok(())
+}
+
+fn read_zip(file: File) -> Result<Vec<ClipboardItem>, ClipboardError> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(archive_error)?;
+
+    let mut items: Vec<ClipboardItem> = {
+        let entry = archive.by_name(ZIP_HISTORY_ENTRY).map_err(archive_error)?;
+        serde_json::from_reader(entry)?
+    };
+
+    for item in &mut items {
+        if let ClipboardContentType::Image { data, format, .. } = &mut item.content {
+            let mut entry = archive
+                .by_name(&image_entry_name(&item.id, format))
+                .map_err(archive_error)?;
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            *data = BASE64_STANDARD.encode(&bytes);
+        }
+    }
+    Ok(items)
+}
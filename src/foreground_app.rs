@@ -0,0 +1,85 @@
+/// The application that has keyboard focus, used to tell where a copy came from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForegroundApp {
+    /// Executable file name, e.g. "KeePassXC.exe"
+    pub process_name: String,
+    pub window_title: String,
+}
+
+impl ForegroundApp {
+    /// Whether this app is on the ignore list. An entry matches the process name (with or
+    /// without ".exe") or any part of the window title, ignoring case.
+    pub fn is_ignored(&self, ignored_apps: &[String]) -> bool {
+        let process = self.process_name.to_lowercase();
+        let process_stem = process.strip_suffix(".exe").unwrap_or(&process);
+        let title = self.window_title.to_lowercase();
+
+        ignored_apps
+            .iter()
+            .map(|entry| entry.trim().to_lowercase())
+            .filter(|entry| !entry.is_empty())
+            .any(|entry| {
+                let entry_stem = entry.strip_suffix(".exe").unwrap_or(&entry);
+                entry_stem == process_stem || title.contains(&entry)
+            })
+    }
+}
+
+/// The currently focused application. Returns `None` where this can't be determined
+/// (platforms other than Windows, or no foreground window).
+pub fn current() -> Option<ForegroundApp> {
+    #[cfg(windows)]
+    {
+        windows_foreground_app()
+    }
+
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+#[cfg(windows)]
+fn windows_foreground_app() -> Option<ForegroundApp> {
+    use std::path::Path;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId,
+    };
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+
+        let mut title = [0u16; 512];
+        let title_len = GetWindowTextW(hwnd, title.as_mut_ptr(), title.len() as i32);
+        let window_title = String::from_utf16_lossy(&title[..title_len.max(0) as usize]);
+
+        let mut process_id = 0;
+        GetWindowThreadProcessId(hwnd, &mut process_id);
+        let mut process_name = String::new();
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, process_id);
+        if !process.is_null() {
+            let mut path = [0u16; 1024];
+            let mut path_len = path.len() as u32;
+            if QueryFullProcessImageNameW(process, 0, path.as_mut_ptr(), &mut path_len) != 0 {
+                let path = String::from_utf16_lossy(&path[..path_len as usize]);
+                process_name = Path::new(&path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or(path);
+            }
+            CloseHandle(process);
+        }
+
+        Some(ForegroundApp {
+            process_name,
+            window_title,
+        })
+    }
+}
@@ -0,0 +1,212 @@
+//! Parsing of hotkey strings such as `"Ctrl+Alt+C"` into Win32 modifier flags and
+//! virtual-key codes, so the global hotkey can be rebound from config.
+
+/// Win32 `MOD_*` flags (same values as in `winuser.h`)
+pub const MOD_ALT: u32 = 0x0001;
+pub const MOD_CONTROL: u32 = 0x0002;
+pub const MOD_SHIFT: u32 = 0x0004;
+pub const MOD_WIN: u32 = 0x0008;
+
+/// A parsed hotkey: `MOD_*` flags plus a Win32 virtual-key code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: u32,
+    pub key_code: u32,
+}
+
+impl Hotkey {
+    /// Parse a `+`-separated hotkey like `"Ctrl+Shift+V"` or `"Win+F9"`.
+    /// Token names are case-insensitive; exactly one non-modifier key is required.
+    pub fn parse(hotkey: &str) -> Result<Self, String> {
+        let mut modifiers = 0;
+        let mut key_code = None;
+
+        for token in hotkey.split('+').map(str::trim) {
+            if token.is_empty() {
+                return Err(format!("Invalid hotkey '{hotkey}': empty key name"));
+            }
+
+            if let Some(modifier) = modifier_flag(token) {
+                if modifiers & modifier != 0 {
+                    return Err(format!("Invalid hotkey '{hotkey}': '{token}' given twice"));
+                }
+                modifiers |= modifier;
+                continue;
+            }
+
+            let code = key_code_for(token)
+                .ok_or_else(|| format!("Invalid hotkey '{hotkey}': unknown key '{token}'"))?;
+            if key_code.replace(code).is_some() {
+                return Err(format!(
+                    "Invalid hotkey '{hotkey}': only one non-modifier key is allowed"
+                ));
+            }
+        }
+
+        let key_code =
+            key_code.ok_or_else(|| format!("Invalid hotkey '{hotkey}': missing a key"))?;
+        Ok(Self {
+            modifiers,
+            key_code,
+        })
+    }
+}
+
+fn modifier_flag(token: &str) -> Option<u32> {
+    match token.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Some(MOD_CONTROL),
+        "alt" => Some(MOD_ALT),
+        "shift" => Some(MOD_SHIFT),
+        "win" | "super" | "meta" => Some(MOD_WIN),
+        _ => None,
+    }
+}
+
+fn key_code_for(token: &str) -> Option<u32> {
+    let upper = token.to_ascii_uppercase();
+
+    // Letters and digits map straight to their ASCII codes
+    if let [c] = upper.as_bytes() {
+        if c.is_ascii_uppercase() || c.is_ascii_digit() {
+            return Some(u32::from(*c));
+        }
+    }
+
+    // F1..F24 are VK_F1 (0x70) onwards
+    if let Some(number) = upper.strip_prefix('F').and_then(|n| n.parse::<u32>().ok()) {
+        if (1..=24).contains(&number) {
+            return Some(0x70 + number - 1);
+        }
+    }
+
+    let code = match upper.as_str() {
+        "SPACE" => 0x20,
+        "ENTER" | "RETURN" => 0x0D,
+        "TAB" => 0x09,
+        "ESC" | "ESCAPE" => 0x1B,
+        "BACKSPACE" => 0x08,
+        "INSERT" | "INS" => 0x2D,
+        "DELETE" | "DEL" => 0x2E,
+        "HOME" => 0x24,
+        "END" => 0x23,
+        "PAGEUP" | "PGUP" => 0x21,
+        "PAGEDOWN" | "PGDN" => 0x22,
+        "LEFT" => 0x25,
+        "UP" => 0x26,
+        "RIGHT" => 0x27,
+        "DOWN" => 0x28,
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(all(target_os = "linux", feature = "gui"))]
+impl Hotkey {
+    /// The equivalent `global-hotkey` binding (used for X11 on Linux)
+    pub fn to_global_hotkey(self) -> Option<global_hotkey::hotkey::HotKey> {
+        use global_hotkey::hotkey::{Code, HotKey, Modifiers};
+
+        const LETTERS: [Code; 26] = [
+            Code::KeyA,
+            Code::KeyB,
+            Code::KeyC,
+            Code::KeyD,
+            Code::KeyE,
+            Code::KeyF,
+            Code::KeyG,
+            Code::KeyH,
+            Code::KeyI,
+            Code::KeyJ,
+            Code::KeyK,
+            Code::KeyL,
+            Code::KeyM,
+            Code::KeyN,
+            Code::KeyO,
+            Code::KeyP,
+            Code::KeyQ,
+            Code::KeyR,
+            Code::KeyS,
+            Code::KeyT,
+            Code::KeyU,
+            Code::KeyV,
+            Code::KeyW,
+            Code::KeyX,
+            Code::KeyY,
+            Code::KeyZ,
+        ];
+        const DIGITS: [Code; 10] = [
+            Code::Digit0,
+            Code::Digit1,
+            Code::Digit2,
+            Code::Digit3,
+            Code::Digit4,
+            Code::Digit5,
+            Code::Digit6,
+            Code::Digit7,
+            Code::Digit8,
+            Code::Digit9,
+        ];
+        const FUNCTION_KEYS: [Code; 24] = [
+            Code::F1,
+            Code::F2,
+            Code::F3,
+            Code::F4,
+            Code::F5,
+            Code::F6,
+            Code::F7,
+            Code::F8,
+            Code::F9,
+            Code::F10,
+            Code::F11,
+            Code::F12,
+            Code::F13,
+            Code::F14,
+            Code::F15,
+            Code::F16,
+            Code::F17,
+            Code::F18,
+            Code::F19,
+            Code::F20,
+            Code::F21,
+            Code::F22,
+            Code::F23,
+            Code::F24,
+        ];
+
+        let code = match self.key_code {
+            0x41..=0x5A => LETTERS[(self.key_code - 0x41) as usize],
+            0x30..=0x39 => DIGITS[(self.key_code - 0x30) as usize],
+            0x70..=0x87 => FUNCTION_KEYS[(self.key_code - 0x70) as usize],
+            0x20 => Code::Space,
+            0x0D => Code::Enter,
+            0x09 => Code::Tab,
+            0x1B => Code::Escape,
+            0x08 => Code::Backspace,
+            0x2D => Code::Insert,
+            0x2E => Code::Delete,
+            0x24 => Code::Home,
+            0x23 => Code::End,
+            0x21 => Code::PageUp,
+            0x22 => Code::PageDown,
+            0x25 => Code::ArrowLeft,
+            0x26 => Code::ArrowUp,
+            0x27 => Code::ArrowRight,
+            0x28 => Code::ArrowDown,
+            _ => return None,
+        };
+
+        let mut modifiers = Modifiers::empty();
+        for (flag, modifier) in [
+            (MOD_CONTROL, Modifiers::CONTROL),
+            (MOD_ALT, Modifiers::ALT),
+            (MOD_SHIFT, Modifiers::SHIFT),
+            (MOD_WIN, Modifiers::SUPER),
+        ] {
+            if self.modifiers & flag != 0 {
+                modifiers |= modifier;
+            }
+        }
+
+        Some(HotKey::new(Some(modifiers), code))
+    }
+}
@@ -1,6 +1,33 @@
+//! Clipboard history engine: capturing, storing, searching and copying back clipboard
+//! items. The egui popup (`popup_ui`) is only built with the default `gui` feature.
+
 pub mod clipboard_item;
 pub mod clipboard_manager;
+pub mod config;
+pub mod encryption;
+pub mod error;
+pub mod export;
+pub mod foreground_app;
+pub mod hotkey;
 pub mod monitor;
+pub mod ocr;
+#[cfg(feature = "gui")]
 pub mod popup_ui;
+pub mod secrets;
 pub mod service;
+pub mod sqlite_storage;
 pub mod storage;
+pub mod system_clipboard;
+pub mod thumbnail;
+#[cfg(feature = "tray")]
+pub mod tray;
+pub mod ui;
+pub mod windows_history;
+
+pub use clipboard_item::{ClipboardContentType, ClipboardItem, ImageFormat};
+pub use clipboard_manager::ClipboardManager;
+pub use config::PopupConfig;
+pub use error::ClipboardError;
+pub use monitor::{ClipboardEvent, ClipboardMonitor};
+pub use service::{ClipboardService, SearchResult};
+pub use storage::{JsonStorage, MemoryStorage, Storage};
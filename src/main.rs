@@ -1,46 +1,349 @@
-mod clipboard_item;
-mod clipboard_manager;
-mod monitor;
-mod popup_ui;
-mod service;
-mod storage;
-
-use popup_ui::{HotkeyManager, PopupClipboardUI, PopupConfig};
-use service::ClipboardService;
+use chrono::{DateTime, Local, Utc};
+use clap::{Parser, Subcommand};
+use clipboard_history::clipboard_item::ClipboardItem;
+use clipboard_history::clipboard_manager::parse_time_range;
+use clipboard_history::config::PopupConfig;
+use clipboard_history::error::ClipboardError;
+use clipboard_history::export::ExportFormat;
+#[cfg(feature = "gui")]
+use clipboard_history::popup_ui::{HotkeyManager, PopupClipboardUI};
+use clipboard_history::service::ClipboardService;
+use clipboard_history::system_clipboard::ArboardClipboard;
+#[cfg(feature = "tray")]
+use clipboard_history::tray::{Tray, TrayAction};
+use clipboard_history::ui::{self, ConsoleInterface};
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Clipboard history manager. Without a command it records the clipboard and opens the
+/// history popup on the hotkey (or the console, in builds without the popup)
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Browse and search the history in the terminal instead of the popup
+    #[arg(long)]
+    console: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the newest items, numbered for `copy`
+    List {
+        /// How many items to print
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Print the items matching a query, numbered for `copy`
+    Search {
+        /// Only items copied in this period: today, yesterday, "last N days" or
+        /// "last N hours"
+        #[arg(long, value_parser = parse_range)]
+        range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+        /// Words to search for; all items in the range if left out
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+    /// Copy the item with this number, as printed by `list` and `search`
+    Copy { number: usize },
+    /// Copy the item whose id starts with this prefix
+    Id { prefix: String },
+    /// Remove every item from the history
+    Clear,
+    /// Print the text items as JSON lines, for piping into other tools
+    DumpText,
+    /// Write the history to a .json or .zip file
+    Export { path: PathBuf },
+    /// Add the items of an export to the history
+    Import { path: PathBuf },
+    /// Add the items of the Windows clipboard history (Win+V)
+    ImportWindowsHistory,
+    /// Set the most items kept in the history
+    MaxHistory { count: usize },
+}
+
+fn parse_range(spec: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    parse_time_range(spec, Local::now()).ok_or_else(|| {
+        format!(
+            "unknown range '{spec}' (use today, yesterday, \"last N days\" or \"last N hours\")"
+        )
+    })
+}
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
-    run_popup_mode().await
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::List { limit }) => run_list(limit).await,
+        Some(Command::Search { range, query }) => run_search(range, &query.join(" ")).await,
+        Some(Command::Copy { number }) => run_copy(number).await,
+        Some(Command::Id { prefix }) => run_copy_by_id(&prefix).await,
+        Some(Command::Clear) => run_clear().await,
+        Some(Command::DumpText) => run_dump_text().await,
+        Some(Command::Export { path }) => run_export(&path).await,
+        Some(Command::Import { path }) => run_import(&path).await,
+        Some(Command::ImportWindowsHistory) => run_import_windows_history().await,
+        Some(Command::MaxHistory { count }) => run_set_max_history(count).await,
+        None if cli.console || std::env::var(ui::UI_ENV_VAR).as_deref() == Ok("console") => {
+            run_console_mode().await
+        }
+        #[cfg(feature = "gui")]
+        None => run_popup_mode().await,
+        // Without the popup, the console is the interface
+        #[cfg(not(feature = "gui"))]
+        None => run_console_mode().await,
+    }
+}
+
+async fn run_import_windows_history() -> io::Result<()> {
+    let service = ClipboardService::new().await?;
+    let imported = service.import_windows_history().await?;
+    println!("Imported {imported} item(s) from the Windows clipboard history");
+    Ok(())
+}
+
+async fn run_export(path: &Path) -> io::Result<()> {
+    let service = ClipboardService::new().await?;
+    service
+        .export_history(path, ExportFormat::from_path(path))
+        .await?;
+    println!(
+        "Exported {} item(s) to {}",
+        service.get_history().await.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+async fn run_import(path: &Path) -> io::Result<()> {
+    let service = ClipboardService::new().await?;
+    let imported = service.import_history(path).await?;
+    println!("Imported {imported} item(s) from {}", path.display());
+    Ok(())
+}
+
+async fn run_search(range: Option<(DateTime<Utc>, DateTime<Utc>)>, query: &str) -> io::Result<()> {
+    let (from, to) = range.unwrap_or((DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC));
+
+    let service = ClipboardService::new().await?;
+    let results = service.search_in_range(query, from, to).await;
+    if results.is_empty() {
+        println!("No matching items");
+    }
+    print_items(results);
+    Ok(())
+}
+
+/// One line per item, numbered from 1 like the UIs so the number works with `copy`
+fn print_items(items: impl IntoIterator<Item = (usize, ClipboardItem)>) {
+    for (index, item) in items {
+        println!(
+            "{:>4}. [{}] {}",
+            index + 1,
+            item.timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            item.clean_preview(80)
+        );
+    }
+}
+
+async fn run_list(limit: usize) -> io::Result<()> {
+    let service = ClipboardService::new().await?;
+    let history = service.get_history().await;
+    if history.is_empty() {
+        println!("No clipboard history yet");
+    }
+    print_items(history.into_iter().enumerate().take(limit));
+    Ok(())
+}
+
+/// A service for the copy commands. On Linux, copied content is served by the process
+/// that copied it and vanishes when it exits, so there copies return only once something
+/// else has been copied.
+async fn copy_service() -> io::Result<ClipboardService> {
+    let clipboard = Arc::new(ArboardClipboard::waiting_until_replaced());
+    Ok(ClipboardService::new_with_clipboard(clipboard).await?)
+}
+
+/// Tell the user about `item` being copied, before the copy when it keeps running
+async fn copy_and_report(
+    item: &str,
+    copy: impl std::future::Future<Output = Result<(), ClipboardError>>,
+) -> io::Result<()> {
+    if cfg!(target_os = "linux") {
+        println!("Copied {item}; it stays on the clipboard until something else is copied");
+        copy.await?;
+    } else {
+        copy.await?;
+        println!("Copied {item}");
+    }
+    Ok(())
+}
+
+/// Copy the item numbered `number` by `list`/`search` (1-based)
+async fn run_copy(number: usize) -> io::Result<()> {
+    let service = copy_service().await?;
+    let len = service.get_history().await.len();
+    let index = number
+        .checked_sub(1)
+        .filter(|&index| index < len)
+        .ok_or_else(|| io::Error::other(format!("There is no item #{number}")))?;
+
+    copy_and_report(&format!("item #{number}"), service.copy_to_clipboard(index)).await
+}
+
+/// Print the text items as JSON lines, for piping into other tools
+async fn run_dump_text() -> io::Result<()> {
+    let service = ClipboardService::new().await?;
+    service.dump_text_jsonl(&mut tokio::io::stdout()).await?;
+    Ok(())
+}
+
+async fn run_clear() -> io::Result<()> {
+    let service = ClipboardService::new().await?;
+    let count = service.get_history().await.len();
+    service.clear_history().await?;
+    println!("Cleared {count} item(s) from the history");
+    Ok(())
+}
+
+async fn run_copy_by_id(prefix: &str) -> io::Result<()> {
+    let service = copy_service().await?;
+    let (index, item) = service.find_by_id_prefix(prefix).await?;
+
+    copy_and_report(
+        &format!("item #{} ({})", index + 1, item.id),
+        service.copy_item_by_id(&item.id),
+    )
+    .await
+}
+
+async fn run_set_max_history(max_history_size: usize) -> io::Result<()> {
+    let service = ClipboardService::new().await?;
+    // Apply first so an invalid limit is rejected before it is written to the config
+    let trimmed = service.set_max_history_size(max_history_size).await?;
+
+    let mut config = PopupConfig::load()?;
+    config.max_history_size = max_history_size;
+    config.save()?;
+
+    println!(
+        "History limit set to {max_history_size} item(s){}",
+        if trimmed {
+            "; older items were removed"
+        } else {
+            ""
+        }
+    );
+    Ok(())
+}
+
+/// Browse and search the history in the terminal instead of the popup
+async fn run_console_mode() -> io::Result<()> {
+    let config = PopupConfig::load().unwrap_or_else(|e| {
+        eprintln!("Failed to load config, using defaults: {e}");
+        PopupConfig::default()
+    });
+    let mut service = ClipboardService::new_streaming().await?;
+    let event_receiver = service.start_monitoring();
+
+    ConsoleInterface::new(service.clone(), event_receiver)
+        .with_group_mode(config.group_mode)
+        .with_max_results(config.console_max_results)
+        .with_view(config.console_view)
+        .with_config_file(PopupConfig::config_file_path())
+        .run()
+        .await?;
+    service.stop_monitoring().await;
+    service.flush().await?;
+    Ok(())
 }
 
+#[cfg(feature = "gui")]
 async fn run_popup_mode() -> io::Result<()> {
+    let config = PopupConfig::load().unwrap_or_else(|e| {
+        eprintln!("Failed to load config, using defaults: {e}");
+        PopupConfig::default()
+    });
+
     println!("Starting clipboard manager...");
-    println!("Press Ctrl+Shift+V to open clipboard popup");
+    println!("Press {} to open clipboard popup", config.hotkey);
 
     // Initialize the clipboard service
-    let mut service = ClipboardService::new().await?;
+    let mut service = ClipboardService::new_streaming().await?;
+
+    println!("History file: {}", service.data_file_path().display());
 
     // Start clipboard monitoring
     let _event_receiver = service.start_monitoring();
 
+    // Stop monitoring and flush pending history changes on Ctrl+C before exiting
+    let shutdown_service = service.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            shutdown_service.stop_monitoring().await;
+            if let Err(e) = shutdown_service.flush().await {
+                eprintln!("Failed to save clipboard history: {e}");
+            }
+            std::process::exit(0);
+        }
+    });
+
     // Set up hotkey manager
     let hotkey_manager = HotkeyManager::new();
-    if let Err(e) = hotkey_manager.register_hotkey("Ctrl+Shift+V") {
+    if let Err(e) = hotkey_manager.register_hotkey(&config.hotkey) {
         eprintln!("Failed to register hotkey: {e}");
         return Err(io::Error::other(format!("Hotkey registration failed: {e}")));
     }
 
-    println!("Hotkey registered successfully. Waiting for Ctrl+Shift+V...");
+    println!(
+        "Hotkey registered successfully. Waiting for {}...",
+        config.hotkey
+    );
+
+    // Without a tray (e.g. no system tray on this desktop) the hotkey still works
+    #[cfg(feature = "tray")]
+    let tray = Tray::new(service.is_monitoring_paused())
+        .inspect_err(|e| eprintln!("Tray icon unavailable: {e}"))
+        .ok();
 
-    // Main loop: wait for hotkey, show popup
+    // Popup UI is reused across hotkey presses so it can remember state between opens
+    let mut popup_ui = PopupClipboardUI::new(service.clone(), config);
+
+    // Main loop: wait for the hotkey (or a tray menu click), show popup
     loop {
-        if hotkey_manager.wait_for_hotkey() {
-            println!("Hotkey pressed! Opening popup...");
+        #[allow(unused_mut)] // Only reassigned with the tray feature
+        let mut open_popup = hotkey_manager.wait_for_hotkey();
+
+        #[cfg(feature = "tray")]
+        while let Some(action) = tray.as_ref().and_then(Tray::try_recv) {
+            match action {
+                TrayAction::OpenPopup => open_popup = true,
+                TrayAction::TogglePause if service.is_monitoring_paused() => {
+                    service.resume_monitoring();
+                    println!("▶ Monitoring resumed");
+                }
+                TrayAction::TogglePause => {
+                    service.pause_monitoring();
+                    println!("⏸ Monitoring paused");
+                }
+                TrayAction::ClearHistory => match service.clear_history().await {
+                    Ok(()) => println!("🗑 History cleared"),
+                    Err(e) => eprintln!("Failed to clear history: {e}"),
+                },
+                TrayAction::Quit => {
+                    service.stop_monitoring().await;
+                    service.flush().await?;
+                    return Ok(());
+                }
+            }
+        }
 
-            // Create popup UI
-            let config = PopupConfig::default();
-            let mut popup_ui = PopupClipboardUI::new(service.clone(), config);
+        if open_popup {
+            println!("Opening popup...");
 
             // Show the popup and handle the result
             let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
@@ -1,32 +1,135 @@
 use base64::prelude::*;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, Notify};
 
 use crate::clipboard_item::{ClipboardContentType, ClipboardItem, ImageFormat};
 use crate::clipboard_manager::ClipboardManager;
+use crate::error::ClipboardError;
+use crate::foreground_app::{self, ForegroundApp};
+use crate::secrets::SecretDetector;
+use crate::system_clipboard::{ImageData, SystemClipboard};
+use crate::thumbnail::stored_thumbnail;
+
+const EVENT_PREVIEW_CHARS: usize = 80; // Length of the preview carried by `ItemAdded`
+
+/// How often the clipboard is checked when nothing else is configured
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Shorter intervals are raised to this, so a typo can't turn the monitor into a busy loop
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How often the monitor purges items older than the configured maximum age
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone)]
 pub enum ClipboardEvent {
-    ItemAdded,
-    Error,
+    ItemAdded {
+        id: String,
+        preview: String,
+        content_type: &'static str,
+    },
+    /// A history item was copied back to the clipboard
+    ItemCopied {
+        index: usize,
+        id: String,
+    },
+    Error {
+        message: String,
+    },
     Started,
+    /// The monitoring loop returned after `stop`
+    Stopped,
+    Paused,
+    Resumed,
+}
+
+impl ClipboardEvent {
+    /// `ItemAdded` announcing `item`
+    pub fn item_added(item: &ClipboardItem) -> Self {
+        ClipboardEvent::ItemAdded {
+            id: item.id.clone(),
+            preview: item.clean_preview(EVENT_PREVIEW_CHARS),
+            content_type: item.content_type_name(),
+        }
+    }
+}
+
+/// Clipboard formats the monitor knows how to capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureFormat {
+    /// Files copied in a file manager, stored as their paths
+    Files,
+    Image,
+    /// Rich text, stored together with the plain-text version when there is one
+    Rtf,
+    /// HTML (e.g. copied from a browser), stored together with its plain-text version
+    Html,
+    Text,
+}
+
+impl CaptureFormat {
+    /// Default capture order: copied files win over images (a copied image file is
+    /// recorded as a file), images over rich text and HTML, which win over plain text
+    pub fn default_priority() -> Vec<CaptureFormat> {
+        vec![
+            CaptureFormat::Files,
+            CaptureFormat::Image,
+            CaptureFormat::Rtf,
+            CaptureFormat::Html,
+            CaptureFormat::Text,
+        ]
+    }
+}
+
+/// What was read from the clipboard, before images are encoded for storage
+enum ClipboardRead {
+    Content(ClipboardContentType),
+    /// Raw RGBA pixels, encoded only once they turn out to be a new copy
+    Image(ImageData<'static>),
 }
 
 pub struct ClipboardMonitor {
     manager: Arc<ClipboardManager>,
-    poll_interval: Duration,
+    clipboard: Arc<dyn SystemClipboard>,
+    capture_priority: Vec<CaptureFormat>,
+    capture_on_start: bool,
+    poll_interval_ms: AtomicU64, // Read on every iteration so it can change while running
     event_sender: broadcast::Sender<ClipboardEvent>,
+    paused: AtomicBool,
+    // Set by `stop`, checked (and cleared) by the loop on every iteration
+    stop_requested: AtomicBool,
+    // Wakes the loop early from its poll wait when `stop` is called
+    stop_signal: Notify,
+    // Copies made while one of these apps is focused are not recorded
+    ignored_apps: RwLock<Vec<String>>,
+    foreground_app: fn() -> Option<ForegroundApp>,
+    // Copied text it recognizes is stored as a masked secret
+    secret_detector: Option<SecretDetector>,
 }
 
 impl ClipboardMonitor {
+    /// Create a monitor that reads from the same system clipboard the manager writes to
     pub fn new(manager: Arc<ClipboardManager>) -> Self {
-        let (event_sender, _) = broadcast::channel(100);
+        let event_sender = manager.event_sender();
+        let clipboard = manager.clipboard();
 
         Self {
             manager,
-            poll_interval: Duration::from_millis(500),
+            clipboard,
+            capture_priority: CaptureFormat::default_priority(),
+            capture_on_start: true,
+            poll_interval_ms: AtomicU64::new(DEFAULT_POLL_INTERVAL.as_millis() as u64),
             event_sender,
+            paused: AtomicBool::new(false),
+            stop_requested: AtomicBool::new(false),
+            stop_signal: Notify::new(),
+            ignored_apps: RwLock::new(Vec::new()),
+            foreground_app: foreground_app::current,
+            secret_detector: None,
         }
     }
 
@@ -34,64 +137,260 @@ impl ClipboardMonitor {
         self.event_sender.subscribe()
     }
 
-    #[allow(dead_code)]
-    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
-        self.poll_interval = interval;
+    pub fn with_poll_interval(self, interval: Duration) -> Self {
+        self.set_poll_interval(interval);
+        self
+    }
+
+    /// Change how often the clipboard is checked, taking effect after the current wait.
+    /// Intervals below `MIN_POLL_INTERVAL` are raised to it.
+    pub fn set_poll_interval(&self, interval: Duration) {
+        let interval = interval.max(MIN_POLL_INTERVAL);
+        self.poll_interval_ms
+            .store(interval.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms.load(Ordering::SeqCst))
+    }
+
+    /// Set the order in which clipboard formats are tried when several are present.
+    /// Formats left out of the list are never captured.
+    pub fn with_capture_priority(mut self, priority: Vec<CaptureFormat>) -> Self {
+        self.capture_priority = priority;
+        self
+    }
+
+    /// Whether whatever is on the clipboard when monitoring starts is added to history
+    /// (default), or only treated as already seen so just later copies are recorded.
+    /// Startup capture goes through the normal add path, so dedup still applies.
+    pub fn with_capture_on_start(mut self, capture_on_start: bool) -> Self {
+        self.capture_on_start = capture_on_start;
+        self
+    }
+
+    /// Read from a different system clipboard implementation (e.g. a mock in tests)
+    pub fn with_clipboard(mut self, clipboard: Arc<dyn SystemClipboard>) -> Self {
+        self.clipboard = clipboard;
+        self
+    }
+
+    pub fn with_ignored_apps(self, ignored_apps: Vec<String>) -> Self {
+        self.set_ignored_apps(ignored_apps);
+        self
+    }
+
+    /// Don't record copies made while an app whose process name or window title matches
+    /// one of these entries is focused. Only takes effect where the focused app can be
+    /// detected (Windows); elsewhere everything is recorded.
+    pub fn set_ignored_apps(&self, ignored_apps: Vec<String>) {
+        if let Ok(mut ignored) = self.ignored_apps.write() {
+            *ignored = ignored_apps;
+        }
+    }
+
+    pub fn ignored_apps(&self) -> Vec<String> {
+        self.ignored_apps
+            .read()
+            .map(|ignored| ignored.clone())
+            .unwrap_or_default()
+    }
+
+    /// Store copied text that `detector` recognizes as a secret, shown masked in the
+    /// history. `None` (default) stores all text as is.
+    pub fn with_secret_detector(mut self, detector: Option<SecretDetector>) -> Self {
+        self.secret_detector = detector;
+        self
+    }
+
+    /// Use a different way of finding the focused app (e.g. a fixed one in tests)
+    pub fn with_foreground_app_source(mut self, source: fn() -> Option<ForegroundApp>) -> Self {
+        self.foreground_app = source;
         self
     }
 
+    /// Whether the focused app is on the ignore list
+    fn copied_from_ignored_app(&self) -> bool {
+        let ignored_apps = self.ignored_apps();
+        !ignored_apps.is_empty()
+            && (self.foreground_app)().is_some_and(|app| app.is_ignored(&ignored_apps))
+    }
+
+    /// Stop recording clipboard changes until `resume` is called
+    pub fn pause(&self) {
+        if !self.paused.swap(true, Ordering::SeqCst) {
+            let _ = self.event_sender.send(ClipboardEvent::Paused);
+        }
+    }
+
+    /// Start recording clipboard changes again. Whatever was copied while paused is
+    /// treated as already seen, so it does not end up in the history after resuming.
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::SeqCst) {
+            let _ = self.event_sender.send(ClipboardEvent::Resumed);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Make a running (or about to start) `start_monitoring` loop return after the
+    /// clipboard check in progress, if any. It can be started again afterwards.
+    pub fn stop(&self) {
+        self.stop_requested.store(true, Ordering::SeqCst);
+        self.stop_signal.notify_one();
+    }
+
+    /// Wait for the poll interval, or less if `stop` is called meanwhile
+    async fn wait_poll_interval(&self) {
+        tokio::select! {
+            _ = tokio::time::sleep(self.poll_interval()) => {}
+            _ = self.stop_signal.notified() => {}
+        }
+    }
+
+    /// Poll the clipboard and record changes until `stop` is called
     pub async fn start_monitoring(&self) {
         let mut last_content_hash = String::new();
 
+        if !self.capture_on_start {
+            // Mark the current clipboard content as seen so it is not recorded
+            if let Ok(read) = self.read_clipboard().await {
+                last_content_hash = Self::create_content_hash(&read);
+            }
+        }
+
         // Notify that monitoring has started
         let _ = self.event_sender.send(ClipboardEvent::Started);
 
+        let mut was_paused = false;
+        let mut last_prune = std::time::Instant::now();
+
         loop {
-            let content_result = self.get_clipboard_content().await;
+            if self.stop_requested.swap(false, Ordering::SeqCst) {
+                break;
+            }
 
-            match content_result {
-                Ok(clipboard_item) => {
-                    // Create a hash of the content to detect changes
-                    let content_hash = self.create_content_hash(&clipboard_item);
+            if self.is_paused() {
+                // Don't even read the clipboard while paused
+                was_paused = true;
+                self.wait_poll_interval().await;
+                continue;
+            }
+
+            // Expire old items now and then, not on every poll
+            if last_prune.elapsed() >= PRUNE_INTERVAL {
+                last_prune = std::time::Instant::now();
+                if let Err(e) = self.manager.prune_expired().await {
+                    let _ = self.event_sender.send(ClipboardEvent::Error {
+                        message: e.to_string(),
+                    });
+                }
+            }
+
+            // Pick up items another running instance saved to the same history file
+            if self.manager.history_changed_externally() {
+                if let Err(e) = self.manager.reload().await {
+                    let _ = self.event_sender.send(ClipboardEvent::Error {
+                        message: e.to_string(),
+                    });
+                }
+            }
+
+            let read_result = self.read_clipboard().await;
+
+            if was_paused {
+                // Content copied while paused counts as seen
+                was_paused = false;
+                if let Ok(read) = &read_result {
+                    last_content_hash = Self::create_content_hash(read);
+                }
+            }
+
+            match read_result {
+                Ok(read) => {
+                    // Compared before an image is encoded, so an image sitting on the
+                    // clipboard is only scaled and encoded once
+                    let content_hash = Self::create_content_hash(&read);
 
                     if !content_hash.is_empty() && content_hash != last_content_hash {
-                        match self.manager.add_clipboard_item(clipboard_item).await {
-                            Ok(()) => {
-                                let _ = self.event_sender.send(ClipboardEvent::ItemAdded);
-                            }
-                            Err(_) => {
-                                let _ = self.event_sender.send(ClipboardEvent::Error);
+                        last_content_hash = content_hash;
+                        match self.to_item(read).await {
+                            Ok(clipboard_item) => self.record(clipboard_item).await,
+                            Err(e) => {
+                                let _ = self.event_sender.send(ClipboardEvent::Error {
+                                    message: e.to_string(),
+                                });
                             }
                         }
-                        last_content_hash = content_hash;
                     }
                 }
-                Err(_) => {
-                    let _ = self.event_sender.send(ClipboardEvent::Error);
+                Err(e) => {
+                    let _ = self.event_sender.send(ClipboardEvent::Error {
+                        message: e.to_string(),
+                    });
                 }
             }
 
-            tokio::time::sleep(self.poll_interval).await;
+            self.wait_poll_interval().await;
+        }
+
+        let _ = self.event_sender.send(ClipboardEvent::Stopped);
+    }
+
+    /// Add newly copied content to the history, unless it is our own copy-back or was
+    /// copied from an ignored app
+    async fn record(&self, clipboard_item: ClipboardItem) {
+        // An item copied back from the history stays where it is, and copies
+        // from ignored apps (e.g. password managers) are never stored
+        if self.manager.is_own_write(&clipboard_item) || self.copied_from_ignored_app() {
+            return;
+        }
+
+        #[cfg(feature = "ocr")]
+        let ocr_item = clipboard_item.clone();
+        let added = ClipboardEvent::item_added(&clipboard_item);
+        match self.manager.add_clipboard_item(clipboard_item).await {
+            Ok(()) => {
+                #[cfg(feature = "ocr")]
+                crate::ocr::spawn_extraction(Arc::clone(&self.manager), &ocr_item);
+                let _ = self.event_sender.send(added);
+            }
+            Err(e) => {
+                let _ = self.event_sender.send(ClipboardEvent::Error {
+                    message: e.to_string(),
+                });
+            }
         }
     }
 
-    /// Create a hash representation of clipboard content for change detection
-    fn create_content_hash(&self, item: &ClipboardItem) -> String {
-        match &item.content {
+    /// Create a hash representation of clipboard content for change detection.
+    /// Images are hashed from their raw pixels, before they are encoded.
+    fn create_content_hash(read: &ClipboardRead) -> String {
+        let content = match read {
+            ClipboardRead::Image(image) => {
+                let mut hasher = DefaultHasher::new();
+                image.bytes.hash(&mut hasher);
+                return format!("img:{}x{}:{:x}", image.width, image.height, hasher.finish());
+            }
+            ClipboardRead::Content(content) => content,
+        };
+        match content {
             ClipboardContentType::Text(text) => text.clone(),
             ClipboardContentType::Image {
                 data,
                 format,
                 width,
                 height,
+                ..
             } => {
                 let data_len = data.len();
                 format!("img:{data_len}:{format:?}:{width}x{height}")
             }
-            ClipboardContentType::Html { html, .. } => {
-                let html_len = html.len();
-                format!("html:{html_len}")
-            }
+            // Prefixed so HTML and its plain-text equivalent count as different content
+            ClipboardContentType::Html { html, .. } => format!("html:{html}"),
+            ClipboardContentType::Rtf { rtf, .. } => format!("rtf:{rtf}"),
             ClipboardContentType::Files(files) => {
                 let file_list = files.join("|");
                 format!("files:{file_list}")
@@ -100,97 +399,197 @@ impl ClipboardMonitor {
                 let data_len = data.len();
                 format!("other:{content_type}:{data_len}")
             }
+            ClipboardContentType::Secret { data, .. } => format!("secret:{data}"),
         }
     }
 
-    async fn get_clipboard_content(&self) -> Result<ClipboardItem, String> {
-        let result = tokio::task::spawn_blocking(|| {
-            let mut clipboard =
-                arboard::Clipboard::new().map_err(|_| "Failed to access clipboard")?;
+    /// Read the current system clipboard into a new item, trying formats in
+    /// capture-priority order
+    pub async fn get_clipboard_content(&self) -> Result<ClipboardItem, ClipboardError> {
+        let read = self.read_clipboard().await?;
+        self.to_item(read).await
+    }
 
-            // Try to get image first (images have higher priority)
-            if let Ok(image_data) = clipboard.get_image() {
-                let width = image_data.width as u32;
-                let height = image_data.height as u32;
+    /// Read the current system clipboard in capture-priority order, leaving images as
+    /// raw pixels
+    async fn read_clipboard(&self) -> Result<ClipboardRead, ClipboardError> {
+        let clipboard = Arc::clone(&self.clipboard);
+        let priority = self.capture_priority.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            for format in priority {
+                match format {
+                    CaptureFormat::Files => {
+                        if let Ok(paths) = clipboard.get_files() {
+                            if !paths.is_empty() {
+                                return Ok(ClipboardRead::Content(ClipboardContentType::Files(
+                                    paths
+                                        .iter()
+                                        .map(|path| path.to_string_lossy().into_owned())
+                                        .collect(),
+                                )));
+                            }
+                        }
+                    }
+                    CaptureFormat::Image => {
+                        if let Ok(image_data) = clipboard.get_image() {
+                            return Ok(ClipboardRead::Image(image_data));
+                        }
+                    }
+                    CaptureFormat::Rtf => {
+                        if let Ok(rtf) = clipboard.get_rtf() {
+                            if !rtf.trim().is_empty() {
+                                let plain_text = clipboard
+                                    .get_text()
+                                    .ok()
+                                    .filter(|text| !text.trim().is_empty());
+                                return Ok(ClipboardRead::Content(ClipboardContentType::Rtf {
+                                    rtf,
+                                    plain_text,
+                                }));
+                            }
+                        }
+                    }
+                    CaptureFormat::Html => {
+                        if let Ok(html) = clipboard.get_html() {
+                            if !html.trim().is_empty() {
+                                let plain_text = clipboard
+                                    .get_text()
+                                    .ok()
+                                    .filter(|text| !text.trim().is_empty());
+                                return Ok(ClipboardRead::Content(ClipboardContentType::Html {
+                                    html,
+                                    plain_text,
+                                }));
+                            }
+                        }
+                    }
+                    CaptureFormat::Text => {
+                        if let Ok(text) = clipboard.get_text() {
+                            if !text.trim().is_empty() {
+                                return Ok(ClipboardRead::Content(ClipboardContentType::Text(
+                                    text,
+                                )));
+                            }
+                        }
+                    }
+                }
+            }
+
+            Err(ClipboardError::ClipboardUnavailable(
+                "No supported clipboard content found".to_string(),
+            ))
+        })
+        .await;
 
-                // Convert RGBA to PNG bytes for storage
-                let png_data = Self::rgba_to_png(&image_data.bytes, width, height)
-                    .map_err(|_| "Failed to encode image data")?;
+        result.unwrap_or_else(|e| {
+            Err(ClipboardError::ClipboardUnavailable(format!(
+                "Clipboard access error: {e}"
+            )))
+        })
+    }
 
-                return Ok(ClipboardContentType::Image {
-                    data: BASE64_STANDARD.encode(&png_data),
-                    format: ImageFormat::Png,
-                    width,
-                    height,
-                });
+    /// Turn what was read into a history item: images are downscaled, encoded as PNG
+    /// and given a thumbnail, and text is checked for secrets
+    async fn to_item(&self, read: ClipboardRead) -> Result<ClipboardItem, ClipboardError> {
+        let content = match read {
+            ClipboardRead::Content(content) => content,
+            ClipboardRead::Image(image_data) => {
+                let max_dimension = self.manager.max_stored_image_dimension();
+                tokio::task::spawn_blocking(move || Self::encode_image(&image_data, max_dimension))
+                    .await
+                    .map_err(|e| ClipboardError::Encode(format!("image as PNG: {e}")))??
             }
+        };
 
-            // Try to get HTML if available (not supported by arboard 3.6)
-            // if let Ok(html) = clipboard.get_html() {
-            //     let plain_text = clipboard.get_text().ok();
-            //     return Ok(ClipboardContentType::Html { html, plain_text });
-            // }
-
-            // Try to get text
-            if let Ok(text) = clipboard.get_text() {
-                if !text.trim().is_empty() {
-                    return Ok(ClipboardContentType::Text(text));
-                }
+        // Create a new ClipboardItem with the appropriate constructor
+        let item = match content {
+            ClipboardContentType::Text(text) => match self
+                .secret_detector
+                .as_ref()
+                .and_then(|detector| detector.classify(&text))
+            {
+                Some(kind) => ClipboardItem::new_secret(text, kind),
+                None => ClipboardItem::new_text(text),
+            },
+            // Already base64 PNG, with its thumbnail
+            image @ ClipboardContentType::Image { .. } => ClipboardItem::new(image),
+            ClipboardContentType::Html { html, plain_text } => {
+                ClipboardItem::new_html(html, plain_text)
+            }
+            ClipboardContentType::Rtf { rtf, plain_text } => {
+                ClipboardItem::new_rtf(rtf, plain_text)
             }
+            ClipboardContentType::Files(files) => ClipboardItem::new_files(files),
+            ClipboardContentType::Other { content_type, data } => {
+                ClipboardItem::new_other(content_type, data)
+            }
+            // Never read from the clipboard as such
+            secret @ ClipboardContentType::Secret { .. } => ClipboardItem::new(secret),
+        };
+        Ok(item)
+    }
+
+    /// Convert RGBA pixels to stored PNG, shrinking large images first. The item keeps
+    /// the original size for display.
+    fn encode_image(
+        image_data: &ImageData<'static>,
+        max_dimension: u32,
+    ) -> Result<ClipboardContentType, ClipboardError> {
+        let width = image_data.width as u32;
+        let height = image_data.height as u32;
+        let png_data = Self::rgba_to_png_scaled(&image_data.bytes, width, height, max_dimension)
+            .map_err(|e| ClipboardError::Encode(format!("image as PNG: {e}")))?;
 
-            Err("No supported clipboard content found")
+        Ok(ClipboardContentType::Image {
+            data: BASE64_STANDARD.encode(&png_data),
+            format: ImageFormat::Png,
+            width,
+            height,
+            thumbnail: stored_thumbnail(&image_data.bytes, width, height),
         })
-        .await;
+    }
 
-        match result {
-            Ok(Ok(content)) => {
-                // Create a new ClipboardItem with the appropriate constructor
-                let item = match content {
-                    ClipboardContentType::Text(text) => ClipboardItem::new_text(text),
-                    ClipboardContentType::Image {
-                        data,
-                        format,
-                        width,
-                        height,
-                    } => {
-                        // Convert base64 string back to bytes
-                        if let Ok(decoded_data) = BASE64_STANDARD.decode(&data) {
-                            ClipboardItem::new_image(decoded_data, format, width, height)
-                        } else {
-                            return Err("Failed to decode image data".to_string());
-                        }
-                    }
-                    ClipboardContentType::Html { html, plain_text } => {
-                        ClipboardItem::new_html(html, plain_text)
-                    }
-                    ClipboardContentType::Files(files) => ClipboardItem::new_files(files),
-                    ClipboardContentType::Other { content_type, data } => {
-                        ClipboardItem::new_other(content_type, data)
-                    }
-                };
-                Ok(item)
-            }
-            Ok(Err(e)) => Err(e.to_string()),
-            Err(e) => Err(format!("Clipboard access error: {e}")),
+    /// Size an image is stored at so its longer edge is at most `max_dimension`,
+    /// keeping the aspect ratio. `0` means no limit.
+    pub fn scaled_dimensions(width: u32, height: u32, max_dimension: u32) -> (u32, u32) {
+        let longest = width.max(height);
+        if max_dimension == 0 || longest <= max_dimension {
+            return (width, height);
         }
+        let scale = |edge: u32| {
+            ((u64::from(edge) * u64::from(max_dimension) + u64::from(longest) / 2)
+                / u64::from(longest))
+            .max(1) as u32
+        };
+        (scale(width), scale(height))
     }
 
-    /// Convert RGBA bytes to PNG format
-    fn rgba_to_png(
+    /// Convert RGBA bytes to PNG format, downscaling to `max_dimension` on the longer edge
+    pub fn rgba_to_png_scaled(
         rgba_data: &[u8],
         width: u32,
         height: u32,
+        max_dimension: u32,
     ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
-        use image::{ImageBuffer, Rgba};
+        use image::{imageops, ImageBuffer, Rgba};
 
         let img_buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, rgba_data)
             .ok_or("Failed to create image buffer")?;
 
+        let (scaled_width, scaled_height) = Self::scaled_dimensions(width, height, max_dimension);
         let mut png_data = Vec::new();
-        img_buffer.write_to(
-            &mut std::io::Cursor::new(&mut png_data),
-            image::ImageFormat::Png,
-        )?;
+        let mut writer = std::io::Cursor::new(&mut png_data);
+        if (scaled_width, scaled_height) == (width, height) {
+            img_buffer.write_to(&mut writer, image::ImageFormat::Png)?;
+        } else {
+            imageops::resize(
+                &img_buffer,
+                scaled_width,
+                scaled_height,
+                imageops::FilterType::Triangle,
+            )
+            .write_to(&mut writer, image::ImageFormat::Png)?;
+        }
         Ok(png_data)
     }
 
@@ -198,6 +597,13 @@ impl ClipboardMonitor {
     pub fn png_to_rgba(
         png_data: &[u8],
     ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        Self::png_to_rgba_with_size(png_data).map(|(rgba, _, _)| rgba)
+    }
+
+    /// Convert PNG bytes back to RGBA format, along with the decoded width and height
+    pub fn png_to_rgba_with_size(
+        png_data: &[u8],
+    ) -> Result<(Vec<u8>, u32, u32), Box<dyn std::error::Error + Send + Sync>> {
         use image::ImageReader;
 
         let reader = ImageReader::new(std::io::Cursor::new(png_data))
@@ -209,6 +615,7 @@ impl ClipboardMonitor {
             .map_err(|e| format!("Failed to decode image: {e}"))?;
 
         let rgba_img = img.to_rgba8();
-        Ok(rgba_img.into_raw())
+        let (width, height) = rgba_img.dimensions();
+        Ok((rgba_img.into_raw(), width, height))
     }
 }
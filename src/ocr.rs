@@ -0,0 +1,54 @@
+//! Optional OCR for image items, so screenshots of text can be found by search.
+//!
+//! Text extraction needs Tesseract and is only compiled with the `ocr` feature.
+
+use base64::prelude::*;
+
+use crate::clipboard_item::{ClipboardContentType, ClipboardItem};
+
+#[cfg(feature = "ocr")]
+use crate::clipboard_manager::ClipboardManager;
+#[cfg(feature = "ocr")]
+use crate::error::ClipboardError;
+#[cfg(feature = "ocr")]
+use std::sync::Arc;
+
+/// Encoded image bytes of an image item, if it is one
+pub fn image_bytes(item: &ClipboardItem) -> Option<Vec<u8>> {
+    match &item.content {
+        ClipboardContentType::Image { data, .. } => BASE64_STANDARD.decode(data).ok(),
+        _ => None,
+    }
+}
+
+/// Run Tesseract over encoded image bytes (PNG, JPEG, ...) and return the recognized text
+#[cfg(feature = "ocr")]
+pub fn extract_text(image: &[u8]) -> Result<String, ClipboardError> {
+    let mut tess = leptess::LepTess::new(None, "eng")
+        .map_err(|e| ClipboardError::Decode(format!("Failed to initialize Tesseract: {e}")))?;
+    tess.set_image_from_mem(image)
+        .map_err(|e| ClipboardError::Decode(format!("Failed to load image for OCR: {e}")))?;
+    let text = tess
+        .get_utf8_text()
+        .map_err(|e| ClipboardError::Decode(format!("OCR produced invalid text: {e}")))?;
+    Ok(text.trim().to_string())
+}
+
+/// Extract text from an image item in the background and attach it to the item once done.
+/// The monitor never waits on this.
+#[cfg(feature = "ocr")]
+pub fn spawn_extraction(manager: Arc<ClipboardManager>, item: &ClipboardItem) {
+    let Some(image) = image_bytes(item) else {
+        return;
+    };
+    let id = item.id.clone();
+
+    tokio::spawn(async move {
+        if let Ok(Ok(text)) = tokio::task::spawn_blocking(move || extract_text(&image)).await {
+            if !text.is_empty() {
+                // The item may have been removed in the meantime; nothing to attach to then
+                let _ = manager.set_ocr_text(&id, text).await;
+            }
+        }
+    });
+}
@@ -1,23 +1,143 @@
+use crate::clipboard_item::{
+    ClipboardContentType, ClipboardItem, ContentTypeFilter, GroupHeader, GroupMode,
+};
+use crate::clipboard_manager::{
+    fuzzy_search_snapshot_with, regex_search_snapshot, search_snapshot, tag_query,
+    tag_search_snapshot,
+};
+pub use crate::config::{PopupConfig, Theme};
+use crate::error::ClipboardError;
+use crate::hotkey::Hotkey;
 use crate::service::{ClipboardService, SearchResult};
-use base64::prelude::*;
+use crate::thumbnail::{Thumbnail, ThumbnailCache, THUMBNAIL_SIZE};
+use chrono::{DateTime, Local, Utc};
 use eframe::egui;
-use std::sync::Arc;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, OnceLock};
+use std::time::Duration;
 use tokio::sync::Mutex;
 
-/// Configuration for the popup UI
-#[derive(Clone, Debug)]
-pub struct PopupConfig {
-    pub popup_width: f32,
-    pub popup_height: f32,
-}
+/// Separators offered when combining multi-selected items (label, separator)
+const COMBINE_SEPARATORS: [(&str, &str); 3] = [("Newline", "\n"), ("Comma", ", "), ("Space", " ")];
+
+/// Height allotted to each history row (tall enough for an image thumbnail)
+const ROW_HEIGHT: f32 = 56.0;
+
+/// Popup space above and around the history list: search box, filter row and margins
+const LIST_TOP_MARGIN: f32 = 110.0;
+
+/// Initial height of the preview pane; the user can drag it taller or shorter
+const PREVIEW_PANE_HEIGHT: f32 = 150.0;
+
+/// Longest edge, in pixels, images are decoded at for the preview pane
+const PREVIEW_IMAGE_SIZE: u32 = 512;
+
+/// Characters of text shown in the preview pane; laying out more would slow every frame
+pub const PREVIEW_PANE_MAX_CHARS: usize = 10_000;
+
+/// CJK-capable fonts that ship with the OS, used when `font_paths` isn't set. Only the
+/// first one found is loaded.
+#[cfg(windows)]
+const SYSTEM_FALLBACK_FONTS: &[&str] = &[
+    r"C:\Windows\Fonts\msyh.ttc",
+    r"C:\Windows\Fonts\YuGothM.ttc",
+    r"C:\Windows\Fonts\malgun.ttf",
+];
+#[cfg(target_os = "macos")]
+const SYSTEM_FALLBACK_FONTS: &[&str] = &[
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/STHeiti Light.ttc",
+];
+#[cfg(not(any(windows, target_os = "macos")))]
+const SYSTEM_FALLBACK_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+];
+
+/// egui's built-in fonts followed by the fonts in `font_paths`, which are used for
+/// characters the built-in ones lack. Files that can't be read or aren't valid fonts
+/// are skipped with a warning, so a bad path never keeps the popup from opening.
+pub fn font_definitions(font_paths: &[PathBuf]) -> egui::FontDefinitions {
+    let mut fonts = egui::FontDefinitions::default();
+    for path in font_paths {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("Failed to read font {}: {e}", path.display());
+                continue;
+            }
+        };
+        // egui panics on fonts it can't parse, so check first
+        if let Err(e) = ab_glyph::FontRef::try_from_slice(&bytes) {
+            eprintln!("Failed to load font {}: {e}", path.display());
+            continue;
+        }
 
-impl Default for PopupConfig {
-    fn default() -> Self {
-        Self {
-            popup_width: 400.0,
-            popup_height: 300.0,
+        let name = path.display().to_string();
+        fonts
+            .font_data
+            .insert(name.clone(), egui::FontData::from_owned(bytes));
+        for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+            fonts.families.entry(family).or_default().push(name.clone());
         }
     }
+    fonts
+}
+
+/// Runtime shared by all popups for talking to the service, so UI actions don't each
+/// start their own. It lives on its own thread, independent of whatever runtime (if any)
+/// the popup was opened from.
+fn background_runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("popup-service")
+            .enable_all()
+            .build()
+            .expect("Failed to start the popup background runtime")
+    })
+}
+
+/// Run `task` on the background runtime. The output arrives on the returned receiver;
+/// drop it for fire-and-forget work.
+fn spawn_task<T: Send + 'static>(
+    task: impl Future<Output = T> + Send + 'static,
+) -> mpsc::Receiver<T> {
+    let (sender, receiver) = mpsc::channel();
+    background_runtime().spawn(async move {
+        let _ = sender.send(task.await);
+    });
+    receiver
+}
+
+/// Lock the service for an action the popup waits on, giving up after `timeout` so a busy
+/// service can't freeze the window. Only the lock is timed: an action that got it runs to
+/// completion.
+async fn lock_within(
+    service: &Mutex<ClipboardService>,
+    timeout: Duration,
+) -> Option<tokio::sync::MutexGuard<'_, ClipboardService>> {
+    tokio::time::timeout(timeout, service.lock()).await.ok()
+}
+
+/// The history the popup searches, with the service's thumbnail cache
+type HistorySnapshot = (Vec<ClipboardItem>, Arc<ThumbnailCache>);
+
+/// Window position shared between a popup and the ones opened after it
+type SharedPosition = Arc<std::sync::Mutex<Option<(f32, f32)>>>;
+
+/// Progress of fetching the history snapshot the popup searches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SnapshotState {
+    Loading,
+    Ready,
+    /// The service lock wasn't available in time; the list is empty because of that, not
+    /// because nothing matched
+    TimedOut,
 }
 
 /// Popup clipboard manager UI
@@ -28,6 +148,11 @@ pub struct PopupClipboardUI {
 
     // UI State - these will be recreated for each popup
     cursor_position: (f32, f32),
+
+    // State kept across popup invocations
+    last_closed_at: Option<DateTime<Utc>>,
+    last_selected_index: Arc<std::sync::Mutex<Option<usize>>>,
+    last_position: SharedPosition,
 }
 
 impl PopupClipboardUI {
@@ -36,45 +161,74 @@ impl PopupClipboardUI {
             service: Arc::new(Mutex::new(service)),
             config,
             cursor_position: (0.0, 0.0),
+            last_closed_at: None,
+            last_selected_index: Arc::new(std::sync::Mutex::new(None)),
+            last_position: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 
+    /// Show the popup until it is closed. Returns the history index of the item that was
+    /// pasted into the previously focused window when `auto_paste` is enabled.
     pub async fn show_popup(&mut self) -> eframe::Result<Option<usize>> {
         // Get current cursor position
         self.update_cursor_position();
+        if let Some(position) = self.remembered_position() {
+            self.cursor_position = position;
+        }
+
+        // Remember where the user was so auto-paste can send the keystroke there
+        #[cfg(windows)]
+        let previous_window = Self::foreground_window();
 
         let native_options = eframe::NativeOptions {
-            viewport: egui::ViewportBuilder::default()
-                .with_inner_size([self.config.popup_width, self.config.popup_height])
-                .with_position([self.cursor_position.0, self.cursor_position.1])
-                .with_decorations(true) // Enable decorations temporarily to avoid black screen
-                .with_resizable(false)
-                .with_transparent(false)
-                .with_always_on_top()
-                .with_close_button(true)
-                .with_minimize_button(false)
-                .with_maximize_button(false)
-                .with_active(true) // Make sure the window is active and can detect focus loss
-                .with_visible(true),
+            viewport: self.viewport_builder(),
             ..Default::default()
         };
 
-        let app = PopupApp::new(Arc::clone(&self.service), self.config.clone());
+        let pasted_index = Arc::new(std::sync::Mutex::new(None));
+        let app = PopupApp::new(
+            Arc::clone(&self.service),
+            self.config.clone(),
+            self.last_closed_at,
+        )
+        .with_pasted_index(Arc::clone(&pasted_index));
+        let app = if self.config.remember_selection {
+            app.with_remembered_selection(Arc::clone(&self.last_selected_index))
+        } else {
+            app
+        };
+        let app = if self.config.remember_position {
+            app.with_remembered_position(Arc::clone(&self.last_position))
+        } else {
+            app
+        };
+
+        let fonts = font_definitions(&self.font_paths());
 
         println!("🪟 Starting popup window...");
         match eframe::run_native(
             "Clipboard Manager",
             native_options,
-            Box::new(|_| Ok(Box::new(app))),
+            Box::new(|cc| {
+                cc.egui_ctx.set_fonts(fonts);
+                Ok(Box::new(app))
+            }),
         ) {
             Ok(_) => {
                 println!("✅ Popup closed cleanly, returning to hotkey waiting");
+                self.last_closed_at = Some(Utc::now());
                 // Force screen refresh to remove any shadows on Windows
                 #[cfg(windows)]
                 {
                     self.force_screen_refresh();
                 }
-                Ok(None)
+
+                let pasted_index = *pasted_index.lock().unwrap();
+                #[cfg(windows)]
+                if pasted_index.is_some() {
+                    Self::paste_into(previous_window);
+                }
+                Ok(pasted_index)
             }
             Err(e) => {
                 println!("❌ eframe error: {e}");
@@ -83,40 +237,100 @@ impl PopupClipboardUI {
         }
     }
 
+    /// The configured fallback fonts, or the first system CJK font found if none are
+    fn font_paths(&self) -> Vec<PathBuf> {
+        if !self.config.font_paths.is_empty() {
+            return self.config.font_paths.clone();
+        }
+        SYSTEM_FALLBACK_FONTS
+            .iter()
+            .map(PathBuf::from)
+            .find(|path| path.is_file())
+            .into_iter()
+            .collect()
+    }
+
+    /// Build the popup window description from the current config and cursor position
+    pub fn viewport_builder(&self) -> egui::ViewportBuilder {
+        let window_level = if self.config.always_on_top {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        };
+
+        egui::ViewportBuilder::default()
+            .with_inner_size([self.config.popup_width, self.config.popup_height])
+            .with_position([self.cursor_position.0, self.cursor_position.1])
+            // Disabling decorations can produce a black window on some Windows setups
+            .with_decorations(self.config.decorations)
+            .with_resizable(false)
+            .with_transparent(false)
+            .with_window_level(window_level)
+            .with_close_button(self.config.show_close_button)
+            .with_minimize_button(false)
+            .with_maximize_button(false)
+            .with_active(true) // Make sure the window is active and can detect focus loss
+            .with_visible(true)
+    }
+
+    /// Where the popup was when it last closed, with `remember_position` enabled. `None`
+    /// if that spot is no longer on a screen (e.g. its monitor was disconnected), so the
+    /// popup follows the cursor instead.
+    fn remembered_position(&self) -> Option<(f32, f32)> {
+        if !self.config.remember_position {
+            return None;
+        }
+        let position = (*self.last_position.lock().unwrap())?;
+        let screen = Self::screen_at(position)?;
+        position_on_screen(position, screen).then_some(position)
+    }
+
+    /// Work area of the monitor containing `position`, or `None` if no monitor does
+    #[cfg(windows)]
+    fn screen_at(position: (f32, f32)) -> Option<ScreenBounds> {
+        use windows_sys::Win32::Foundation::POINT;
+        use windows_sys::Win32::Graphics::Gdi::{MonitorFromPoint, MONITOR_DEFAULTTONULL};
+
+        let point = POINT {
+            x: position.0 as i32,
+            y: position.1 as i32,
+        };
+        let monitor = unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTONULL) };
+        (!monitor.is_null()).then(|| Self::monitor_bounds(point))
+    }
+
+    /// Size of the X screen, which spans all monitors
+    #[cfg(target_os = "linux")]
+    fn screen_at(_position: (f32, f32)) -> Option<ScreenBounds> {
+        Self::x11_pointer_position().map(|(_, _, screen)| screen)
+    }
+
+    /// Screens can't be queried here, so the saved position is trusted
+    #[cfg(not(any(windows, target_os = "linux")))]
+    fn screen_at(_position: (f32, f32)) -> Option<ScreenBounds> {
+        Some(ScreenBounds {
+            left: f32::NEG_INFINITY,
+            top: f32::NEG_INFINITY,
+            right: f32::INFINITY,
+            bottom: f32::INFINITY,
+        })
+    }
+
     fn update_cursor_position(&mut self) {
         #[cfg(windows)]
         {
-            use winapi::shared::windef::POINT;
-            use winapi::um::winuser::GetCursorPos;
+            use windows_sys::Win32::Foundation::POINT;
+            use windows_sys::Win32::UI::WindowsAndMessaging::GetCursorPos;
             let mut point = POINT { x: 0, y: 0 };
             unsafe {
                 if GetCursorPos(&mut point) != 0 {
-                    // Adjust position to ensure popup stays on screen
-                    let screen_width = 1920.0; // Default screen width - could be made dynamic
-                    let screen_height = 1080.0; // Default screen height - could be made dynamic
-
-                    let mut x = point.x as f32;
-                    let mut y = point.y as f32;
-
-                    // Ensure popup doesn't go off the right edge of screen
-                    if x + self.config.popup_width > screen_width {
-                        x = screen_width - self.config.popup_width;
-                    }
-
-                    // Ensure popup doesn't go off the bottom edge of screen
-                    if y + self.config.popup_height > screen_height {
-                        y = screen_height - self.config.popup_height;
-                    }
-
-                    // Ensure popup doesn't go off the left or top edges
-                    if x < 0.0 {
-                        x = 0.0;
-                    }
-                    if y < 0.0 {
-                        y = 0.0;
-                    }
-
-                    self.cursor_position = (x, y);
+                    // Clamp to the monitor under the cursor, which may have a negative origin
+                    let screen = Self::monitor_bounds(point);
+                    self.cursor_position = clamp_to_screen(
+                        (point.x as f32, point.y as f32),
+                        (self.config.popup_width, self.config.popup_height),
+                        screen,
+                    );
                 } else {
                     // Fallback to center of screen if cursor position can't be retrieved
                     self.cursor_position = (100.0, 100.0);
@@ -124,23 +338,137 @@ impl PopupClipboardUI {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(target_os = "linux")]
+        {
+            // Ask the X server where the pointer is; under Wayland this only works via XWayland
+            self.cursor_position = match Self::x11_pointer_position() {
+                Some((x, y, screen)) => clamp_to_screen(
+                    (x, y),
+                    (self.config.popup_width, self.config.popup_height),
+                    screen,
+                ),
+                None => (100.0, 100.0),
+            };
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            // For non-Windows platforms, use a default position
+            // For other platforms, use a default position
             self.cursor_position = (100.0, 100.0);
         }
     }
 
+    /// Work area of the monitor containing `point`, falling back to the primary screen size
+    #[cfg(windows)]
+    fn monitor_bounds(point: windows_sys::Win32::Foundation::POINT) -> ScreenBounds {
+        use windows_sys::Win32::Graphics::Gdi::{
+            GetMonitorInfoW, MonitorFromPoint, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+        };
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN,
+        };
+
+        unsafe {
+            let monitor = MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST);
+            if !monitor.is_null() {
+                let mut info: MONITORINFO = std::mem::zeroed();
+                info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+                if GetMonitorInfoW(monitor, &mut info) != 0 {
+                    let work = info.rcWork;
+                    return ScreenBounds {
+                        left: work.left as f32,
+                        top: work.top as f32,
+                        right: work.right as f32,
+                        bottom: work.bottom as f32,
+                    };
+                }
+            }
+
+            ScreenBounds {
+                left: 0.0,
+                top: 0.0,
+                right: GetSystemMetrics(SM_CXSCREEN) as f32,
+                bottom: GetSystemMetrics(SM_CYSCREEN) as f32,
+            }
+        }
+    }
+
+    /// Pointer position and screen bounds from the X server
+    #[cfg(target_os = "linux")]
+    fn x11_pointer_position() -> Option<(f32, f32, ScreenBounds)> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::ConnectionExt;
+
+        let (connection, screen_number) = x11rb::connect(None).ok()?;
+        let screen = connection.setup().roots.get(screen_number)?;
+        let pointer = connection.query_pointer(screen.root).ok()?.reply().ok()?;
+
+        Some((
+            f32::from(pointer.root_x),
+            f32::from(pointer.root_y),
+            ScreenBounds {
+                left: 0.0,
+                top: 0.0,
+                right: f32::from(screen.width_in_pixels),
+                bottom: f32::from(screen.height_in_pixels),
+            },
+        ))
+    }
+
+    /// Handle of the window that currently has focus, as an integer so it can be kept around
+    #[cfg(windows)]
+    fn foreground_window() -> isize {
+        unsafe { windows_sys::Win32::UI::WindowsAndMessaging::GetForegroundWindow() as isize }
+    }
+
+    /// Give focus back to `window` and send it a Ctrl+V keystroke
+    #[cfg(windows)]
+    fn paste_into(window: isize) {
+        use windows_sys::Win32::Foundation::HWND;
+        use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+            SendInput, INPUT, INPUT_KEYBOARD, KEYEVENTF_KEYUP, VK_CONTROL,
+        };
+        use windows_sys::Win32::UI::WindowsAndMessaging::SetForegroundWindow;
+
+        unsafe {
+            if window != 0 {
+                SetForegroundWindow(window as HWND);
+            }
+            // Give the target window a moment to become active before typing into it
+            std::thread::sleep(std::time::Duration::from_millis(50));
+
+            let key = |virtual_key: u16, flags: u32| {
+                let mut input: INPUT = std::mem::zeroed();
+                input.r#type = INPUT_KEYBOARD;
+                input.Anonymous.ki.wVk = virtual_key;
+                input.Anonymous.ki.dwFlags = flags;
+                input
+            };
+            let inputs = [
+                key(VK_CONTROL, 0),
+                key(u16::from(b'V'), 0),
+                key(u16::from(b'V'), KEYEVENTF_KEYUP),
+                key(VK_CONTROL, KEYEVENTF_KEYUP),
+            ];
+            SendInput(
+                inputs.len() as u32,
+                inputs.as_ptr(),
+                std::mem::size_of::<INPUT>() as i32,
+            );
+        }
+    }
+
     #[cfg(windows)]
     fn force_screen_refresh(&self) {
         use std::ptr;
-        use winapi::um::winuser::{
+        use windows_sys::Win32::Foundation::RECT;
+        use windows_sys::Win32::Graphics::Gdi::{
             RedrawWindow, RDW_ALLCHILDREN, RDW_ERASE, RDW_FRAME, RDW_INVALIDATE,
         };
 
         unsafe {
             // Force redraw the area where the popup was
-            let rect = winapi::shared::windef::RECT {
+            let rect = RECT {
                 left: self.cursor_position.0 as i32,
                 top: self.cursor_position.1 as i32,
                 right: (self.cursor_position.0 + self.config.popup_width) as i32,
@@ -165,6 +493,8 @@ struct PopupApp {
     search_text: String,
     selected_index: usize,
     search_results: Vec<SearchResult>,
+    // Matches before `search_results` was cut to `max_results`
+    total_results: usize,
     should_close: bool,
     should_copy_selected: bool,
     selected_item_index: Option<usize>,
@@ -173,126 +503,663 @@ struct PopupApp {
 
     // Performance optimization: Cache textures to avoid recreating them
     texture_cache: std::collections::HashMap<String, egui::TextureHandle>,
+    // Decoded thumbnails shared with the service; arrives with the history snapshot
+    thumbnails: Option<Arc<ThumbnailCache>>,
+    // Preview pane image of the highlighted item, by content hash (`None` if undecodable)
+    preview_texture: Option<(String, Option<egui::TextureHandle>)>,
 
     // Performance optimization: Cache style to avoid recreating every frame
     style_set: bool,
+    // Colors for the configured theme, resolved together with the style
+    palette: PopupPalette,
+
+    // "Only new since last open" view
+    last_closed_at: Option<DateTime<Utc>>,
+    show_only_new: bool,
+
+    // History indices picked with Ctrl+Click, in the order they were picked
+    multi_selection: Vec<usize>,
+
+    // History fetched once per popup; searches run against it without locking the service
+    history_snapshot: Option<Vec<ClipboardItem>>,
+    snapshot_state: SnapshotState,
+    pending_snapshot: Option<mpsc::Receiver<Option<HistorySnapshot>>>,
+
+    // Compile error for a `/pattern/` search, shown next to the search box
+    regex_error: Option<String>,
+
+    // Content type picked with the toggle buttons under the search box
+    content_filter: ContentTypeFilter,
+
+    // With auto-paste, the copied item's index is handed back here so it is pasted on close
+    pasted_index: Arc<std::sync::Mutex<Option<usize>>>,
+
+    // Tag being typed for the highlighted item (Ctrl+T); `None` when not tagging
+    tag_input: Option<String>,
+
+    // With `remember_selection`, the highlighted row shared with later popups
+    remembered_selection: Option<Arc<std::sync::Mutex<Option<usize>>>>,
+    selection_restored: bool,
+
+    // With `remember_position`, the window position shared with later popups
+    remembered_position: Option<SharedPosition>,
 }
 
 impl PopupApp {
-    fn new(service: Arc<Mutex<ClipboardService>>, config: PopupConfig) -> Self {
+    fn new(
+        service: Arc<Mutex<ClipboardService>>,
+        config: PopupConfig,
+        last_closed_at: Option<DateTime<Utc>>,
+    ) -> Self {
         Self {
             service,
             config,
             search_text: String::new(),
             selected_index: 0,
             search_results: Vec::new(),
+            total_results: 0,
             should_close: false,
             should_copy_selected: false,
             selected_item_index: None,
             data_loaded: false,
             close_requested: false,
             texture_cache: std::collections::HashMap::new(),
+            thumbnails: None,
+            preview_texture: None,
             style_set: false,
+            palette: PopupPalette::LIGHT,
+            last_closed_at,
+            show_only_new: false,
+            multi_selection: Vec::new(),
+            history_snapshot: None,
+            snapshot_state: SnapshotState::Loading,
+            pending_snapshot: None,
+            regex_error: None,
+            content_filter: ContentTypeFilter::All,
+            pasted_index: Arc::new(std::sync::Mutex::new(None)),
+            tag_input: None,
+            remembered_selection: None,
+            selection_restored: false,
+            remembered_position: None,
+        }
+    }
+
+    fn with_pasted_index(mut self, pasted_index: Arc<std::sync::Mutex<Option<usize>>>) -> Self {
+        self.pasted_index = pasted_index;
+        self
+    }
+
+    /// Start on the row highlighted when the previous popup closed, and remember the
+    /// highlighted row for the next one
+    fn with_remembered_selection(
+        mut self,
+        remembered_selection: Arc<std::sync::Mutex<Option<usize>>>,
+    ) -> Self {
+        self.remembered_selection = Some(remembered_selection);
+        self
+    }
+
+    /// Remember where the window is, so the next popup opens at the same spot
+    fn with_remembered_position(mut self, remembered_position: SharedPosition) -> Self {
+        self.remembered_position = Some(remembered_position);
+        self
+    }
+
+    /// Move the highlight to the remembered row once the history has loaded
+    fn restore_selection(&mut self) {
+        if self.selection_restored {
+            return;
+        }
+        self.selection_restored = true;
+        if let Some(remembered) = &self.remembered_selection {
+            if let Some(index) = *remembered.lock().unwrap() {
+                self.selected_index = clamp_selection(index, self.search_results.len());
+            }
+        }
+    }
+
+    /// Remember the highlighted row, but only while the full history is listed; an index
+    /// into search or filter results would point somewhere else next time
+    fn remember_selection(&self) {
+        let unfiltered = self.search_text.is_empty()
+            && self.content_filter == ContentTypeFilter::All
+            && !self.show_only_new;
+        if let Some(remembered) = &self.remembered_selection {
+            if unfiltered && self.snapshot_state == SnapshotState::Ready {
+                *remembered.lock().unwrap() = Some(self.selected_index);
+            }
+        }
+    }
+
+    /// Record the window's current position; the last one recorded before closing is kept
+    fn remember_position(&self, ctx: &egui::Context) {
+        if let Some(remembered) = &self.remembered_position {
+            if let Some(rect) = ctx.input(|i| i.viewport().outer_rect) {
+                *remembered.lock().unwrap() = Some((rect.min.x, rect.min.y));
+            }
         }
     }
 
     fn refresh_data(&mut self) {
-        // Performance optimization: Use a more efficient approach for data loading
-        let service = Arc::clone(&self.service);
-        let search_text = self.search_text.clone();
-
-        // Use a more efficient async approach with timeout to prevent hanging
-        let results = std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-
-            // Add timeout to prevent hanging on slow operations
-            rt.block_on(async {
-                // Use a timeout for the operation
-                match tokio::time::timeout(
-                    std::time::Duration::from_millis(500), // 500ms timeout
-                    async {
-                        let service = service.lock().await;
-                        if search_text.is_empty() {
-                            // Show all history
-                            let history = service.get_history().await;
-                            history
-                                .into_iter()
-                                .enumerate()
-                                .map(|(index, item)| SearchResult {
-                                    item,
-                                    index,
-                                    score: None,
-                                })
-                                .collect::<Vec<_>>()
-                        } else {
-                            // Perform search with limit to improve performance
-                            let (exact, fuzzy) = service.search_unified(&search_text).await;
-                            let mut results = if !fuzzy.is_empty() { fuzzy } else { exact };
-
-                            // Limit results to improve UI performance (show top 50 results)
-                            results.truncate(50);
-                            results
-                        }
-                    },
+        // Only hit the service when there is no snapshot yet; searches run on the local copy
+        let Some(snapshot) = self.history_snapshot.as_deref() else {
+            // Results are filled in by `poll_history_snapshot` once the history arrives
+            self.load_history_snapshot();
+            self.search_results.clear();
+            self.selected_index = 0;
+            self.data_loaded = true;
+            return;
+        };
+        let new_since = self.last_closed_at.filter(|_| self.show_only_new);
+
+        let to_result = |index: usize, score: Option<i64>| SearchResult {
+            index,
+            item: snapshot[index].clone(),
+            score,
+        };
+
+        self.regex_error = None;
+        let max_results = self.config.max_results.max(1);
+        let mut uncapped_count = None;
+        let mut results: Vec<SearchResult> = if self.search_text.is_empty() {
+            // Show all history, group by group (if grouped), pinned items first
+            let group_mode = self.config.group_mode;
+            let today = Local::now().date_naive();
+            let mut all: Vec<SearchResult> = (0..snapshot.len())
+                .map(|index| to_result(index, None))
+                .collect();
+            all.sort_by_key(|result| {
+                (
+                    GroupHeader::of(&result.item, group_mode, today),
+                    !result.item.pinned,
                 )
-                .await
-                {
-                    Ok(data) => data,
-                    Err(_) => {
-                        eprintln!("Search operation timed out");
-                        Vec::new()
-                    }
+            });
+            all
+        } else if let Some(tag) = tag_query(&self.search_text) {
+            tag_search_snapshot(snapshot, tag)
+                .into_iter()
+                .map(|index| to_result(index, None))
+                .collect()
+        } else if let Some(pattern) = regex_query(&self.search_text) {
+            match regex::Regex::new(pattern) {
+                Ok(pattern) => regex_search_snapshot(snapshot, &pattern)
+                    .into_iter()
+                    .map(|index| to_result(index, None))
+                    .collect(),
+                Err(e) => {
+                    self.regex_error = Some(e.to_string());
+                    Vec::new()
                 }
+            }
+        } else {
+            // Filters drop results afterwards, so only cap the search when none apply.
+            // Capped before the items are cloned, counting what was left out
+            let unfiltered = new_since.is_none() && self.content_filter == ContentTypeFilter::All;
+            let mut fuzzy = fuzzy_search_snapshot_with(snapshot, &self.search_text, None, None);
+            if unfiltered && fuzzy.len() > max_results {
+                uncapped_count = Some(fuzzy.len());
+                fuzzy.truncate(max_results);
+            }
+            if !fuzzy.is_empty() {
+                fuzzy
+                    .into_iter()
+                    .map(|(index, score)| to_result(index, Some(score)))
+                    .collect()
+            } else {
+                search_snapshot(snapshot, &self.search_text)
+                    .into_iter()
+                    .map(|index| to_result(index, None))
+                    .collect()
+            }
+        };
+
+        if let Some(since) = new_since {
+            results = filter_new_since(results, since);
+        }
+        results.retain(|result| self.content_filter.matches(&result.item));
+        self.total_results = uncapped_count.unwrap_or(results.len());
+        if !self.search_text.is_empty() {
+            // Limit results to improve UI performance
+            results.truncate(max_results);
+        }
+
+        self.search_results = results;
+        self.selected_index = 0;
+        self.data_loaded = true;
+    }
+
+    /// Start fetching a snapshot of the history from the service in the background,
+    /// unless a fetch is already running
+    fn load_history_snapshot(&mut self) {
+        if self.pending_snapshot.is_some() {
+            return;
+        }
+        let service = Arc::clone(&self.service);
+        let timeout = Duration::from_millis(self.config.search_timeout_ms);
+        self.snapshot_state = SnapshotState::Loading;
+        self.pending_snapshot = Some(spawn_task(async move {
+            // Give up if the lock is held too long, rather than leaving the popup waiting
+            tokio::time::timeout(timeout, async {
+                let service = service.lock().await;
+                (service.get_history().await, service.thumbnails())
             })
-        })
-        .join();
+            .await
+            .ok()
+        }));
+    }
 
-        if let Ok(data) = results {
-            self.search_results = data;
-            self.selected_index = 0;
-            self.data_loaded = true;
-        } else {
-            // Fallback to empty results
-            self.search_results = Vec::new();
-            self.selected_index = 0;
-            self.data_loaded = true;
+    /// Pick up the history snapshot once the background fetch has finished
+    fn poll_history_snapshot(&mut self) {
+        let Some(receiver) = &self.pending_snapshot else {
+            return;
+        };
+        let outcome = match receiver.try_recv() {
+            Ok(outcome) => outcome,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        self.pending_snapshot = None;
+
+        match outcome {
+            Some((history, thumbnails)) => {
+                self.history_snapshot = Some(history);
+                self.thumbnails = Some(thumbnails);
+                self.snapshot_state = SnapshotState::Ready;
+                self.refresh_data();
+                self.restore_selection();
+            }
+            // Shown in the list with a Retry button
+            None => self.snapshot_state = SnapshotState::TimedOut,
         }
     }
 
-    fn copy_selected_item(&mut self) {
+    /// Bottom pane with the full content of the highlighted item; its top edge can be
+    /// dragged to resize it
+    fn show_preview_pane(&mut self, ctx: &egui::Context) {
+        let texture = self.preview_texture(ctx);
+        let Some(result) = self.search_results.get(self.selected_index) else {
+            return;
+        };
+
+        egui::TopBottomPanel::bottom("preview_pane")
+            .resizable(true)
+            .default_height(PREVIEW_PANE_HEIGHT)
+            .frame(
+                egui::Frame::default()
+                    .fill(self.palette.background)
+                    .stroke(egui::Stroke::new(1.0, self.palette.border))
+                    .inner_margin(egui::Margin::same(10.0)),
+            )
+            .show(ctx, |ui| {
+                ui.weak(format!(
+                    "{} · {}",
+                    result.item.content_type_name(),
+                    result.item.formatted_timestamp()
+                ));
+                match &texture {
+                    Some(texture) => {
+                        ui.add(egui::Image::from_texture(texture).shrink_to_fit());
+                    }
+                    None => {
+                        egui::ScrollArea::vertical()
+                            .auto_shrink([false; 2])
+                            .show(ui, |ui| ui.label(preview_pane_text(&result.item)));
+                    }
+                }
+            });
+    }
+
+    /// Preview pane image of the highlighted item if it is an image, decoded only when
+    /// the highlight moves to a different image
+    fn preview_texture(&mut self, ctx: &egui::Context) -> Option<egui::TextureHandle> {
+        let item = &self.search_results.get(self.selected_index)?.item;
+        if !matches!(item.content, ClipboardContentType::Image { .. }) {
+            return None;
+        }
+        if let Some((hash, texture)) = &self.preview_texture {
+            if *hash == item.content_hash {
+                return texture.clone();
+            }
+        }
+
+        let texture = Thumbnail::render_preview(item, PREVIEW_IMAGE_SIZE).map(|image| {
+            let size = [image.width as usize, image.height as usize];
+            ctx.load_texture(
+                "preview",
+                egui::ColorImage::from_rgba_unmultiplied(size, &image.rgba),
+                egui::TextureOptions::default(),
+            )
+        });
+        self.preview_texture = Some((item.content_hash.clone(), texture.clone()));
+        texture
+    }
+
+    /// Copy the highlighted item; with `as_plain_text`, HTML/RTF formatting is dropped
+    fn copy_selected_item(&mut self, as_plain_text: bool) {
         if self.selected_index < self.search_results.len() {
             let selected_result = &self.search_results[self.selected_index];
             self.selected_item_index = Some(selected_result.index);
             self.should_copy_selected = true;
 
-            // Copy to clipboard in a background thread with proper error handling
+            // Copy to clipboard in a background thread with proper error handling.
+            // By id, since new captures shift the history indices the results were built with.
             let service = Arc::clone(&self.service);
             let index = selected_result.index;
+            let id = selected_result.item.id.clone();
             let item_preview = selected_result.item.clean_preview(50);
-
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                rt.block_on(async {
-                    let service = service.lock().await;
-                    match service.copy_to_clipboard(index).await {
-                        Ok(_) => {
-                            println!("✅ Item copied to clipboard!");
-                        }
-                        Err(e) => {
-                            eprintln!("❌ Failed to copy item to clipboard: {e}");
-                            eprintln!("   Item preview: {item_preview}");
-                            eprintln!("   This may be due to corrupted image data or unsupported format.");
-
-                            // Try to provide helpful information
-                            if e.to_string().contains("Invalid buffer length") {
-                                eprintln!("   Suggestion: This image may be corrupted or have invalid metadata.");
-                            }
+            let pop = self.config.pop_on_copy && !as_plain_text;
+            let timeout = self.action_timeout();
+
+            let copy_task = spawn_task(async move {
+                let Some(service) = lock_within(&service, timeout).await else {
+                    eprintln!("❌ Failed to copy item: the history is busy, try again");
+                    return false;
+                };
+                let copied = if as_plain_text {
+                    service.copy_item_as_text_by_id(&id).await
+                } else if pop {
+                    service.copy_and_remove_by_id(&id).await
+                } else {
+                    service.copy_item_by_id(&id).await
+                };
+                match copied {
+                    Ok(()) => {
+                        println!("✅ Item copied to clipboard!");
+                        true
+                    }
+                    Err(e) => {
+                        eprintln!("❌ Failed to copy item to clipboard: {e}");
+                        eprintln!("   Item preview: {item_preview}");
+                        if matches!(e, ClipboardError::InvalidImageData(_)) {
+                            eprintln!("   This image may be corrupted or have invalid metadata.");
                         }
+                        false
                     }
-                });
+                }
             });
 
-            // Item copied but popup stays open - no automatic closing
+            // Without auto-paste the item is copied but the popup stays open
+            if self.config.auto_paste || pop {
+                // The clipboard must hold the item before the paste keystroke is sent
+                let copied = copy_task.recv().unwrap_or(false);
+                if copied && pop {
+                    self.remove_from_list(index);
+                }
+                if copied && self.config.auto_paste {
+                    *self.pasted_index.lock().unwrap() = Some(index);
+                    self.should_close = true;
+                    self.close_requested = true;
+                }
+            }
+        }
+    }
+
+    /// Toggle a history index in the multi-selection, keeping pick order
+    fn toggle_multi_selection(&mut self, history_index: usize) {
+        if let Some(position) = self
+            .multi_selection
+            .iter()
+            .position(|&index| index == history_index)
+        {
+            self.multi_selection.remove(position);
+        } else {
+            self.multi_selection.push(history_index);
+        }
+    }
+
+    fn copy_combined_selection(&mut self, separator: &'static str) {
+        let service = Arc::clone(&self.service);
+        // The selection holds snapshot indices; the service is asked by id, since new
+        // captures shift its indices
+        let snapshot = self.history_snapshot.as_deref().unwrap_or_default();
+        let ids: Vec<String> = std::mem::take(&mut self.multi_selection)
+            .into_iter()
+            .filter_map(|index| snapshot.get(index).map(|item| item.id.clone()))
+            .collect();
+
+        spawn_task(async move {
+            let service = service.lock().await;
+            match service.copy_combined_by_id(&ids, separator).await {
+                Ok(()) => println!("✅ Combined {} items to clipboard!", ids.len()),
+                Err(ClipboardError::NotFound(_)) => eprintln!("❌ No text items to combine"),
+                Err(e) => eprintln!("❌ Failed to copy combined items: {e}"),
+            }
+        });
+    }
+
+    /// Empty the search box and list the whole history again from the top
+    fn clear_search(&mut self) {
+        self.search_text.clear();
+        self.data_loaded = false;
+        self.refresh_data();
+    }
+
+    /// Delete the highlighted item and keep the highlight on the row that takes its place
+    fn delete_selected_item(&mut self) {
+        let Some((history_index, id)) = self
+            .search_results
+            .get(self.selected_index)
+            .map(|result| (result.index, result.item.id.clone()))
+        else {
+            return;
+        };
+        let service = Arc::clone(&self.service);
+        let timeout = self.action_timeout();
+
+        // By id: captures made while the popup is open shift the history indices
+        let outcome = spawn_task(async move {
+            let service = lock_within(&service, timeout).await?;
+            Some(service.delete_item_by_id(&id).await)
+        })
+        .recv();
+
+        match outcome {
+            Ok(Some(Ok(true))) => self.remove_from_list(history_index),
+            Ok(Some(Ok(false))) => {}
+            Ok(Some(Err(e))) => eprintln!("❌ Failed to delete item: {e}"),
+            Ok(None) => eprintln!("❌ Failed to delete item: the history is busy, try again"),
+            Err(_) => eprintln!("❌ Failed to delete item"),
+        }
+    }
+
+    /// How long an action the popup waits on may wait for the service
+    fn action_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.search_timeout_ms)
+    }
+
+    /// Drop a deleted item from the snapshot and the list, keeping the highlight on the
+    /// row that takes its place
+    fn remove_from_list(&mut self, history_index: usize) {
+        // Later indices in the snapshot shift the same way they did in the history
+        let selected_index = self.selected_index;
+        if let Some(snapshot) = self.history_snapshot.as_mut() {
+            if history_index < snapshot.len() {
+                snapshot.remove(history_index);
+            }
+        }
+        self.multi_selection.clear();
+        self.refresh_data();
+        self.selected_index = selected_index.min(self.search_results.len().saturating_sub(1));
+    }
+
+    /// Pin or unpin the highlighted item, keeping it highlighted as the list reorders
+    fn toggle_selected_pin(&mut self) {
+        let Some((history_index, id)) = self
+            .search_results
+            .get(self.selected_index)
+            .map(|result| (result.index, result.item.id.clone()))
+        else {
+            return;
+        };
+        let service = Arc::clone(&self.service);
+        let timeout = self.action_timeout();
+
+        let outcome = spawn_task(async move {
+            let service = lock_within(&service, timeout).await?;
+            Some(service.toggle_pin_by_id(&id).await)
+        })
+        .recv();
+
+        match outcome {
+            Ok(Some(Ok(Some(pinned)))) => {
+                if let Some(item) = self
+                    .history_snapshot
+                    .as_mut()
+                    .and_then(|snapshot| snapshot.get_mut(history_index))
+                {
+                    item.pinned = pinned;
+                }
+                self.refresh_data();
+                if let Some(position) = self
+                    .search_results
+                    .iter()
+                    .position(|result| result.index == history_index)
+                {
+                    self.selected_index = position;
+                }
+            }
+            Ok(Some(Ok(None))) => {}
+            Ok(Some(Err(e))) => eprintln!("❌ Failed to pin item: {e}"),
+            Ok(None) => eprintln!("❌ Failed to pin item: the history is busy, try again"),
+            Err(_) => eprintln!("❌ Failed to pin item"),
+        }
+    }
+
+    /// Move the highlighted item to the top of the history and keep it highlighted
+    fn promote_selected_item(&mut self) {
+        let Some((history_index, id)) = self
+            .search_results
+            .get(self.selected_index)
+            .map(|result| (result.index, result.item.id.clone()))
+        else {
+            return;
+        };
+        let service = Arc::clone(&self.service);
+        let timeout = self.action_timeout();
+
+        let outcome = spawn_task(async move {
+            let service = lock_within(&service, timeout).await?;
+            Some(service.promote_to_front_by_id(&id).await)
+        })
+        .recv();
+
+        match outcome {
+            Ok(Some(Ok(true))) => {
+                // Mirror the move in the snapshot; items above it shift down by one
+                if let Some(snapshot) = self.history_snapshot.as_mut() {
+                    if history_index < snapshot.len() {
+                        let mut item = snapshot.remove(history_index);
+                        item.timestamp = Utc::now();
+                        snapshot.insert(0, item);
+                    }
+                }
+                self.multi_selection.clear();
+                self.refresh_data();
+                if let Some(position) = self
+                    .search_results
+                    .iter()
+                    .position(|result| result.index == 0)
+                {
+                    self.selected_index = position;
+                }
+            }
+            Ok(Some(Ok(false))) => {}
+            Ok(Some(Err(e))) => eprintln!("❌ Failed to move item to the top: {e}"),
+            Ok(None) => {
+                eprintln!("❌ Failed to move item to the top: the history is busy, try again")
+            }
+            Err(_) => eprintln!("❌ Failed to move item to the top"),
+        }
+    }
+
+    /// Apply the typed tag to the highlighted item; a leading '-' removes the tag instead
+    fn apply_tag_input(&mut self) {
+        let Some(input) = self.tag_input.take() else {
+            return;
+        };
+        let Some((history_index, id)) = self
+            .search_results
+            .get(self.selected_index)
+            .map(|result| (result.index, result.item.id.clone()))
+        else {
+            return;
+        };
+        let (remove, tag) = match input.trim().strip_prefix('-') {
+            Some(tag) => (true, tag.to_string()),
+            None => (false, input.trim().to_string()),
+        };
+        let Some(tag) = crate::clipboard_item::normalize_tag(&tag) else {
+            return;
+        };
+        let service = Arc::clone(&self.service);
+        let service_tag = tag.clone();
+        let timeout = self.action_timeout();
+
+        let outcome = spawn_task(async move {
+            let service = lock_within(&service, timeout).await?;
+            Some(if remove {
+                service.remove_tag_by_id(&id, &service_tag).await
+            } else {
+                service.add_tag_by_id(&id, &service_tag).await
+            })
+        })
+        .recv();
+
+        match outcome {
+            Ok(Some(Ok(true))) => {
+                if let Some(item) = self
+                    .history_snapshot
+                    .as_mut()
+                    .and_then(|snapshot| snapshot.get_mut(history_index))
+                {
+                    if remove {
+                        item.tags.retain(|t| !t.eq_ignore_ascii_case(&tag));
+                    } else {
+                        item.tags.push(tag);
+                    }
+                }
+                let selected_index = self.selected_index;
+                self.refresh_data();
+                self.selected_index =
+                    selected_index.min(self.search_results.len().saturating_sub(1));
+            }
+            Ok(Some(Ok(false))) => {}
+            Ok(Some(Err(e))) => eprintln!("❌ Failed to update tags: {e}"),
+            Ok(None) => eprintln!("❌ Failed to update tags: the history is busy, try again"),
+            Err(_) => eprintln!("❌ Failed to update tags"),
+        }
+    }
+
+    fn open_selected_url(&mut self) {
+        let Some(selected_result) = self.search_results.get(self.selected_index) else {
+            return;
+        };
+        if selected_result.item.as_url().is_none() {
+            return;
+        }
+        let service = Arc::clone(&self.service);
+        let id = selected_result.item.id.clone();
+
+        spawn_task(async move {
+            let service = service.lock().await;
+            if let Err(e) = service.open_url_by_id(&id).await {
+                eprintln!("❌ Failed to open the link in the browser: {e}");
+            }
+        });
+    }
+
+    fn copy_selected_metadata(&mut self) {
+        if let Some(selected_result) = self.search_results.get(self.selected_index) {
+            let service = Arc::clone(&self.service);
+            let id = selected_result.item.id.clone();
+
+            spawn_task(async move {
+                let service = service.lock().await;
+                match service.copy_item_metadata_by_id(&id).await {
+                    Ok(()) => println!("✅ Item metadata copied to clipboard!"),
+                    Err(e) => eprintln!("❌ Failed to copy item metadata to clipboard: {e}"),
+                }
+            });
         }
     }
 }
@@ -337,8 +1204,8 @@ impl eframe::App for PopupApp {
 
         // Prevent further processing if we're supposed to be closing
         if self.should_close || self.close_requested {
-            // Just return early to avoid drawing anything else
-            // The eframe loop should handle the close naturally
+            // Return early to avoid drawing anything else and ask eframe to close the window
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
             return;
         }
 
@@ -346,15 +1213,19 @@ impl eframe::App for PopupApp {
         if !self.data_loaded {
             self.refresh_data();
         }
+        self.poll_history_snapshot();
 
-        // Set up the popup style with bright, visible background and bigger font (only once)
+        // Set up the popup theme and a bigger font (only once)
         if !self.style_set {
+            self.palette = PopupPalette::for_theme(self.config.theme, system_prefers_dark(ctx));
+            ctx.set_theme(if self.palette.dark {
+                egui::Theme::Dark
+            } else {
+                egui::Theme::Light
+            });
+
             let mut style = (*ctx.style()).clone();
-            style.visuals.window_fill = egui::Color32::WHITE; // Pure white background
-            style.visuals.window_stroke =
-                egui::Stroke::new(2.0, egui::Color32::from_rgb(70, 70, 70)); // Dark border for contrast
-            style.visuals.panel_fill = egui::Color32::WHITE; // White panel
-            style.visuals.override_text_color = Some(egui::Color32::BLACK); // Ensure text is black
+            style.visuals = self.palette.visuals();
 
             // Increase font size for better readability
             style.text_styles.insert(
@@ -374,10 +1245,15 @@ impl eframe::App for PopupApp {
             self.style_set = true;
         }
 
+        // Added before the central panel so the list gets the space that is left
+        if self.config.show_preview_pane {
+            self.show_preview_pane(ctx);
+        }
+
         egui::CentralPanel::default()
             .frame(egui::Frame::default()
-                .fill(egui::Color32::WHITE) // Pure white background
-                .stroke(egui::Stroke::new(2.0, egui::Color32::from_rgb(100, 100, 100)))
+                .fill(self.palette.background)
+                .stroke(egui::Stroke::new(2.0, self.palette.border))
                 .rounding(egui::Rounding::same(6.0)) // Slightly rounded corners
                 .inner_margin(egui::Margin::same(10.0)) // More margin for better spacing
             )
@@ -387,14 +1263,15 @@ impl eframe::App for PopupApp {
                     ui.horizontal(|ui| {
                         ui.label("🔍 Search:");
 
-                        // Style the search text box with white background and border
+                        // Style the search text box with the theme's input background and border
+                        let palette = self.palette;
                         let search_style = ui.style_mut();
-                        search_style.visuals.extreme_bg_color = egui::Color32::WHITE;
-                        search_style.visuals.widgets.inactive.bg_fill = egui::Color32::WHITE;
-                        search_style.visuals.widgets.hovered.bg_fill = egui::Color32::from_rgb(248, 248, 248);
-                        search_style.visuals.widgets.active.bg_fill = egui::Color32::WHITE;
-                        search_style.visuals.widgets.inactive.fg_stroke = egui::Stroke::new(1.0, egui::Color32::BLACK);
-                        search_style.visuals.widgets.inactive.bg_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(150, 150, 150));
+                        search_style.visuals.extreme_bg_color = palette.input_background;
+                        search_style.visuals.widgets.inactive.bg_fill = palette.input_background;
+                        search_style.visuals.widgets.hovered.bg_fill = palette.input_hover;
+                        search_style.visuals.widgets.active.bg_fill = palette.input_background;
+                        search_style.visuals.widgets.inactive.fg_stroke = egui::Stroke::new(1.0, palette.text);
+                        search_style.visuals.widgets.inactive.bg_stroke = egui::Stroke::new(1.0, palette.input_border);
 
                         let search_response = ui.text_edit_singleline(&mut self.search_text);
 
@@ -404,8 +1281,58 @@ impl eframe::App for PopupApp {
                             self.refresh_data();
                         }
 
-                        // Auto-focus the search box when popup opens
-                        search_response.request_focus();
+                        if !self.search_text.is_empty()
+                            && ui.small_button("✕").on_hover_text("Clear search (Ctrl+Backspace)").clicked()
+                        {
+                            self.clear_search();
+                        }
+
+                        // Auto-focus the search box when popup opens (unless a tag is being typed)
+                        if self.tag_input.is_none() {
+                            search_response.request_focus();
+                        }
+
+                        if let Some(error) = &self.regex_error {
+                            ui.colored_label(self.palette.error, "⚠ Invalid regex")
+                                .on_hover_text(error);
+                        }
+
+                        // Only offer the "new" view once a previous popup has been closed
+                        if self.last_closed_at.is_some()
+                            && ui
+                                .checkbox(&mut self.show_only_new, "🆕 New")
+                                .on_hover_text("Only show items copied since the popup was last closed")
+                                .changed()
+                        {
+                            self.data_loaded = false;
+                            self.refresh_data();
+                        }
+                    });
+
+                    // Content type toggles; clicking the active one shows everything again
+                    ui.horizontal(|ui| {
+                        for (filter, label) in [
+                            (ContentTypeFilter::Text, "📝 Text"),
+                            (ContentTypeFilter::Image, "🖼 Image"),
+                            (ContentTypeFilter::Files, "📁 Files"),
+                            (ContentTypeFilter::Html, "🌐 HTML"),
+                        ] {
+                            let active = self.content_filter == filter;
+                            if ui.selectable_label(active, label).clicked() {
+                                self.content_filter = if active { ContentTypeFilter::All } else { filter };
+                                self.data_loaded = false;
+                                self.refresh_data();
+                            }
+                        }
+
+                        // Tag entry for the highlighted item: Enter applies, Esc cancels
+                        if let Some(tag_input) = &mut self.tag_input {
+                            ui.separator();
+                            ui.label("🏷");
+                            ui.text_edit_singleline(tag_input)
+                                .on_hover_text("Tag for the selected item (prefix with - to remove)")
+                                .request_focus();
+                        }
                     });
 
                     ui.separator();
@@ -413,32 +1340,59 @@ impl eframe::App for PopupApp {
                     // History list with scrolling - using full available space
                     let mut should_copy = false;
                     let mut copy_index = None;
+                    let mut multi_toggle = None;
 
                     egui::ScrollArea::vertical()
-                        .max_height(self.config.popup_height - 80.0) // Reduced space reservation for search only
+                        .max_height((self.config.popup_height - LIST_TOP_MARGIN).min(ui.available_height())) // Leave room for the search box and filter row
                         .auto_shrink([false; 2]) // Prevent shrinking
                         .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::VisibleWhenNeeded)
                         .show(ui, |ui| {
                             // Set the UI width to ensure proper scrollbar positioning
                             ui.set_min_width(self.config.popup_width - 30.0); // Leave space for scrollbar on right
 
+                            // Say why the list is empty: still loading, timed out, or no matches
+                            match self.snapshot_state {
+                                SnapshotState::Loading => {
+                                    ui.horizontal(|ui| {
+                                        ui.spinner();
+                                        ui.weak("Searching...");
+                                    });
+                                }
+                                SnapshotState::TimedOut => {
+                                    ui.horizontal(|ui| {
+                                        ui.weak("Search timed out, try again");
+                                        if ui.button("Retry").clicked() {
+                                            self.data_loaded = false;
+                                        }
+                                    });
+                                }
+                                SnapshotState::Ready if self.search_results.is_empty() => {
+                                    ui.weak(if self.search_text.is_empty() { "No clipboard history yet" } else { "No matches" });
+                                }
+                                SnapshotState::Ready => {}
+                            }
+
+                            let show_timestamps = self.config.show_timestamps;
+                            // Search results are ranked, so only the plain history is grouped
+                            let group_mode = if self.search_text.is_empty() { self.config.group_mode } else { GroupMode::None };
+                            let today = Local::now().date_naive();
+                            let mut current_group = None;
+
                             // Display ALL search results, not just the first 10
                             for (display_index, result) in self.search_results.iter().enumerate() {
-                                let is_selected = display_index == self.selected_index;
+                                if group_mode != GroupMode::None {
+                                    let header = GroupHeader::of(&result.item, group_mode, today);
+                                    if current_group.replace(header) != Some(header) {
+                                        ui.add_space(4.0);
+                                        ui.weak(header.label());
+                                    }
+                                }
 
-                                // Alternating background colors: white and more visible gray
-                                let row_bg_color = if display_index % 2 == 0 {
-                                    egui::Color32::WHITE // Even rows: white
-                                } else {
-                                    egui::Color32::from_rgb(230, 230, 230) // Odd rows: more visible gray
-                                };
+                                let is_selected = display_index == self.selected_index;
+                                let is_multi_selected = self.multi_selection.contains(&result.index);
 
-                                // Override with selection color if selected
-                                let final_bg_color = if is_selected {
-                                    egui::Color32::from_rgb(200, 220, 255) // Light blue selection
-                                } else {
-                                    row_bg_color
-                                };
+                                // Alternating row background, overridden by the selection colors
+                                let final_bg_color = self.palette.row_fill(display_index, is_selected, is_multi_selected);
 
                                 // Create a frame for the entire row with alternating background
                                 let row_frame = egui::Frame::default()
@@ -449,7 +1403,7 @@ impl eframe::App for PopupApp {
                                 // Use allocate_ui_with_layout to ensure full width background
                                 let available_rect = ui.available_rect_before_wrap();
                                 let item_response = ui.allocate_ui_with_layout(
-                                    egui::Vec2::new(available_rect.width(), 56.0), // Increased height for bigger images
+                                    egui::Vec2::new(available_rect.width(), ROW_HEIGHT), // Increased height for bigger images
                                     egui::Layout::left_to_right(egui::Align::Center),
                                     |ui| {
                                         row_frame.show(ui, |ui| {
@@ -458,46 +1412,34 @@ impl eframe::App for PopupApp {
 
                                             // Check if this is an image item to display preview
                                             match &result.item.content {
-                                                crate::clipboard_item::ClipboardContentType::Image { data, .. } => {
+                                                crate::clipboard_item::ClipboardContentType::Image { .. } => {
                                                     // Display image preview with text
                                                     ui.horizontal(|ui| {
-                                                        // Try to decode and display the image
-                                                        if let Ok(image_data) = base64::prelude::BASE64_STANDARD.decode(data) {
-                                                            // Check if we have a cached texture first
-                                                            let texture_id = format!("thumb_{}", &result.item.id);
-
-                                                            if let Some(cached_texture) = self.texture_cache.get(&texture_id) {
-                                                                // Use cached texture
-                                                                let image = egui::Image::from_texture(cached_texture)
-                                                                    .fit_to_exact_size(egui::Vec2::new(48.0, 48.0));
-                                                                ui.add(image);
-                                                            } else if let Ok(dynamic_image) = image::load_from_memory(&image_data) {
-                                                                // Create new texture and cache it
-                                                                let thumbnail_size = 48;
-                                                                let thumbnail = dynamic_image.thumbnail(thumbnail_size, thumbnail_size);
-                                                                let rgba_image = thumbnail.to_rgba8();
-                                                                let size = [rgba_image.width() as usize, rgba_image.height() as usize];
-                                                                let pixels = rgba_image.as_flat_samples();
-
-                                                                // Create texture from image data
-                                                                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
-                                                                let texture_handle = ui.ctx().load_texture(
-                                                                    texture_id.clone(),
-                                                                    color_image,
-                                                                    egui::TextureOptions::default()
-                                                                );
-
-                                                                // Cache the texture for future use
-                                                                self.texture_cache.insert(texture_id, texture_handle.clone());
-
-                                                                let image = egui::Image::from_texture(&texture_handle)
-                                                                    .fit_to_exact_size(egui::Vec2::new(48.0, 48.0));
-
-                                                                ui.add(image);
-                                                            } else {
-                                                                // Fallback to icon if image can't be loaded
-                                                                ui.label("🖼️");
-                                                            }
+                                                        let texture_id = format!("thumb_{}", &result.item.content_hash);
+                                                        let thumbnail_edge = THUMBNAIL_SIZE as f32;
+
+                                                        if let Some(cached_texture) = self.texture_cache.get(&texture_id) {
+                                                            // Use cached texture
+                                                            let image = egui::Image::from_texture(cached_texture)
+                                                                .fit_to_exact_size(egui::Vec2::splat(thumbnail_edge));
+                                                            ui.add(image);
+                                                        } else if let Some(thumbnail) = self.thumbnails.as_ref().and_then(|cache| cache.get(&result.item)) {
+                                                            // The thumbnail is already decoded and scaled; just upload it
+                                                            let size = [thumbnail.width as usize, thumbnail.height as usize];
+                                                            let color_image = egui::ColorImage::from_rgba_unmultiplied(size, &thumbnail.rgba);
+                                                            let texture_handle = ui.ctx().load_texture(
+                                                                texture_id.clone(),
+                                                                color_image,
+                                                                egui::TextureOptions::default()
+                                                            );
+
+                                                            // Cache the texture for future use
+                                                            self.texture_cache.insert(texture_id, texture_handle.clone());
+
+                                                            let image = egui::Image::from_texture(&texture_handle)
+                                                                .fit_to_exact_size(egui::Vec2::splat(thumbnail_edge));
+
+                                                            ui.add(image);
                                                         } else {
                                                             // Fallback to icon if image can't be decoded
                                                             ui.label("🖼️");
@@ -505,15 +1447,36 @@ impl eframe::App for PopupApp {
 
                                                         // Add image info text
                                                         let item_number = display_index + 1;
-                                                        ui.label(format!("{item_number}. image"));
+                                                        let pin = if result.item.pinned { "📌 " } else { "" };
+                                                        ui.label(format!("{item_number}. {pin}image"));
+                                                        if !result.item.tags.is_empty() {
+                                                            ui.weak(result.item.tags_label());
+                                                        }
+                                                        if show_timestamps {
+                                                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                                                ui.weak(result.item.relative_timestamp())
+                                                                    .on_hover_text(result.item.formatted_timestamp());
+                                                            });
+                                                        }
                                                     }).response
                                                 },
                                                 _ => {
                                                     // Regular text-based items
                                                     ui.horizontal(|ui| {
                                                         let item_number = display_index + 1;
+                                                        let pin = if result.item.pinned { "📌 " } else { "" };
                                                         let preview_text = result.item.clean_preview(50);
-                                                        ui.label(format!("{item_number}. {preview_text}"))
+                                                        let response = ui.label(format!("{item_number}. {pin}{preview_text}"));
+                                                        if !result.item.tags.is_empty() {
+                                                            ui.weak(result.item.tags_label());
+                                                        }
+                                                        if show_timestamps {
+                                                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                                                ui.weak(result.item.relative_timestamp())
+                                                                    .on_hover_text(result.item.formatted_timestamp());
+                                                            });
+                                                        }
+                                                        response
                                                     }).response
                                                 }
                                             }
@@ -526,9 +1489,12 @@ impl eframe::App for PopupApp {
                                     item_response.scroll_to_me(Some(egui::Align::Center));
                                 }
 
-                                // Handle single click to select
+                                // Handle single click to select, Ctrl+Click to add to the multi-selection
                                 if item_response.clicked() {
                                     self.selected_index = display_index;
+                                    if ui.input(|i| i.modifiers.command) {
+                                        multi_toggle = Some(result.index);
+                                    }
                                 }
 
                                 // Handle double click to select and close
@@ -545,15 +1511,40 @@ impl eframe::App for PopupApp {
                                     ui.separator();
                                 }
                             }
+
+                            if self.total_results > self.search_results.len() {
+                                ui.add_space(4.0);
+                                ui.weak(format!("Showing {} of {}", self.search_results.len(), self.total_results));
+                            }
                         });
 
                     // Handle the copy operation after the borrow ends
                     if should_copy {
                         if let Some(index) = copy_index {
                             self.selected_index = index;
-                            self.copy_selected_item();
+                            self.copy_selected_item(false);
                         }
                     }
+
+                    if let Some(history_index) = multi_toggle {
+                        self.toggle_multi_selection(history_index);
+                    }
+
+                    // Action menu for the multi-selection: join text items with a separator
+                    if !self.multi_selection.is_empty() {
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} selected, join with:", self.multi_selection.len()));
+                            for (label, separator) in COMBINE_SEPARATORS {
+                                if ui.button(label).clicked() {
+                                    self.copy_combined_selection(separator);
+                                }
+                            }
+                            if ui.button("✕").on_hover_text("Clear selection").clicked() {
+                                self.multi_selection.clear();
+                            }
+                        });
+                    }
                 });
             });
 
@@ -562,7 +1553,34 @@ impl eframe::App for PopupApp {
 
         // Method 1: Check raw events
         for event in &input.events {
+            // Arrow keys and Enter are consumed even when they do nothing, rather than
+            // falling through to later arms as they would with match guards
+            #[allow(clippy::collapsible_match)]
             match event {
+                egui::Event::Key {
+                    key: egui::Key::Escape,
+                    pressed: true,
+                    ..
+                } if self.tag_input.is_some() => {
+                    // Cancel tagging without closing the popup
+                    self.tag_input = None;
+                }
+                egui::Event::Key {
+                    key: egui::Key::Enter,
+                    pressed: true,
+                    ..
+                } if self.tag_input.is_some() => {
+                    self.apply_tag_input();
+                }
+                egui::Event::Key {
+                    key: egui::Key::T,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.command && !self.search_results.is_empty() => {
+                    // Ctrl+T starts typing a tag for the highlighted item
+                    self.tag_input = Some(String::new());
+                }
                 egui::Event::Key {
                     key: egui::Key::Escape,
                     pressed: true,
@@ -572,6 +1590,15 @@ impl eframe::App for PopupApp {
                     self.should_close = true;
                     self.close_requested = true;
                 }
+                egui::Event::Key {
+                    key: egui::Key::ArrowUp,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.command => {
+                    // Ctrl+Up moves the highlighted item to the top without copying it
+                    self.promote_selected_item();
+                }
                 egui::Event::Key {
                     key: egui::Key::ArrowUp,
                     pressed: true,
@@ -590,17 +1617,101 @@ impl eframe::App for PopupApp {
                         self.selected_index += 1;
                     }
                 }
+                egui::Event::Key {
+                    key:
+                        key @ (egui::Key::PageUp
+                        | egui::Key::PageDown
+                        | egui::Key::Home
+                        | egui::Key::End),
+                    pressed: true,
+                    ..
+                } if self.tag_input.is_none() => {
+                    let len = self.search_results.len();
+                    let page = rows_per_page(self.config.popup_height);
+                    self.selected_index = match key {
+                        egui::Key::PageUp => self.selected_index.saturating_sub(page),
+                        egui::Key::PageDown => clamp_selection(self.selected_index + page, len),
+                        egui::Key::Home => 0,
+                        _ => len.saturating_sub(1),
+                    };
+                }
+                egui::Event::Key {
+                    key: egui::Key::Space,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.command && self.tag_input.is_none() => {
+                    // Ctrl+Space adds or removes the highlighted item from the multi-selection
+                    if let Some(result) = self.search_results.get(self.selected_index) {
+                        let history_index = result.index;
+                        self.toggle_multi_selection(history_index);
+                    }
+                }
+                egui::Event::Key {
+                    key: egui::Key::Enter,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.command && !self.multi_selection.is_empty() => {
+                    // Ctrl+Enter copies the multi-selection joined with newlines
+                    self.copy_combined_selection("\n");
+                }
                 egui::Event::Key {
                     key: egui::Key::Enter,
                     pressed: true,
+                    modifiers,
                     ..
                 } => {
                     if !self.search_results.is_empty()
                         && self.selected_index < self.search_results.len()
                     {
-                        self.copy_selected_item();
+                        // Shift+Enter copies without formatting
+                        self.copy_selected_item(modifiers.shift);
                     }
                 }
+                egui::Event::Key {
+                    key: egui::Key::I,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.command => {
+                    // Ctrl+I copies the item's metadata instead of its content
+                    self.copy_selected_metadata();
+                }
+                egui::Event::Key {
+                    key: egui::Key::O,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.command => {
+                    // Ctrl+O opens a link item in the browser
+                    self.open_selected_url();
+                }
+                egui::Event::Key {
+                    key: egui::Key::P,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.command => {
+                    // Ctrl+P pins/unpins the item so it is never evicted
+                    self.toggle_selected_pin();
+                }
+                egui::Event::Key {
+                    key: egui::Key::Backspace,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.command && self.tag_input.is_none() => {
+                    // Ctrl+Backspace clears the whole search instead of one word
+                    self.clear_search();
+                }
+                egui::Event::Key {
+                    key: egui::Key::Delete,
+                    pressed: true,
+                    ..
+                } if self.tag_input.is_none() => {
+                    self.delete_selected_item();
+                }
                 _ => {}
             }
         }
@@ -608,9 +1719,16 @@ impl eframe::App for PopupApp {
         // Request repaint only when there's actual UI interaction (reduce CPU usage)
         let needs_repaint = !self.search_text.is_empty()
             || self.selected_index > 0
-            || !self.search_results.is_empty();
+            || !self.search_results.is_empty()
+            || self.pending_snapshot.is_some(); // Keep polling until the history arrives
 
-        if !self.should_close && !self.close_requested && needs_repaint {
+        self.remember_selection();
+        self.remember_position(ctx);
+
+        if self.should_close || self.close_requested {
+            // Run one more frame so the window gets closed
+            ctx.request_repaint();
+        } else if needs_repaint {
             ctx.request_repaint_after(std::time::Duration::from_millis(16)); // ~60 FPS when needed
         }
     }
@@ -626,10 +1744,214 @@ impl eframe::App for PopupApp {
     }
 }
 
-/// Global hotkey manager for the popup
+/// Text shown for `item` in the preview pane: its full display content (secrets stay
+/// masked), file paths one per line, cut to `PREVIEW_PANE_MAX_CHARS` characters
+pub fn preview_pane_text(item: &ClipboardItem) -> String {
+    let text = match &item.content {
+        ClipboardContentType::Files(files) => files.join("\n"),
+        _ => item.display_content(),
+    };
+    match text.char_indices().nth(PREVIEW_PANE_MAX_CHARS) {
+        Some((cut, _)) => format!("{}...", &text[..cut]),
+        None => text,
+    }
+}
+
+/// Pattern of a `/pattern/` regex search query, or `None` for a plain text query
+pub fn regex_query(query: &str) -> Option<&str> {
+    query
+        .strip_prefix('/')
+        .and_then(|rest| rest.strip_suffix('/'))
+        .filter(|pattern| !pattern.is_empty())
+}
+
+/// A remembered selection moved onto a list of `len` rows; the history may have shrunk
+/// since it was remembered
+pub fn clamp_selection(index: usize, len: usize) -> usize {
+    index.min(len.saturating_sub(1))
+}
+
+/// Number of history rows that fit in a popup `popup_height` pixels tall, i.e. how far
+/// Page Up/Page Down move the selection (always at least one row)
+pub fn rows_per_page(popup_height: f32) -> usize {
+    ((popup_height - LIST_TOP_MARGIN) / ROW_HEIGHT)
+        .floor()
+        .max(1.0) as usize
+}
+
+/// Keep only results captured after `since` (e.g. the last time the popup was closed)
+pub fn filter_new_since(results: Vec<SearchResult>, since: DateTime<Utc>) -> Vec<SearchResult> {
+    results
+        .into_iter()
+        .filter(|result| result.item.timestamp > since)
+        .collect()
+}
+
+/// Colors of the popup for one resolved theme
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PopupPalette {
+    pub dark: bool,
+    pub background: egui::Color32,
+    pub border: egui::Color32,
+    pub text: egui::Color32,
+    pub input_background: egui::Color32,
+    pub input_hover: egui::Color32,
+    pub input_border: egui::Color32,
+    /// Background of every other history row
+    pub row_stripe: egui::Color32,
+    pub selected_row: egui::Color32,
+    pub multi_selected_row: egui::Color32,
+    pub error: egui::Color32,
+}
+
+impl PopupPalette {
+    pub const LIGHT: Self = Self {
+        dark: false,
+        background: egui::Color32::WHITE,
+        border: egui::Color32::from_rgb(70, 70, 70),
+        text: egui::Color32::BLACK,
+        input_background: egui::Color32::WHITE,
+        input_hover: egui::Color32::from_rgb(248, 248, 248),
+        input_border: egui::Color32::from_rgb(150, 150, 150),
+        row_stripe: egui::Color32::from_rgb(230, 230, 230),
+        selected_row: egui::Color32::from_rgb(200, 220, 255),
+        multi_selected_row: egui::Color32::from_rgb(210, 240, 210),
+        error: egui::Color32::from_rgb(200, 0, 0),
+    };
+
+    pub const DARK: Self = Self {
+        dark: true,
+        background: egui::Color32::from_rgb(30, 30, 30),
+        border: egui::Color32::from_rgb(110, 110, 110),
+        text: egui::Color32::from_rgb(230, 230, 230),
+        input_background: egui::Color32::from_rgb(18, 18, 18),
+        input_hover: egui::Color32::from_rgb(40, 40, 40),
+        input_border: egui::Color32::from_rgb(120, 120, 120),
+        row_stripe: egui::Color32::from_rgb(45, 45, 45),
+        selected_row: egui::Color32::from_rgb(40, 75, 125),
+        multi_selected_row: egui::Color32::from_rgb(40, 90, 50),
+        error: egui::Color32::from_rgb(255, 110, 110),
+    };
+
+    /// Palette for `theme`; `system_dark` is the OS dark mode preference, if known
+    pub fn for_theme(theme: Theme, system_dark: Option<bool>) -> Self {
+        let dark = match theme {
+            Theme::Light => false,
+            Theme::Dark => true,
+            Theme::SystemAuto => system_dark.unwrap_or(false),
+        };
+        if dark {
+            Self::DARK
+        } else {
+            Self::LIGHT
+        }
+    }
+
+    /// egui visuals for the whole popup, built on egui's own light or dark defaults
+    pub fn visuals(&self) -> egui::Visuals {
+        let mut visuals = if self.dark {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        visuals.window_fill = self.background;
+        visuals.window_stroke = egui::Stroke::new(2.0, self.border);
+        visuals.panel_fill = self.background;
+        visuals.override_text_color = Some(self.text);
+        visuals.faint_bg_color = self.row_stripe;
+        visuals.selection.bg_fill = self.selected_row;
+        visuals.error_fg_color = self.error;
+        visuals
+    }
+
+    /// Background of a history row: striped, unless it is highlighted or multi-selected
+    pub fn row_fill(
+        &self,
+        display_index: usize,
+        selected: bool,
+        multi_selected: bool,
+    ) -> egui::Color32 {
+        if selected {
+            self.selected_row
+        } else if multi_selected {
+            self.multi_selected_row
+        } else if display_index.is_multiple_of(2) {
+            self.background
+        } else {
+            self.row_stripe
+        }
+    }
+}
+
+/// Whether the OS prefers dark mode. eframe reports this on Windows and macOS; on
+/// Linux the GNOME color scheme setting is checked instead. `None` when unknown.
+fn system_prefers_dark(ctx: &egui::Context) -> Option<bool> {
+    if let Some(theme) = ctx.system_theme() {
+        return Some(theme == egui::Theme::Dark);
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let output = std::process::Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.interface", "color-scheme"])
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).contains("dark"))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Area of a monitor in virtual-screen coordinates; `left`/`top` can be negative
+/// for monitors placed left of or above the primary one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenBounds {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+/// Adjust a popup position so a popup of `size` stays fully inside `screen`
+pub fn clamp_to_screen(position: (f32, f32), size: (f32, f32), screen: ScreenBounds) -> (f32, f32) {
+    let (x, y) = position;
+    let (width, height) = size;
+
+    // Pull back from the right/bottom edges first, then make sure the left/top edges win
+    let x = x.min(screen.right - width).max(screen.left);
+    let y = y.min(screen.bottom - height).max(screen.top);
+    (x, y)
+}
+
+/// Whether a popup placed at `position` would open with its top-left corner (and so its
+/// title bar) on `screen`
+pub fn position_on_screen(position: (f32, f32), screen: ScreenBounds) -> bool {
+    let (x, y) = position;
+    (screen.left..screen.right).contains(&x) && (screen.top..screen.bottom).contains(&y)
+}
+
+/// Longest `wait_for_hotkey` blocks without a hotkey press on platforms that have no
+/// message loop, so tray menu clicks are noticed promptly
+#[cfg(not(windows))]
+const HOTKEY_WAIT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Global hotkey manager for the popup.
+/// Uses `RegisterHotKey` on Windows and the `global-hotkey` crate (X11) on Linux.
 pub struct HotkeyManager {
     #[allow(dead_code)] // Used in Windows-specific code
     hotkey_id: u32,
+
+    // `None` if there is no X11 display to grab keys on
+    #[cfg(target_os = "linux")]
+    global: Option<global_hotkey::GlobalHotKeyManager>,
+    #[cfg(target_os = "linux")]
+    registered: std::sync::Mutex<Option<global_hotkey::hotkey::HotKey>>,
 }
 
 impl Default for HotkeyManager {
@@ -640,35 +1962,54 @@ impl Default for HotkeyManager {
 
 impl HotkeyManager {
     pub fn new() -> Self {
-        Self { hotkey_id: 1 }
+        Self {
+            hotkey_id: 1,
+            #[cfg(target_os = "linux")]
+            global: global_hotkey::GlobalHotKeyManager::new().ok(),
+            #[cfg(target_os = "linux")]
+            registered: std::sync::Mutex::new(None),
+        }
     }
 
-    pub fn register_hotkey(&self, _hotkey: &str) -> Result<(), String> {
+    pub fn register_hotkey(&self, hotkey: &str) -> Result<(), String> {
+        let parsed = Hotkey::parse(hotkey)?;
+
         // For now, we'll implement Windows-specific hotkey registration
         #[cfg(windows)]
         {
             use std::ptr;
-            use winapi::um::winuser::{RegisterHotKey, MOD_CONTROL, MOD_SHIFT};
-
-            // Parse hotkey string (for now, hardcoded to Ctrl+Shift+V)
-            let modifiers = MOD_CONTROL | MOD_SHIFT;
-            let key = 0x56u32; // VK_V key code
+            use windows_sys::Win32::UI::Input::KeyboardAndMouse::RegisterHotKey;
 
             unsafe {
                 if RegisterHotKey(
                     ptr::null_mut(),
                     self.hotkey_id as i32,
-                    modifiers as u32,
-                    key,
+                    parsed.modifiers,
+                    parsed.key_code,
                 ) == 0
                 {
-                    return Err("Failed to register hotkey".to_string());
+                    return Err(format!("Failed to register hotkey {hotkey}"));
                 }
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(target_os = "linux")]
+        {
+            let manager = self.global.as_ref().ok_or_else(|| {
+                "Global hotkeys need an X11 display (Wayland sessions need XWayland)".to_string()
+            })?;
+            let binding = parsed
+                .to_global_hotkey()
+                .ok_or_else(|| format!("Hotkey {hotkey} is not supported on this platform"))?;
+            manager
+                .register(binding)
+                .map_err(|e| format!("Failed to register hotkey {hotkey}: {e}"))?;
+            *self.registered.lock().unwrap() = Some(binding);
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
+            let _ = parsed;
             eprintln!("Hotkey registration not implemented for this platform");
         }
 
@@ -679,40 +2020,71 @@ impl HotkeyManager {
         #[cfg(windows)]
         {
             use std::ptr;
-            use winapi::um::winuser::UnregisterHotKey;
+            use windows_sys::Win32::UI::Input::KeyboardAndMouse::UnregisterHotKey;
 
             unsafe {
                 UnregisterHotKey(ptr::null_mut(), self.hotkey_id as i32);
             }
         }
+
+        #[cfg(target_os = "linux")]
+        {
+            if let (Some(manager), Some(binding)) =
+                (&self.global, self.registered.lock().unwrap().take())
+            {
+                let _ = manager.unregister(binding);
+            }
+        }
     }
 
+    /// Block until the hotkey is pressed (`true`), or return `false` early so the caller
+    /// can look at other event sources such as the tray menu: on Windows after any other
+    /// window message (which is dispatched first, so the tray icon's window keeps
+    /// working), elsewhere after at most `HOTKEY_WAIT_TIMEOUT`.
     pub fn wait_for_hotkey(&self) -> bool {
         #[cfg(windows)]
         {
             use std::mem;
-            use winapi::um::winuser::{GetMessageW, MSG, WM_HOTKEY};
+            use windows_sys::Win32::UI::WindowsAndMessaging::{
+                DispatchMessageW, GetMessageW, TranslateMessage, MSG, WM_HOTKEY,
+            };
 
-            loop {
-                let mut msg: MSG = unsafe { mem::zeroed() };
-                let result = unsafe { GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) };
+            let mut msg: MSG = unsafe { mem::zeroed() };
+            let result = unsafe { GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) };
+            if result > 0 {
+                if msg.message == WM_HOTKEY && msg.wParam == self.hotkey_id as usize {
+                    return true;
+                }
+                unsafe {
+                    TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+            }
+        }
 
-                match result.cmp(&0) {
-                    std::cmp::Ordering::Greater => {
-                        if msg.message == WM_HOTKEY && msg.wParam == self.hotkey_id as usize {
-                            return true;
-                        }
+        #[cfg(target_os = "linux")]
+        {
+            use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
+
+            let registered_id = self.registered.lock().unwrap().map(|binding| binding.id());
+            if let Some(id) = registered_id {
+                // Events arrive from global-hotkey's X11 thread; no event loop needed here
+                while let Ok(event) =
+                    GlobalHotKeyEvent::receiver().recv_timeout(HOTKEY_WAIT_TIMEOUT)
+                {
+                    if event.id() == id && event.state() == HotKeyState::Pressed {
+                        return true;
                     }
-                    std::cmp::Ordering::Less => break,
-                    std::cmp::Ordering::Equal => {}
                 }
+                return false;
             }
+            std::thread::sleep(HOTKEY_WAIT_TIMEOUT);
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            // For non-Windows platforms, return false for now
-            std::thread::sleep(std::time::Duration::from_millis(100));
+            // For other platforms, return false for now
+            std::thread::sleep(HOTKEY_WAIT_TIMEOUT);
         }
 
         false
@@ -0,0 +1,72 @@
+//! Recognizing copied secrets (API keys, tokens, passwords), so they can be stored as
+//! `ClipboardContentType::Secret` and shown masked in the history.
+
+use crate::error::ClipboardError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Shown instead of a secret's value
+pub const SECRET_MASK: &str = "••••••••";
+
+/// A kind of secret and the regular expression that finds it in copied text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretPattern {
+    /// Shown in place of the value, e.g. "AWS access key"
+    pub name: String,
+    pub pattern: String,
+}
+
+impl SecretPattern {
+    pub fn new(name: &str, pattern: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+        }
+    }
+
+    /// Patterns for common, recognizable secrets
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("AWS access key", r"\b(AKIA|ASIA)[0-9A-Z]{16}\b"),
+            Self::new(
+                "JSON web token",
+                r"\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+",
+            ),
+            Self::new("GitHub token", r"\bgh[pousr]_[A-Za-z0-9]{36,}\b"),
+            Self::new("Private key", r"-----BEGIN [A-Z ]*PRIVATE KEY-----"),
+            Self::new("Password", r"(?i)(password|passwd|pwd)\s*[=:]\s*\S"),
+        ]
+    }
+}
+
+/// Compiled secret patterns, checked in order
+#[derive(Debug, Clone)]
+pub struct SecretDetector {
+    patterns: Vec<(String, Regex)>,
+}
+
+impl SecretDetector {
+    /// Fails with `ClipboardError::Config` naming the first pattern that isn't a valid
+    /// regular expression
+    pub fn new(patterns: &[SecretPattern]) -> Result<Self, ClipboardError> {
+        let patterns = patterns
+            .iter()
+            .map(|secret| {
+                Regex::new(&secret.pattern)
+                    .map(|regex| (secret.name.clone(), regex))
+                    .map_err(|e| {
+                        ClipboardError::Config(format!("secret pattern '{}': {e}", secret.name))
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Name of the first pattern found in `text`, if any
+    pub fn classify(&self, text: &str) -> Option<&str> {
+        self.patterns
+            .iter()
+            .find(|(_, regex)| regex.is_match(text))
+            .map(|(name, _)| name.as_str())
+    }
+}
@@ -1,9 +1,20 @@
-use crate::clipboard_item::ClipboardItem;
-use crate::clipboard_manager::ClipboardManager;
+use crate::clipboard_item::{
+    group_items, ClipboardItem, ContentTypeFilter, GroupHeader, GroupMode, ItemSummary,
+};
+use crate::clipboard_manager::{tag_query, ClipboardManager, ContentLimits, UsageStats};
+use crate::config::PopupConfig;
+use crate::error::ClipboardError;
+use crate::export::{self, ExportFormat};
 use crate::monitor::{ClipboardEvent, ClipboardMonitor};
-use std::io;
-use std::sync::Arc;
+use crate::system_clipboard::SystemClipboard;
+use crate::thumbnail::ThumbnailCache;
+use chrono::{DateTime, Local, Utc};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 
 /// Core service that provides all clipboard management functionality
 /// This is completely UI-agnostic and can be used by any interface (console, desktop, web, etc.)
@@ -11,47 +22,209 @@ use tokio::sync::broadcast;
 pub struct ClipboardService {
     manager: Arc<ClipboardManager>,
     monitor: Option<Arc<ClipboardMonitor>>,
+    // The running monitor loop, shared by clones so any of them can stop it
+    monitor_task: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl ClipboardService {
     /// Create a new clipboard service instance
-    pub async fn new() -> io::Result<Self> {
-        let manager = Arc::new(ClipboardManager::new().await?);
-        let monitor = Arc::new(ClipboardMonitor::new(Arc::clone(&manager)));
+    pub async fn new() -> Result<Self, ClipboardError> {
+        Ok(Self::with_monitor(ClipboardManager::new().await?))
+    }
+
+    /// Like `new`, copying to `clipboard` instead of the default system clipboard
+    pub async fn new_with_clipboard(
+        clipboard: Arc<dyn SystemClipboard>,
+    ) -> Result<Self, ClipboardError> {
+        Ok(Self::with_monitor(
+            ClipboardManager::new().await?.with_clipboard(clipboard),
+        ))
+    }
+
+    /// Create a service whose history is kept only in memory: nothing is read from or
+    /// written to disk, and the history is gone when the service is dropped
+    pub async fn new_ephemeral() -> Result<Self, ClipboardError> {
+        Ok(Self::with_monitor(ClipboardManager::new_ephemeral().await?))
+    }
 
-        Ok(Self {
+    /// Like `new`, but returns as soon as the newest items are loaded and reads the rest
+    /// of the history in the background, so a large history doesn't hold up startup.
+    /// Exports and changes to the whole history wait until it is complete.
+    pub async fn new_streaming() -> Result<Self, ClipboardError> {
+        let service = Self::with_monitor(ClipboardManager::new_streaming().await?);
+        let manager = Arc::clone(&service.manager);
+        tokio::spawn(async move {
+            if let Err(e) = manager.finish_loading().await {
+                let _ = manager.event_sender().send(ClipboardEvent::Error {
+                    message: format!("The rest of the history couldn't be loaded, so changes won't be saved: {e}"),
+                });
+            }
+        });
+        Ok(service)
+    }
+
+    /// Wrap `manager` with a monitor configured from the config file
+    fn with_monitor(manager: ClipboardManager) -> Self {
+        // A broken config file is reported by the caller; just use the defaults here
+        let config = PopupConfig::load().unwrap_or_default();
+        let manager = Arc::new(manager);
+        let monitor = Arc::new(
+            ClipboardMonitor::new(Arc::clone(&manager))
+                .with_poll_interval(Duration::from_millis(config.poll_interval_ms))
+                .with_secret_detector(config.secret_detector().ok().flatten())
+                .with_capture_priority(config.capture_priority)
+                .with_capture_on_start(config.capture_on_start)
+                .with_ignored_apps(config.ignored_apps),
+        );
+
+        Self {
             manager,
             monitor: Some(monitor),
-        })
+            monitor_task: Arc::default(),
+        }
     }
 
     /// Create a service instance with a provided manager (for testing)
-    #[allow(dead_code)] // Used by tests
     pub fn new_with_manager(manager: Arc<ClipboardManager>) -> Self {
         Self {
             manager,
             monitor: None,
+            monitor_task: Arc::default(),
         }
     }
 
-    /// Start background clipboard monitoring
+    /// The file the history is persisted to
+    pub fn data_file_path(&self) -> &std::path::Path {
+        self.manager.data_file_path()
+    }
+
+    /// Alias of `data_file_path`
+    pub fn get_storage_path(&self) -> &std::path::Path {
+        self.data_file_path()
+    }
+
+    /// Maximum number of items kept in the history
+    pub fn max_history_size(&self) -> usize {
+        self.manager.max_history_size()
+    }
+
+    /// Change the history size limit, trimming the history right away if it is now too long.
+    /// This only affects the running service; `PopupConfig::max_history_size` persists it.
+    pub async fn set_max_history_size(
+        &self,
+        max_history_size: usize,
+    ) -> Result<bool, ClipboardError> {
+        self.manager.set_max_history_size(max_history_size).await
+    }
+
+    /// Start background clipboard monitoring, unless it is already running.
     /// Returns a receiver for clipboard events
     pub fn start_monitoring(&mut self) -> Option<broadcast::Receiver<ClipboardEvent>> {
+        let monitor = self.monitor.as_ref()?;
+        let event_receiver = monitor.subscribe();
+        let mut monitor_task = self.lock_monitor_task();
+        if monitor_task.as_ref().is_none_or(|task| task.is_finished()) {
+            let monitor = Arc::clone(monitor);
+            *monitor_task = Some(tokio::spawn(async move {
+                monitor.start_monitoring().await;
+            }));
+        }
+        Some(event_receiver)
+    }
+
+    /// Stop background monitoring and wait for the monitor task to finish its current
+    /// clipboard check. Does nothing if monitoring isn't running; it can be started again.
+    pub async fn stop_monitoring(&self) {
+        let Some(monitor_task) = self.lock_monitor_task().take() else {
+            return;
+        };
         if let Some(monitor) = &self.monitor {
-            let event_receiver = monitor.subscribe();
-            let monitor_clone = Arc::clone(monitor);
-            let monitor_task = tokio::spawn(async move {
-                monitor_clone.start_monitoring().await;
-            });
-
-            // Store the task handle if needed for cleanup
-            // For now, we'll let it run until the service is dropped
-            std::mem::forget(monitor_task);
-
-            Some(event_receiver)
-        } else {
-            None
+            monitor.stop();
         }
+        // A task that panicked has stopped too
+        let _ = monitor_task.await;
+    }
+
+    fn lock_monitor_task(&self) -> std::sync::MutexGuard<'_, Option<JoinHandle<()>>> {
+        // A panic while holding the lock can't leave the handle inconsistent
+        self.monitor_task
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// A new receiver for clipboard events, independent of the one returned by
+    /// `start_monitoring`, so other consumers don't take events away from the UI.
+    /// `None` for a service without a monitor (`new_with_manager`).
+    pub fn subscribe(&self) -> Option<broadcast::Receiver<ClipboardEvent>> {
+        self.monitor.as_ref().map(|monitor| monitor.subscribe())
+    }
+
+    /// Stop recording clipboard changes (e.g. while copying passwords)
+    pub fn pause_monitoring(&self) {
+        if let Some(monitor) = &self.monitor {
+            monitor.pause();
+        }
+    }
+
+    /// Resume recording clipboard changes after `pause_monitoring`
+    pub fn resume_monitoring(&self) {
+        if let Some(monitor) = &self.monitor {
+            monitor.resume();
+        }
+    }
+
+    /// Change how often the clipboard is checked, while monitoring keeps running.
+    /// Short intervals (e.g. 100ms) record copies almost immediately but wake the CPU
+    /// more often; long ones (e.g. 2s) save battery but may miss a value that is replaced
+    /// before the next check. Intervals below `MIN_POLL_INTERVAL` are raised to it.
+    pub fn set_poll_interval(&self, interval: Duration) {
+        if let Some(monitor) = &self.monitor {
+            monitor.set_poll_interval(interval);
+        }
+    }
+
+    /// Current clipboard poll interval, or `None` for a service without a monitor
+    pub fn poll_interval(&self) -> Option<Duration> {
+        self.monitor.as_ref().map(|monitor| monitor.poll_interval())
+    }
+
+    /// Replace the list of apps whose copies are not recorded (process names or window
+    /// title substrings). Has no effect where the focused app can't be detected.
+    pub fn set_ignored_apps(&self, ignored_apps: Vec<String>) {
+        if let Some(monitor) = &self.monitor {
+            monitor.set_ignored_apps(ignored_apps);
+        }
+    }
+
+    /// Whether monitoring is currently paused
+    pub fn is_monitoring_paused(&self) -> bool {
+        self.monitor
+            .as_ref()
+            .is_some_and(|monitor| monitor.is_paused())
+    }
+
+    /// Persist any pending changes; call before exiting
+    pub async fn flush(&self) -> Result<(), ClipboardError> {
+        self.manager.flush().await
+    }
+
+    /// Add an item to the history without going through the system clipboard (e.g.
+    /// snippets generated by a script). The same size limit and deduplication apply as
+    /// to copies recorded by the monitor, and subscribers get an `ItemAdded` event.
+    pub async fn add_item(&self, item: ClipboardItem) -> Result<(), ClipboardError> {
+        let added = ClipboardEvent::item_added(&item);
+        #[cfg(feature = "ocr")]
+        let ocr_item = item.clone();
+        self.manager.add_clipboard_item(item).await?;
+        #[cfg(feature = "ocr")]
+        crate::ocr::spawn_extraction(Arc::clone(&self.manager), &ocr_item);
+        let _ = self.manager.event_sender().send(added);
+        Ok(())
+    }
+
+    /// Add a text item to the history, as `add_item` does
+    pub async fn add_text(&self, text: String) -> Result<(), ClipboardError> {
+        self.add_item(ClipboardItem::new_text(text)).await
     }
 
     /// Get the current clipboard history
@@ -59,20 +232,296 @@ impl ClipboardService {
         self.manager.get_history().await
     }
 
+    /// Up to `limit` history items starting at `offset`, newest first
+    pub async fn get_history_page(&self, offset: usize, limit: usize) -> Vec<ClipboardItem> {
+        self.manager.get_history_page(offset, limit).await
+    }
+
+    /// Summaries of up to `limit` history items starting at `offset`, newest first, for
+    /// lists that don't need the items' content
+    pub async fn get_history_previews(&self, offset: usize, limit: usize) -> Vec<ItemSummary> {
+        self.manager.get_history_previews(offset, limit).await
+    }
+
+    /// The history split into groups under headers (e.g. "Today", "Images"), for UIs
+    /// that show it grouped. `GroupMode::None` gives a single group with everything.
+    pub async fn grouped_history(&self, mode: GroupMode) -> Vec<(GroupHeader, Vec<ClipboardItem>)> {
+        group_items(self.get_history().await, mode, Local::now().date_naive())
+    }
+
+    /// Number of items in the history
+    pub async fn get_history_count(&self) -> usize {
+        self.manager.get_history_count().await
+    }
+
+    /// Item count and sizes of the history
+    pub async fn get_usage_stats(&self) -> UsageStats {
+        self.manager.get_usage_stats().await
+    }
+
+    /// The history size limit and the per-item size limit
+    pub fn get_content_limits(&self) -> ContentLimits {
+        self.manager.get_content_limits()
+    }
+
     /// Search clipboard history with exact text matching
     pub async fn search(&self, query: &str) -> Vec<(usize, ClipboardItem)> {
         self.manager.search_history(query).await
     }
 
+    /// Search clipboard history for items of one content type
+    pub async fn search_filtered(
+        &self,
+        query: &str,
+        filter: ContentTypeFilter,
+    ) -> Vec<(usize, ClipboardItem)> {
+        self.manager.search_history_filtered(query, filter).await
+    }
+
+    /// Search the items copied in `[from, to)`
+    pub async fn search_in_range(
+        &self,
+        query: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<(usize, ClipboardItem)> {
+        self.manager.search_in_range(query, from, to).await
+    }
+
+    /// Search clipboard history with a regular expression
+    pub async fn regex_search(
+        &self,
+        pattern: &str,
+    ) -> Result<Vec<(usize, ClipboardItem)>, regex::Error> {
+        self.manager.regex_search_history(pattern).await
+    }
+
     /// Search clipboard history with fuzzy matching
     pub async fn fuzzy_search(&self, query: &str) -> Vec<(usize, ClipboardItem, i64)> {
         self.manager.fuzzy_search_history(query).await
     }
 
+    /// Fuzzy search returning only matches scoring at least `min_score`, and at most
+    /// `limit` of them
+    pub async fn fuzzy_search_with(
+        &self,
+        query: &str,
+        min_score: Option<i64>,
+        limit: Option<usize>,
+    ) -> Vec<(usize, ClipboardItem, i64)> {
+        self.manager
+            .fuzzy_search_history_with(query, min_score, limit)
+            .await
+    }
+
+    /// Import text entries from the native Windows clipboard history (Win+V).
+    /// Entries already present in the history are skipped; returns how many were added.
+    /// This is a no-op on other platforms and on Windows versions without the API.
+    pub async fn import_windows_history(&self) -> Result<usize, ClipboardError> {
+        let entries = tokio::task::spawn_blocking(crate::windows_history::read_entries)
+            .await
+            .map_err(|e| {
+                ClipboardError::ClipboardUnavailable(format!("Windows history import failed: {e}"))
+            })??;
+        self.manager.import_items(entries).await
+    }
+
+    /// Write the whole history to `path`, e.g. to move it to another machine
+    pub async fn export_history(
+        &self,
+        path: &Path,
+        format: ExportFormat,
+    ) -> Result<(), ClipboardError> {
+        self.manager.wait_until_loaded().await;
+        let items = self.manager.get_history().await;
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || export::export_items(&items, &path, format))
+            .await
+            .map_err(|e| ClipboardError::Io(std::io::Error::other(format!("Export failed: {e}"))))?
+    }
+
+    /// Write every plain text item to `writer` as one JSON object per line
+    /// (`{"id", "timestamp", "text"}`), newest first. Only the text items are taken from
+    /// the history, and they are written one at a time rather than built into a single
+    /// string. Returns the number of lines written.
+    pub async fn dump_text_jsonl<W>(&self, writer: &mut W) -> Result<usize, ClipboardError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.manager.wait_until_loaded().await;
+        let mut written = 0;
+        for item in self.manager.get_text_items().await {
+            if let Some(line) = export::text_jsonl_line(&item)? {
+                writer.write_all(&line).await?;
+                written += 1;
+            }
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    /// Merge the items of an export into the history, skipping content that is already there.
+    /// Returns how many items were added.
+    pub async fn import_history(&self, path: &Path) -> Result<usize, ClipboardError> {
+        let path = path.to_path_buf();
+        let items = tokio::task::spawn_blocking(move || export::read_export(&path))
+            .await
+            .map_err(|e| {
+                ClipboardError::Io(std::io::Error::other(format!("Import failed: {e}")))
+            })??;
+        self.manager.import_items(items).await
+    }
+
+    /// Cache of image thumbnails, shared by every popup
+    pub fn thumbnails(&self) -> Arc<ThumbnailCache> {
+        self.manager.thumbnails()
+    }
+
     /// Copy a specific item back to the system clipboard
-    pub async fn copy_to_clipboard(&self, index: usize) -> io::Result<bool> {
+    pub async fn copy_to_clipboard(&self, index: usize) -> Result<(), ClipboardError> {
         self.manager.copy_item_to_clipboard(index).await
     }
+
+    /// Copy an item back to the system clipboard without formatting
+    pub async fn copy_to_clipboard_as_text(&self, index: usize) -> Result<(), ClipboardError> {
+        self.manager.copy_item_to_clipboard_as_text(index).await
+    }
+
+    /// Copy the item with the given id back to the system clipboard
+    pub async fn copy_item_by_id(&self, id: &str) -> Result<(), ClipboardError> {
+        self.manager.copy_item_by_id(id).await
+    }
+
+    /// Copy the item with the given id back to the system clipboard without formatting
+    pub async fn copy_item_as_text_by_id(&self, id: &str) -> Result<(), ClipboardError> {
+        self.manager.copy_item_as_text_by_id(id).await
+    }
+
+    /// Find the single item whose id starts with `prefix`
+    pub async fn find_by_id_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<(usize, ClipboardItem), ClipboardError> {
+        self.manager.find_by_id_prefix(prefix).await
+    }
+
+    /// Remove every item from the history
+    pub async fn clear_history(&self) -> Result<(), ClipboardError> {
+        self.manager.clear_history().await
+    }
+
+    /// Bring back the items removed by the last `clear_history`, if nothing has been
+    /// added since. Returns whether anything was restored.
+    pub async fn undo_clear(&self) -> Result<bool, ClipboardError> {
+        self.manager.undo_clear().await
+    }
+
+    /// Remove a single item from the history
+    pub async fn delete_item(&self, index: usize) -> Result<bool, ClipboardError> {
+        self.manager.delete_item(index).await
+    }
+
+    /// Remove the item with the given id from the history
+    pub async fn delete_item_by_id(&self, id: &str) -> Result<bool, ClipboardError> {
+        self.manager.delete_item_by_id(id).await
+    }
+
+    /// Copy an item back to the system clipboard and then remove it from the history,
+    /// for paste-once workflows. If the copy fails, the item is kept.
+    pub async fn copy_and_remove(&self, index: usize) -> Result<(), ClipboardError> {
+        self.manager.copy_and_remove(index).await
+    }
+
+    /// Same as `copy_and_remove`, for the item with the given id
+    pub async fn copy_and_remove_by_id(&self, id: &str) -> Result<(), ClipboardError> {
+        self.manager.copy_and_remove_by_id(id).await
+    }
+
+    /// Pin or unpin an item so it survives the history size limit.
+    /// Returns the new pinned state, or `None` if there is no item at `index`.
+    pub async fn toggle_pin(&self, index: usize) -> Result<Option<bool>, ClipboardError> {
+        self.manager.toggle_pinned(index).await
+    }
+
+    /// Same as `toggle_pin`, for the item with the given id
+    pub async fn toggle_pin_by_id(&self, id: &str) -> Result<Option<bool>, ClipboardError> {
+        self.manager.toggle_pinned_by_id(id).await
+    }
+
+    /// Move an item to the top of the history without copying it.
+    /// Returns false if there is no item at `index`.
+    pub async fn promote_to_front(&self, index: usize) -> Result<bool, ClipboardError> {
+        self.manager.promote_to_front(index).await
+    }
+
+    /// Same as `promote_to_front`, for the item with the given id
+    pub async fn promote_to_front_by_id(&self, id: &str) -> Result<bool, ClipboardError> {
+        self.manager.promote_to_front_by_id(id).await
+    }
+
+    /// Attach a tag to an item. Returns false if the item doesn't exist, the tag is
+    /// invalid, or the item already has it.
+    pub async fn add_tag(&self, index: usize, tag: &str) -> Result<bool, ClipboardError> {
+        self.manager.add_tag(index, tag).await
+    }
+
+    /// Remove a tag from an item. Returns false if the item doesn't have it.
+    pub async fn remove_tag(&self, index: usize, tag: &str) -> Result<bool, ClipboardError> {
+        self.manager.remove_tag(index, tag).await
+    }
+
+    /// Same as `add_tag`, for the item with the given id
+    pub async fn add_tag_by_id(&self, id: &str, tag: &str) -> Result<bool, ClipboardError> {
+        self.manager.add_tag_by_id(id, tag).await
+    }
+
+    /// Same as `remove_tag`, for the item with the given id
+    pub async fn remove_tag_by_id(&self, id: &str, tag: &str) -> Result<bool, ClipboardError> {
+        self.manager.remove_tag_by_id(id, tag).await
+    }
+
+    /// Copy the text of several items joined with `separator`, in the order given
+    pub async fn copy_combined(
+        &self,
+        indices: &[usize],
+        separator: &str,
+    ) -> Result<(), ClipboardError> {
+        self.manager.copy_combined(indices, separator).await
+    }
+
+    /// Same as `copy_combined`, for the items with the given ids
+    pub async fn copy_combined_by_id(
+        &self,
+        ids: &[String],
+        separator: &str,
+    ) -> Result<(), ClipboardError> {
+        self.manager.copy_combined_by_id(ids, separator).await
+    }
+
+    /// Copy the text of several items joined with newlines, in the order given
+    pub async fn copy_concatenated(&self, indices: &[usize]) -> Result<(), ClipboardError> {
+        self.copy_combined(indices, "\n").await
+    }
+
+    /// Open a link item in the default browser; returns false if the item isn't a URL
+    pub async fn open_url(&self, index: usize) -> Result<bool, ClipboardError> {
+        self.manager.open_item_url(index).await
+    }
+
+    /// Same as `open_url`, for the item with the given id
+    pub async fn open_url_by_id(&self, id: &str) -> Result<bool, ClipboardError> {
+        self.manager.open_item_url_by_id(id).await
+    }
+
+    /// Copy an item's metadata summary (id, type, size, timestamp) instead of its content
+    pub async fn copy_item_metadata(&self, index: usize) -> Result<(), ClipboardError> {
+        self.manager.copy_item_metadata(index).await
+    }
+
+    /// Same as `copy_item_metadata`, for the item with the given id
+    pub async fn copy_item_metadata_by_id(&self, id: &str) -> Result<(), ClipboardError> {
+        self.manager.copy_item_metadata_by_id(id).await
+    }
 }
 
 /// Search result wrapper
@@ -80,13 +529,28 @@ impl ClipboardService {
 pub struct SearchResult {
     pub index: usize,
     pub item: ClipboardItem,
-    #[allow(dead_code)] // May be used for future search result ranking features
     pub score: Option<i64>, // None for exact text search, Some(score) for fuzzy search
 }
 
 impl ClipboardService {
-    /// Unified search method that returns both exact and fuzzy results
+    /// Unified search method that returns both exact and fuzzy results.
+    /// A `#tag` query lists the items with that tag as exact results.
     pub async fn search_unified(&self, query: &str) -> (Vec<SearchResult>, Vec<SearchResult>) {
+        if let Some(tag) = tag_query(query) {
+            let tagged = self
+                .manager
+                .search_by_tag(tag)
+                .await
+                .into_iter()
+                .map(|(index, item)| SearchResult {
+                    index,
+                    item,
+                    score: None,
+                })
+                .collect();
+            return (tagged, Vec::new());
+        }
+
         let exact_results = self.search(query).await;
         let fuzzy_results = self.fuzzy_search(query).await;
 
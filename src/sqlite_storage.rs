@@ -0,0 +1,212 @@
+//! SQLite history backend: one row per item, keyed by id, so adding an item is a
+//! single insert instead of rewriting the whole history file.
+
+use crate::clipboard_item::ClipboardItem;
+use crate::encryption::{self, PASSPHRASE_ENV_VAR};
+use crate::error::ClipboardError;
+use crate::storage::{data_dir, JsonStorage, Storage};
+use async_trait::async_trait;
+use rusqlite::{params, Connection};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Marks rows whose stored content hasn't been read yet, so the next save rewrites them
+const UNKNOWN_FINGERPRINT: u64 = 0;
+
+#[derive(Debug)]
+pub struct SqliteStorage {
+    db_file: PathBuf,
+    connection: Mutex<Connection>,
+    /// What is on disk: item id -> (fingerprint of the stored JSON, sequence number).
+    /// Newer items have higher sequence numbers.
+    rows: Mutex<HashMap<String, (u64, i64)>>,
+    /// JSON history to import on first run
+    legacy_json: Option<PathBuf>,
+}
+
+impl SqliteStorage {
+    /// Open `history.db` in the default data directory, importing an existing
+    /// `history.json` the first time. Rows are stored unencrypted, so this refuses
+    /// to open while `CLIPBOARD_HISTORY_PASSPHRASE` is set.
+    pub fn new() -> Result<Self, ClipboardError> {
+        if std::env::var(PASSPHRASE_ENV_VAR).is_ok_and(|passphrase| !passphrase.is_empty()) {
+            return Err(ClipboardError::Encryption(format!(
+                "The sqlite backend does not encrypt the history; unset {PASSPHRASE_ENV_VAR} or use the json backend"
+            )));
+        }
+        let dir = data_dir()?;
+        Self::new_with_file(dir.join("history.db"), Some(dir.join("history.json")))
+    }
+
+    /// Open (or create) the database at `db_file`. If the database is empty and
+    /// `legacy_json` exists, its items are imported on the first load.
+    pub fn new_with_file(
+        db_file: PathBuf,
+        legacy_json: Option<PathBuf>,
+    ) -> Result<Self, ClipboardError> {
+        if let Some(parent) = db_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let connection = Connection::open(&db_file)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS items (
+                 id   TEXT PRIMARY KEY,
+                 seq  INTEGER NOT NULL,
+                 item TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS items_seq ON items (seq);",
+        )?;
+
+        let rows = {
+            let mut statement = connection.prepare("SELECT id, seq FROM items")?;
+            let rows = statement
+                .query_map([], |row| {
+                    Ok((row.get(0)?, (UNKNOWN_FINGERPRINT, row.get(1)?)))
+                })?
+                .collect::<Result<HashMap<_, _>, _>>()?;
+            rows
+        };
+
+        Ok(Self {
+            db_file,
+            connection: Mutex::new(connection),
+            rows: Mutex::new(rows),
+            legacy_json,
+        })
+    }
+
+    fn fingerprint(json: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        json.hash(&mut hasher);
+        // Keep the sentinel free for rows that haven't been read
+        hasher.finish().max(1)
+    }
+
+    fn read_rows(&self) -> Result<VecDeque<ClipboardItem>, ClipboardError> {
+        let connection = self.connection.lock().unwrap();
+        let mut rows = self.rows.lock().unwrap();
+
+        let mut statement =
+            connection.prepare("SELECT id, seq, item FROM items ORDER BY seq DESC")?;
+        let stored = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut history = VecDeque::with_capacity(stored.len());
+        for (id, seq, json) in stored {
+            // Skip rows that no longer parse rather than losing the whole history
+            if let Ok(item) = serde_json::from_str::<ClipboardItem>(&json) {
+                rows.insert(id, (Self::fingerprint(&json), seq));
+                history.push_back(item);
+            }
+        }
+        Ok(history)
+    }
+
+    /// Bring the table in line with `history`, touching only rows that changed
+    fn write_rows(&self, history: &VecDeque<ClipboardItem>) -> Result<(), ClipboardError> {
+        let mut connection = self.connection.lock().unwrap();
+        let mut rows = self.rows.lock().unwrap();
+        let transaction = connection.transaction()?;
+
+        let mut next_seq = rows.values().map(|(_, seq)| *seq).max().unwrap_or(0) + 1;
+        let mut last_seq = i64::MIN;
+        let mut present = HashSet::with_capacity(history.len());
+
+        // Oldest first: an unchanged row can stay as long as it is still in order
+        for item in history.iter().rev() {
+            present.insert(item.id.as_str());
+            let json = serde_json::to_string(item)?;
+            let fingerprint = Self::fingerprint(&json);
+
+            if let Some(&(stored, seq)) = rows.get(&item.id) {
+                if stored == fingerprint && seq > last_seq {
+                    last_seq = seq;
+                    continue;
+                }
+            }
+
+            let seq = next_seq;
+            next_seq += 1;
+            transaction.execute(
+                "INSERT INTO items (id, seq, item) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(id) DO UPDATE SET seq = excluded.seq, item = excluded.item",
+                params![item.id, seq, json],
+            )?;
+            rows.insert(item.id.clone(), (fingerprint, seq));
+            last_seq = seq;
+        }
+
+        let removed: Vec<String> = rows
+            .keys()
+            .filter(|id| !present.contains(id.as_str()))
+            .cloned()
+            .collect();
+        for id in removed {
+            transaction.execute("DELETE FROM items WHERE id = ?1", params![id])?;
+            rows.remove(&id);
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Import the legacy JSON history into an empty database and move the file aside.
+    /// An encrypted history is left alone: the database would hold it in plaintext.
+    async fn migrate_legacy_json(&self) -> Result<Option<VecDeque<ClipboardItem>>, ClipboardError> {
+        let Some(legacy) = self.legacy_json.as_ref().filter(|path| path.exists()) else {
+            return Ok(None);
+        };
+
+        let mut header = Vec::new();
+        std::fs::File::open(legacy)?
+            .take(64)
+            .read_to_end(&mut header)?;
+        if encryption::is_encrypted(&header) {
+            return Err(ClipboardError::Encryption(format!(
+                "{} is encrypted and the sqlite backend stores items unencrypted; keep using the json backend",
+                legacy.display()
+            )));
+        }
+
+        let json = JsonStorage::new_with_file(legacy.clone())?;
+        let history = json.load_history().await?;
+        self.write_rows(&history)?;
+
+        // Keep the old file around, but make sure it is not imported twice
+        std::fs::rename(legacy, legacy.with_extension("json.migrated"))?;
+        Ok(Some(history))
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn load_history(&self) -> Result<VecDeque<ClipboardItem>, ClipboardError> {
+        let is_empty = self.rows.lock().unwrap().is_empty();
+        if is_empty {
+            if let Some(migrated) = self.migrate_legacy_json().await? {
+                return Ok(migrated);
+            }
+        }
+        self.read_rows()
+    }
+
+    async fn save_history(&self, history: &VecDeque<ClipboardItem>) -> Result<(), ClipboardError> {
+        self.write_rows(history)
+    }
+
+    fn get_data_file_path(&self) -> &Path {
+        &self.db_file
+    }
+}
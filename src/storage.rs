@@ -1,51 +1,723 @@
 use crate::clipboard_item::ClipboardItem;
-use std::collections::VecDeque;
+use crate::config::PopupConfig;
+use crate::encryption::{self, EncryptionKey, PASSPHRASE_ENV_VAR};
+use crate::error::ClipboardError;
+use crate::sqlite_storage::SqliteStorage;
+use async_trait::async_trait;
+use base64::prelude::*;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::de::{Deserializer as _, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
-use std::io;
-use std::path::PathBuf;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
 
-#[derive(Debug)]
-pub struct Storage {
-    data_file: PathBuf,
+/// Environment variable selecting the storage backend (`json`, `sqlite` or `memory`)
+pub const BACKEND_ENV_VAR: &str = "CLIPBOARD_HISTORY_BACKEND";
+/// Environment variable selecting the JSON save strategy (`full` or `incremental`)
+pub const SAVE_STRATEGY_ENV_VAR: &str = "CLIPBOARD_HISTORY_SAVE";
+/// Environment variable that turns on gzip compression of the history file (`1` or `true`)
+pub const COMPRESS_ENV_VAR: &str = "CLIPBOARD_HISTORY_COMPRESS";
+/// Environment variable overriding the directory history files are kept in
+pub const DIR_ENV_VAR: &str = "CLIPBOARD_HISTORY_DIR";
+/// Logged changes before the incremental log is compacted into the main file
+pub const DEFAULT_COMPACT_EVERY: usize = 50;
+
+/// Where the clipboard history is persisted
+#[async_trait]
+pub trait Storage: Send + Sync + std::fmt::Debug {
+    async fn load_history(&self) -> Result<VecDeque<ClipboardItem>, ClipboardError>;
+
+    /// Load the history newest first, passing it to `on_batch` in batches of at most
+    /// `batch_size` items as they are read, so the start of a large history can be used
+    /// before the rest is loaded. By default everything is loaded, then passed on at once.
+    async fn load_history_batched(
+        &self,
+        _batch_size: usize,
+        on_batch: &mut (dyn FnMut(Vec<ClipboardItem>) + Send),
+    ) -> Result<(), ClipboardError> {
+        let history = self.load_history().await?;
+        if !history.is_empty() {
+            on_batch(history.into());
+        }
+        Ok(())
+    }
+
+    async fn save_history(&self, history: &VecDeque<ClipboardItem>) -> Result<(), ClipboardError>;
+
+    /// Write out anything that is only buffered or logged (e.g. on clean shutdown)
+    async fn flush(&self, _history: &VecDeque<ClipboardItem>) -> Result<(), ClipboardError> {
+        Ok(())
+    }
+
+    /// The file the history is stored in
+    fn get_data_file_path(&self) -> &Path;
+
+    /// Whether something else (e.g. another running instance) wrote the history since
+    /// this storage last loaded or saved it
+    fn changed_externally(&self) -> bool {
+        false
+    }
+}
+
+/// Open the storage backend selected by `CLIPBOARD_HISTORY_BACKEND` (JSON by default)
+pub fn default_storage() -> Result<Box<dyn Storage>, ClipboardError> {
+    match std::env::var(BACKEND_ENV_VAR).as_deref() {
+        Ok("sqlite") => Ok(Box::new(SqliteStorage::new()?)),
+        Ok("memory") => Ok(in_memory()),
+        _ => Ok(Box::new(JsonStorage::new()?)),
+    }
+}
+
+/// Storage that keeps nothing: the history lives only in memory and is gone on exit
+pub fn in_memory() -> Box<dyn Storage> {
+    Box::new(MemoryStorage)
+}
+
+/// The directory for history files, created if needed: `CLIPBOARD_HISTORY_DIR` if set,
+/// else `data_dir` from the config file, else the platform data directory
+pub fn data_dir() -> Result<PathBuf, ClipboardError> {
+    let from_env = std::env::var_os(DIR_ENV_VAR).map(PathBuf::from);
+    // A broken config file is reported by the caller; just use the defaults here
+    let from_config = PopupConfig::load().unwrap_or_default().data_dir;
+    let data_dir = resolve_data_dir(from_env.or(from_config));
+
+    fs::create_dir_all(&data_dir)?;
+    Ok(data_dir)
 }
 
-impl Storage {
-    pub fn new() -> io::Result<Self> {
-        let data_dir = dirs::data_dir()
+/// Where history files go given an optional override. Relative overrides are taken
+/// relative to the executable's directory, so a portable install can keep its history
+/// next to the program.
+pub fn resolve_data_dir(custom: Option<PathBuf>) -> PathBuf {
+    match custom.filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) if dir.is_relative() => std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|parent| parent.join(&dir)))
+            .unwrap_or(dir),
+        Some(dir) => dir,
+        None => dirs::data_dir()
             .unwrap_or_else(|| PathBuf::from("."))
-            .join("clipboard-history");
+            .join("clipboard-history"),
+    }
+}
+
+/// First bytes of a gzip stream; a JSON history file never starts with them
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
 
-        fs::create_dir_all(&data_dir)?;
-        let data_file = data_dir.join("history.json");
+fn compress(data: &[u8]) -> Result<Vec<u8>, ClipboardError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
 
-        Ok(Self { data_file })
+/// Deserialize a history array from `reader`, decompressing it first if it is gzipped,
+/// passing the items to `on_batch` in batches of at most `batch_size` as they are parsed.
+/// `None` if it can't be parsed; the batches before the broken part were passed on.
+fn parse_history(
+    mut reader: impl BufRead,
+    batch_size: usize,
+    on_batch: &mut dyn FnMut(Vec<ClipboardItem>),
+) -> Option<()> {
+    let batches = BatchVisitor {
+        batch_size,
+        on_batch,
+    };
+    if reader.fill_buf().ok()?.starts_with(GZIP_MAGIC) {
+        parse_batches(BufReader::new(GzDecoder::new(reader)), batches)
+    } else {
+        parse_batches(reader, batches)
+    }
+}
+
+fn parse_batches(reader: impl Read, batches: BatchVisitor<'_>) -> Option<()> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    deserializer.deserialize_seq(batches).ok()?;
+    // Only whitespace may follow the array
+    deserializer.end().ok()
+}
+
+/// Passes the items of a history array on in batches while it is deserialized
+struct BatchVisitor<'a> {
+    batch_size: usize,
+    on_batch: &'a mut dyn FnMut(Vec<ClipboardItem>),
+}
+
+impl<'de> Visitor<'de> for BatchVisitor<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a list of clipboard items")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut items: A) -> Result<(), A::Error> {
+        let mut batch = Vec::new();
+        while let Some(item) = items.next_element()? {
+            batch.push(item);
+            if batch.len() >= self.batch_size {
+                (self.on_batch)(std::mem::take(&mut batch));
+            }
+        }
+        if !batch.is_empty() {
+            (self.on_batch)(batch);
+        }
+        Ok(())
+    }
+}
+
+/// How `JsonStorage` persists changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaveStrategy {
+    /// Rewrite the whole file on every save
+    #[default]
+    Full,
+    /// Append changes to a log next to the file, and rewrite (compact) the file
+    /// only after `compact_every` logged changes or on `flush`
+    Incremental { compact_every: usize },
+}
+
+/// One change in the append-only log
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LogRecord {
+    /// A new item at the front of the history
+    Push {
+        item: ClipboardItem,
+    },
+    /// An existing item whose fields changed
+    Update {
+        item: ClipboardItem,
+    },
+    Remove {
+        id: String,
+    },
+}
+
+impl LogRecord {
+    /// Replaying is idempotent, so a log that survived a compaction is harmless
+    fn apply(self, history: &mut VecDeque<ClipboardItem>) {
+        match self {
+            LogRecord::Push { item } => {
+                if !history.iter().any(|existing| existing.id == item.id) {
+                    history.push_front(item);
+                }
+            }
+            LogRecord::Update { item } => {
+                if let Some(existing) = history.iter_mut().find(|existing| existing.id == item.id) {
+                    *existing = item;
+                }
+            }
+            LogRecord::Remove { id } => history.retain(|existing| existing.id != id),
+        }
+    }
+}
+
+/// What the file plus log currently hold, so a save can log just the difference
+#[derive(Debug, Default)]
+struct LogState {
+    /// (id, fingerprint) of every persisted item, newest first; `None` until known
+    persisted: Option<Vec<(String, u64)>>,
+    /// Records appended since the last compaction
+    pending: usize,
+}
+
+/// Stores the whole history as one JSON file, optionally compressed and encrypted
+#[derive(Debug)]
+pub struct JsonStorage {
+    data_file: PathBuf,
+    encryption: Option<EncryptionKey>,
+    compress: bool,
+    save_strategy: SaveStrategy,
+    log_state: Mutex<LogState>,
+    keep_backup: bool,
+    /// Set when the main file was unreadable and the history came from the backup,
+    /// so the next save doesn't overwrite the good backup with the broken file
+    restored_from_backup: AtomicBool,
+    /// Modification time of the file and log after our own last load or save
+    last_modified: Mutex<Option<SystemTime>>,
+    detect_external_changes: bool,
+}
+
+impl JsonStorage {
+    /// Open the default history file. If `CLIPBOARD_HISTORY_PASSPHRASE` is set,
+    /// the history is encrypted with it; `CLIPBOARD_HISTORY_SAVE=incremental`
+    /// switches to the append-only change log and `CLIPBOARD_HISTORY_COMPRESS=1`
+    /// gzips the file.
+    pub fn new() -> Result<Self, ClipboardError> {
+        let data_file = data_dir()?.join("history.json");
+
+        let mut storage = Self::with_data_file(data_file);
+        if std::env::var(SAVE_STRATEGY_ENV_VAR).as_deref() == Ok("incremental") {
+            storage.save_strategy = SaveStrategy::Incremental {
+                compact_every: DEFAULT_COMPACT_EVERY,
+            };
+        }
+        if matches!(std::env::var(COMPRESS_ENV_VAR).as_deref(), Ok("1" | "true")) {
+            storage.compress = true;
+        }
+        match std::env::var(PASSPHRASE_ENV_VAR) {
+            Ok(passphrase) if !passphrase.is_empty() => storage.with_passphrase(&passphrase),
+            _ => Ok(storage),
+        }
     }
 
     // Public method for testing - allows specifying a custom file path
-    #[allow(dead_code)] // Used by tests
-    pub fn new_with_file(file_path: PathBuf) -> io::Result<Self> {
+    pub fn new_with_file(file_path: PathBuf) -> Result<Self, ClipboardError> {
         if let Some(parent) = file_path.parent() {
             fs::create_dir_all(parent)?;
         }
-        Ok(Self {
-            data_file: file_path,
-        })
+        Ok(Self::with_data_file(file_path))
+    }
+
+    fn with_data_file(data_file: PathBuf) -> Self {
+        Self {
+            data_file,
+            encryption: None,
+            compress: false,
+            save_strategy: SaveStrategy::default(),
+            log_state: Mutex::new(LogState::default()),
+            keep_backup: true,
+            restored_from_backup: AtomicBool::new(false),
+            last_modified: Mutex::new(None),
+            detect_external_changes: true,
+        }
+    }
+
+    /// Encrypt the history with a key derived from `passphrase`.
+    /// An existing plaintext file is still readable and gets encrypted on the next save.
+    pub fn with_passphrase(mut self, passphrase: &str) -> Result<Self, ClipboardError> {
+        let existing = fs::read(&self.data_file).unwrap_or_default();
+        self.encryption = Some(EncryptionKey::for_existing(passphrase, &existing)?);
+        Ok(self)
+    }
+
+    /// Choose between rewriting the file on every save and appending to a change log
+    pub fn with_save_strategy(mut self, save_strategy: SaveStrategy) -> Self {
+        self.save_strategy = save_strategy;
+        self
     }
 
-    pub async fn load_history(&self) -> io::Result<VecDeque<ClipboardItem>> {
-        if self.data_file.exists() {
-            let content = fs::read_to_string(&self.data_file)?;
-            if let Ok(loaded) = serde_json::from_str::<VecDeque<ClipboardItem>>(&content) {
-                return Ok(loaded);
+    /// Gzip the history file on save. Base64 image data makes up most of a large
+    /// history, and compresses well. Compressed and uncompressed files both load
+    /// regardless of this setting.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Whether `changed_externally` watches for other writers of the file (on by
+    /// default). Turn it off for throwaway files that many managers share.
+    pub fn with_external_change_detection(mut self, detect: bool) -> Self {
+        self.detect_external_changes = detect;
+        self
+    }
+
+    /// Whether to keep the previous version of the file as `history.json.bak` (on by default)
+    pub fn with_backup(mut self, keep_backup: bool) -> Self {
+        self.keep_backup = keep_backup;
+        self
+    }
+
+    /// The previous good version of the history file
+    pub fn backup_file_path(&self) -> PathBuf {
+        self.data_file.with_extension("json.bak")
+    }
+
+    /// The append-only change log used by `SaveStrategy::Incremental`
+    pub fn log_file_path(&self) -> PathBuf {
+        self.data_file.with_extension("log")
+    }
+
+    /// Latest modification time of the history file and its change log
+    fn modified_time(&self) -> Option<SystemTime> {
+        [self.data_file.clone(), self.log_file_path()]
+            .iter()
+            .filter_map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+            .max()
+    }
+
+    fn record_modified_time(&self) {
+        *self.last_modified.lock().unwrap() = self.modified_time();
+    }
+
+    fn fingerprint(item: &ClipboardItem) -> Result<u64, ClipboardError> {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(item)?.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    fn fingerprints(
+        history: &VecDeque<ClipboardItem>,
+    ) -> Result<Vec<(String, u64)>, ClipboardError> {
+        history
+            .iter()
+            .map(|item| Ok((item.id.clone(), Self::fingerprint(item)?)))
+            .collect()
+    }
+
+    /// Read the main file, falling back to the backup if it is missing or corrupt
+    fn read_snapshot(&self) -> Result<VecDeque<ClipboardItem>, ClipboardError> {
+        if let Some(loaded) = self.read_history_file(&self.data_file)? {
+            return Ok(loaded);
+        }
+
+        if let Some(loaded) = self.read_history_file(&self.backup_file_path())? {
+            self.restored_from_backup
+                .store(self.data_file.exists(), Ordering::SeqCst);
+            return Ok(loaded);
+        }
+
+        Ok(VecDeque::new())
+    }
+
+    /// `Ok(None)` if the file doesn't exist or can't be parsed
+    fn read_history_file(
+        &self,
+        path: &Path,
+    ) -> Result<Option<VecDeque<ClipboardItem>>, ClipboardError> {
+        let mut history = VecDeque::new();
+        let parsed =
+            self.stream_history_file(path, usize::MAX, &mut |batch| history.extend(batch))?;
+        Ok(parsed.then_some(history))
+    }
+
+    /// Pass the items in the file at `path` to `on_batch` as they are parsed.
+    /// `Ok(false)` if the file doesn't exist or can't be parsed. Plain and compressed
+    /// files are parsed as they are read, so the whole file is never held in memory as
+    /// text; only encrypted files have to be read in full to be decrypted.
+    fn stream_history_file(
+        &self,
+        path: &Path,
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<ClipboardItem>),
+    ) -> Result<bool, ClipboardError> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        let mut reader = BufReader::new(file);
+        if encryption::is_encrypted(reader.fill_buf()?) {
+            let mut sealed = Vec::new();
+            reader.read_to_end(&mut sealed)?;
+            let content = self.open_sealed(sealed)?;
+            return Ok(parse_history(content.as_slice(), batch_size, on_batch).is_some());
+        }
+        Ok(parse_history(reader, batch_size, on_batch).is_some())
+    }
+
+    /// Decrypt `data` if it is encrypted; plaintext is passed through
+    fn open_sealed(&self, data: Vec<u8>) -> Result<Vec<u8>, ClipboardError> {
+        if !encryption::is_encrypted(&data) {
+            // Plaintext history from before encryption was enabled
+            return Ok(data);
+        }
+        let key = self.encryption.as_ref().ok_or_else(|| {
+            ClipboardError::Encryption(format!(
+                "History file is encrypted; set {PASSPHRASE_ENV_VAR} to open it"
+            ))
+        })?;
+        key.decrypt(&data)
+    }
+
+    fn seal(&self, plaintext: Vec<u8>) -> Result<Vec<u8>, ClipboardError> {
+        match &self.encryption {
+            Some(key) => key.encrypt(&plaintext),
+            None => Ok(plaintext),
+        }
+    }
+
+    /// Replay the change log on top of the snapshot. A torn last line (from a crash
+    /// while appending) ends the replay instead of failing the load.
+    fn replay_log(&self, history: &mut VecDeque<ClipboardItem>) -> Result<usize, ClipboardError> {
+        let file = match fs::File::open(self.log_file_path()) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut replayed = 0;
+        for line in BufReader::new(file).lines() {
+            let Ok(line) = line else { break };
+            let Ok(record) = self.decode_record(&line) else {
+                break;
+            };
+            record.apply(history);
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+
+    fn decode_record(&self, line: &str) -> Result<LogRecord, ClipboardError> {
+        let bytes = if self.encryption.is_some() || !line.starts_with('{') {
+            let sealed = BASE64_STANDARD
+                .decode(line)
+                .map_err(|e| ClipboardError::Decode(format!("Invalid log line: {e}")))?;
+            self.open_sealed(sealed)?
+        } else {
+            line.as_bytes().to_vec()
+        };
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn encode_record(&self, record: &LogRecord) -> Result<String, ClipboardError> {
+        let json = serde_json::to_vec(record)?;
+        match &self.encryption {
+            Some(key) => Ok(BASE64_STANDARD.encode(key.encrypt(&json)?)),
+            None => Ok(String::from_utf8_lossy(&json).into_owned()),
+        }
+    }
+
+    /// Rewrite the whole file and drop the log. The new file is written to
+    /// `history.json.tmp` and renamed over the old one, so a crash leaves either the
+    /// old or the new file, never a truncated one.
+    fn write_snapshot(&self, history: &VecDeque<ClipboardItem>) -> Result<(), ClipboardError> {
+        let json = if self.compress {
+            compress(&serde_json::to_vec(history)?)?
+        } else {
+            serde_json::to_string_pretty(history)?.into_bytes()
+        };
+        let sealed = self.seal(json)?;
+
+        let temp_file = self.data_file.with_extension("json.tmp");
+        let mut file = fs::File::create(&temp_file)?;
+        file.write_all(&sealed)?;
+        file.sync_all()?;
+        drop(file);
+
+        let restored = self.restored_from_backup.swap(false, Ordering::SeqCst);
+        if self.keep_backup && !restored && self.data_file.exists() {
+            fs::rename(&self.data_file, self.backup_file_path())?;
+        }
+        fs::rename(&temp_file, &self.data_file)?;
+
+        match fs::remove_file(self.log_file_path()) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        self.record_modified_time();
+        let mut state = self.log_state.lock().unwrap();
+        state.persisted = Some(Self::fingerprints(history)?);
+        state.pending = 0;
+        Ok(())
+    }
+
+    /// Log records that turn `persisted` into `current`, or `None` if the change
+    /// can't be expressed as pushes, updates and removals (e.g. a reorder)
+    fn diff(
+        persisted: &[(String, u64)],
+        current: &[(&ClipboardItem, u64)],
+    ) -> Option<Vec<LogRecord>> {
+        let known: HashMap<&str, u64> = persisted
+            .iter()
+            .map(|(id, fingerprint)| (id.as_str(), *fingerprint))
+            .collect();
+        let current_ids: HashSet<&str> = current.iter().map(|(item, _)| item.id.as_str()).collect();
+
+        // New items may only appear at the front, and the rest must keep their order
+        let new_count = current
+            .iter()
+            .take_while(|(item, _)| !known.contains_key(item.id.as_str()))
+            .count();
+        let kept: Vec<&str> = persisted
+            .iter()
+            .map(|(id, _)| id.as_str())
+            .filter(|id| current_ids.contains(id))
+            .collect();
+        let rest: Vec<&str> = current[new_count..]
+            .iter()
+            .map(|(item, _)| item.id.as_str())
+            .collect();
+        if kept != rest {
+            return None;
+        }
+
+        let mut records: Vec<LogRecord> = persisted
+            .iter()
+            .filter(|(id, _)| !current_ids.contains(id.as_str()))
+            .map(|(id, _)| LogRecord::Remove { id: id.clone() })
+            .collect();
+        // Oldest new item first, so replaying push_front restores the order
+        for (item, _) in current[..new_count].iter().rev() {
+            records.push(LogRecord::Push {
+                item: (*item).clone(),
+            });
+        }
+        for (item, fingerprint) in &current[new_count..] {
+            if known.get(item.id.as_str()) != Some(fingerprint) {
+                records.push(LogRecord::Update {
+                    item: (*item).clone(),
+                });
+            }
+        }
+        Some(records)
+    }
+
+    fn append_changes(
+        &self,
+        history: &VecDeque<ClipboardItem>,
+        compact_every: usize,
+    ) -> Result<(), ClipboardError> {
+        let current = history
+            .iter()
+            .map(|item| Ok((item, Self::fingerprint(item)?)))
+            .collect::<Result<Vec<_>, ClipboardError>>()?;
+
+        let records = {
+            let state = self.log_state.lock().unwrap();
+            match &state.persisted {
+                Some(persisted) => Self::diff(persisted, &current)
+                    .filter(|records| state.pending + records.len() <= compact_every),
+                None => None,
+            }
+        };
+        let Some(records) = records else {
+            return self.write_snapshot(history);
+        };
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = String::new();
+        for record in &records {
+            lines.push_str(&self.encode_record(record)?);
+            lines.push('\n');
+        }
+        let mut log = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_file_path())?;
+        log.write_all(lines.as_bytes())?;
+        log.sync_data()?;
+        drop(log);
+        self.record_modified_time();
+
+        let mut state = self.log_state.lock().unwrap();
+        state.persisted = Some(
+            current
+                .iter()
+                .map(|(item, fingerprint)| (item.id.clone(), *fingerprint))
+                .collect(),
+        );
+        state.pending += records.len();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for JsonStorage {
+    async fn load_history(&self) -> Result<VecDeque<ClipboardItem>, ClipboardError> {
+        let mut history = self.read_snapshot()?;
+        let replayed = self.replay_log(&mut history)?;
+        self.record_modified_time();
+
+        let mut state = self.log_state.lock().unwrap();
+        state.persisted = Some(Self::fingerprints(&history)?);
+        state.pending = replayed;
+        Ok(history)
+    }
+
+    async fn load_history_batched(
+        &self,
+        batch_size: usize,
+        on_batch: &mut (dyn FnMut(Vec<ClipboardItem>) + Send),
+    ) -> Result<(), ClipboardError> {
+        // Logged changes apply to the whole snapshot, so it has to be loaded first
+        if self.log_file_path().exists() {
+            let history = self.load_history().await?;
+            if !history.is_empty() {
+                on_batch(history.into());
+            }
+            return Ok(());
+        }
+
+        let mut persisted = Vec::new();
+        let mut fingerprint_error = None;
+        let mut passed_on = HashSet::new();
+        let mut pass_on = |batch: Vec<ClipboardItem>| {
+            // A main file found broken partway is read again from the backup
+            let batch: Vec<ClipboardItem> = batch
+                .into_iter()
+                .filter(|item| passed_on.insert(item.id.clone()))
+                .collect();
+            for item in &batch {
+                match Self::fingerprint(item) {
+                    Ok(fingerprint) => persisted.push((item.id.clone(), fingerprint)),
+                    Err(e) => fingerprint_error = Some(e),
+                }
             }
+            if !batch.is_empty() {
+                on_batch(batch);
+            }
+        };
+        if !self.stream_history_file(&self.data_file, batch_size, &mut pass_on)?
+            && self.stream_history_file(&self.backup_file_path(), batch_size, &mut pass_on)?
+        {
+            self.restored_from_backup
+                .store(self.data_file.exists(), Ordering::SeqCst);
+        }
+        if let Some(e) = fingerprint_error {
+            return Err(e);
         }
+        self.record_modified_time();
+
+        let mut state = self.log_state.lock().unwrap();
+        state.persisted = Some(persisted);
+        state.pending = 0;
+        Ok(())
+    }
 
+    async fn save_history(&self, history: &VecDeque<ClipboardItem>) -> Result<(), ClipboardError> {
+        match self.save_strategy {
+            SaveStrategy::Full => self.write_snapshot(history),
+            SaveStrategy::Incremental { compact_every } => {
+                self.append_changes(history, compact_every)
+            }
+        }
+    }
+
+    async fn flush(&self, history: &VecDeque<ClipboardItem>) -> Result<(), ClipboardError> {
+        if self.log_file_path().exists() {
+            self.write_snapshot(history)?;
+        }
+        Ok(())
+    }
+
+    fn get_data_file_path(&self) -> &Path {
+        &self.data_file
+    }
+
+    fn changed_externally(&self) -> bool {
+        self.detect_external_changes && *self.last_modified.lock().unwrap() != self.modified_time()
+    }
+}
+
+/// Storage for ephemeral sessions: starts empty and never writes anything
+#[derive(Debug, Default)]
+pub struct MemoryStorage;
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn load_history(&self) -> Result<VecDeque<ClipboardItem>, ClipboardError> {
         Ok(VecDeque::new())
     }
 
-    pub async fn save_history(&self, history: &VecDeque<ClipboardItem>) -> io::Result<()> {
-        let json = serde_json::to_string_pretty(history)?;
-        fs::write(&self.data_file, json)?;
+    async fn save_history(&self, _history: &VecDeque<ClipboardItem>) -> Result<(), ClipboardError> {
         Ok(())
     }
+
+    /// There is no file; this placeholder is what gets shown as the history location
+    fn get_data_file_path(&self) -> &Path {
+        Path::new(":memory:")
+    }
 }
@@ -0,0 +1,580 @@
+use crate::error::ClipboardError;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub use arboard::ImageData;
+
+/// Abstraction over the OS clipboard so capture and copy-back logic can be
+/// exercised without a real display server.
+pub trait SystemClipboard: Send + Sync + Debug {
+    fn get_text(&self) -> Result<String, ClipboardError>;
+    fn get_image(&self) -> Result<ImageData<'static>, ClipboardError>;
+    fn set_text(&self, text: String) -> Result<(), ClipboardError>;
+    fn set_image(&self, image: ImageData<'static>) -> Result<(), ClipboardError>;
+    fn set_html(&self, html: &str, alt_text: Option<&str>) -> Result<(), ClipboardError>;
+    fn get_html(&self) -> Result<String, ClipboardError>;
+    fn get_rtf(&self) -> Result<String, ClipboardError>;
+    fn set_rtf(&self, rtf: &str, alt_text: Option<&str>) -> Result<(), ClipboardError>;
+    /// Files copied in a file manager (`CF_HDROP` on Windows, a URI list on Linux)
+    fn get_files(&self) -> Result<Vec<PathBuf>, ClipboardError>;
+    /// Put files on the clipboard as file references, so pasting into a file manager
+    /// copies the files themselves rather than their paths as text
+    fn set_files(&self, paths: &[PathBuf]) -> Result<(), ClipboardError>;
+}
+
+/// System clipboard backed by `arboard`.
+/// A fresh handle is opened per call, since holding one open can block other apps on Windows.
+#[derive(Debug, Default)]
+pub struct ArboardClipboard {
+    #[cfg(target_os = "linux")]
+    wait_until_replaced: bool,
+}
+
+impl ArboardClipboard {
+    /// A clipboard whose writes return only once another app has put something else on
+    /// the clipboard. On Linux the content is served by the process that copied it, so a
+    /// short-lived process (e.g. the CLI) has to stay around until then for the copy to
+    /// be pasteable. Elsewhere the OS keeps the content and writes return right away.
+    pub fn waiting_until_replaced() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            wait_until_replaced: true,
+        }
+    }
+
+    fn open() -> Result<arboard::Clipboard, ClipboardError> {
+        arboard::Clipboard::new().map_err(|e| {
+            ClipboardError::ClipboardUnavailable(format!("Failed to access clipboard: {e}"))
+        })
+    }
+
+    /// Write `what` through `write` with a fresh handle
+    fn set(
+        &self,
+        what: &str,
+        write: impl FnOnce(arboard::Set<'_>) -> Result<(), arboard::Error>,
+    ) -> Result<(), ClipboardError> {
+        let mut clipboard = Self::open()?;
+        let set = clipboard.set();
+        #[cfg(target_os = "linux")]
+        let set = if self.wait_until_replaced {
+            arboard::SetExtLinux::wait(set)
+        } else {
+            set
+        };
+        write(set).map_err(|e| {
+            ClipboardError::ClipboardUnavailable(format!("Failed to set clipboard {what}: {e}"))
+        })
+    }
+}
+
+impl SystemClipboard for ArboardClipboard {
+    fn get_text(&self) -> Result<String, ClipboardError> {
+        Self::open()?
+            .get_text()
+            .map_err(|e| ClipboardError::ClipboardUnavailable(e.to_string()))
+    }
+
+    fn get_image(&self) -> Result<ImageData<'static>, ClipboardError> {
+        Self::open()?
+            .get_image()
+            .map_err(|e| ClipboardError::ClipboardUnavailable(e.to_string()))
+    }
+
+    fn set_text(&self, text: String) -> Result<(), ClipboardError> {
+        self.set("text", |set| set.text(text))
+    }
+
+    fn set_image(&self, image: ImageData<'static>) -> Result<(), ClipboardError> {
+        self.set("image", |set| set.image(image))
+    }
+
+    fn set_html(&self, html: &str, alt_text: Option<&str>) -> Result<(), ClipboardError> {
+        self.set("HTML", |set| set.html(html, alt_text))
+    }
+
+    fn get_html(&self) -> Result<String, ClipboardError> {
+        Self::open()?
+            .get()
+            .html()
+            .map_err(|e| ClipboardError::ClipboardUnavailable(e.to_string()))
+    }
+
+    // arboard has no rich text support, so RTF goes through the native clipboard API
+    #[cfg(windows)]
+    fn get_rtf(&self) -> Result<String, ClipboardError> {
+        windows_rtf::get_rtf()
+    }
+
+    #[cfg(not(windows))]
+    fn get_rtf(&self) -> Result<String, ClipboardError> {
+        Err(ClipboardError::ClipboardUnavailable(
+            "RTF clipboard access is only supported on Windows".to_string(),
+        ))
+    }
+
+    #[cfg(windows)]
+    fn set_rtf(&self, rtf: &str, alt_text: Option<&str>) -> Result<(), ClipboardError> {
+        windows_rtf::set_rtf(rtf, alt_text)
+    }
+
+    #[cfg(not(windows))]
+    fn set_rtf(&self, _rtf: &str, _alt_text: Option<&str>) -> Result<(), ClipboardError> {
+        Err(ClipboardError::ClipboardUnavailable(
+            "RTF clipboard access is only supported on Windows".to_string(),
+        ))
+    }
+
+    fn get_files(&self) -> Result<Vec<PathBuf>, ClipboardError> {
+        Self::open()?
+            .get()
+            .file_list()
+            .map_err(|e| ClipboardError::ClipboardUnavailable(e.to_string()))
+    }
+
+    // arboard can read file lists but not write them
+    #[cfg(windows)]
+    fn set_files(&self, paths: &[PathBuf]) -> Result<(), ClipboardError> {
+        windows_rtf::set_files(paths)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_files(&self, paths: &[PathBuf]) -> Result<(), ClipboardError> {
+        linux_files::set_files(paths)
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    fn set_files(&self, _paths: &[PathBuf]) -> Result<(), ClipboardError> {
+        Err(ClipboardError::ClipboardUnavailable(
+            "Copying files is only supported on Windows and Linux".to_string(),
+        ))
+    }
+}
+
+/// Files as a `text/uri-list`: one `file://` URI per line, CRLF-terminated.
+/// Fails for relative paths, which have no file URI.
+pub fn file_uri_list(paths: &[PathBuf]) -> Result<String, ClipboardError> {
+    paths
+        .iter()
+        .map(|path| {
+            url::Url::from_file_path(path)
+                .map(|uri| format!("{uri}\r\n"))
+                .map_err(|()| {
+                    ClipboardError::Encode(format!("{} is not an absolute path", path.display()))
+                })
+        })
+        .collect()
+}
+
+/// Linux file copies. The X11 and Wayland clipboards are served by the owning process
+/// for as long as the content stays on the clipboard, so the list is handed to
+/// `wl-copy` or `xclip`, which keep running in the background to do that.
+#[cfg(target_os = "linux")]
+mod linux_files {
+    use super::file_uri_list;
+    use crate::error::ClipboardError;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+
+    pub fn set_files(paths: &[PathBuf]) -> Result<(), ClipboardError> {
+        let uri_list = file_uri_list(paths)?;
+        let (program, args): (&str, &[&str]) = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            ("wl-copy", &["--type", "text/uri-list"])
+        } else {
+            (
+                "xclip",
+                &["-selection", "clipboard", "-target", "text/uri-list"],
+            )
+        };
+
+        let unavailable =
+            |e: std::io::Error| ClipboardError::ClipboardUnavailable(format!("{program}: {e}"));
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(unavailable)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(uri_list.as_bytes()).map_err(unavailable)?;
+        }
+        // Both tools fork once they have read the list, so this doesn't wait for the
+        // clipboard to change
+        let status = child.wait().map_err(unavailable)?;
+        if !status.success() {
+            return Err(ClipboardError::ClipboardUnavailable(format!(
+                "{program} exited with {status}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// RTF and file lists on the Windows clipboard. RTF is stored under the registered
+/// "Rich Text Format" format, files as `CF_HDROP`.
+#[cfg(windows)]
+mod windows_rtf {
+    use crate::error::ClipboardError;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::PathBuf;
+    use std::ptr;
+    use windows_sys::Win32::Foundation::GlobalFree;
+    use windows_sys::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, GetClipboardData, IsClipboardFormatAvailable,
+        OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows_sys::Win32::System::Memory::{
+        GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+    };
+    use windows_sys::Win32::System::Ole::{CF_HDROP, CF_UNICODETEXT};
+
+    fn unavailable(message: &str) -> ClipboardError {
+        ClipboardError::ClipboardUnavailable(message.to_string())
+    }
+
+    fn rtf_format() -> Result<u32, ClipboardError> {
+        let name: Vec<u16> = "Rich Text Format\0".encode_utf16().collect();
+        match unsafe { RegisterClipboardFormatW(name.as_ptr()) } {
+            0 => Err(unavailable("Failed to register the RTF clipboard format")),
+            format => Ok(format),
+        }
+    }
+
+    /// Keeps the clipboard open for this thread until dropped
+    struct OpenClipboardGuard;
+
+    impl OpenClipboardGuard {
+        fn open() -> Result<Self, ClipboardError> {
+            if unsafe { OpenClipboard(ptr::null_mut()) } == 0 {
+                return Err(unavailable("Failed to open clipboard"));
+            }
+            Ok(Self)
+        }
+    }
+
+    impl Drop for OpenClipboardGuard {
+        fn drop(&mut self) {
+            unsafe {
+                CloseClipboard();
+            }
+        }
+    }
+
+    pub fn get_rtf() -> Result<String, ClipboardError> {
+        let format = rtf_format()?;
+        if unsafe { IsClipboardFormatAvailable(format) } == 0 {
+            return Err(unavailable("No RTF on clipboard"));
+        }
+
+        let _guard = OpenClipboardGuard::open()?;
+        unsafe {
+            let handle = GetClipboardData(format);
+            if handle.is_null() {
+                return Err(unavailable("Failed to read clipboard RTF"));
+            }
+            let data = GlobalLock(handle) as *const u8;
+            if data.is_null() {
+                return Err(unavailable("Failed to lock clipboard RTF"));
+            }
+            let bytes = std::slice::from_raw_parts(data, GlobalSize(handle));
+            // RTF is 7-bit text terminated by a NUL byte
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            let rtf = String::from_utf8_lossy(&bytes[..end]).into_owned();
+            GlobalUnlock(handle);
+            Ok(rtf)
+        }
+    }
+
+    pub fn set_rtf(rtf: &str, alt_text: Option<&str>) -> Result<(), ClipboardError> {
+        let format = rtf_format()?;
+        let mut rtf_bytes = rtf.as_bytes().to_vec();
+        rtf_bytes.push(0);
+
+        let _guard = OpenClipboardGuard::open()?;
+        unsafe {
+            EmptyClipboard();
+            put_data(format, &rtf_bytes)?;
+            if let Some(text) = alt_text {
+                // Plain-text fallback for apps that don't understand RTF
+                let wide: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+                let wide_bytes =
+                    std::slice::from_raw_parts(wide.as_ptr() as *const u8, wide.len() * 2);
+                put_data(u32::from(CF_UNICODETEXT), wide_bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_files(paths: &[PathBuf]) -> Result<(), ClipboardError> {
+        // A DROPFILES header (offset of the file list, drop point, non-client flag,
+        // wide-char flag) followed by NUL-terminated UTF-16 paths and a final NUL
+        const HEADER_LEN: u32 = 20;
+        let mut drop_files = Vec::new();
+        drop_files.extend_from_slice(&HEADER_LEN.to_le_bytes());
+        drop_files.extend_from_slice(&[0; 12]);
+        drop_files.extend_from_slice(&1u32.to_le_bytes());
+        for path in paths {
+            for unit in path.as_os_str().encode_wide().chain(Some(0)) {
+                drop_files.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+        drop_files.extend_from_slice(&0u16.to_le_bytes());
+
+        let _guard = OpenClipboardGuard::open()?;
+        unsafe {
+            EmptyClipboard();
+            put_data(u32::from(CF_HDROP), &drop_files)
+        }
+    }
+
+    /// Copy `bytes` into a global memory block and hand it to the clipboard.
+    /// The clipboard must be open.
+    unsafe fn put_data(format: u32, bytes: &[u8]) -> Result<(), ClipboardError> {
+        let handle = GlobalAlloc(GMEM_MOVEABLE, bytes.len());
+        if handle.is_null() {
+            return Err(unavailable("Failed to allocate clipboard memory"));
+        }
+        let data = GlobalLock(handle) as *mut u8;
+        if data.is_null() {
+            GlobalFree(handle);
+            return Err(unavailable("Failed to lock clipboard memory"));
+        }
+        ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+        GlobalUnlock(handle);
+
+        // On success the clipboard owns the memory
+        if SetClipboardData(format, handle).is_null() {
+            GlobalFree(handle);
+            return Err(unavailable("Failed to set clipboard data"));
+        }
+        Ok(())
+    }
+}
+
+/// A write made to a [`MockClipboard`], recorded in call order
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClipboardWrite {
+    Text(String),
+    Image {
+        width: usize,
+        height: usize,
+        bytes: Vec<u8>,
+    },
+    Html {
+        html: String,
+        alt_text: Option<String>,
+    },
+    Rtf {
+        rtf: String,
+        alt_text: Option<String>,
+    },
+    Files(Vec<PathBuf>),
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    text: Option<String>,
+    image: Option<ImageData<'static>>,
+    html: Option<String>,
+    rtf: Option<String>,
+    files: Option<Vec<PathBuf>>,
+    writes: Vec<ClipboardWrite>,
+    panic_on_write: bool,
+    reject_files: bool,
+}
+
+/// In-memory clipboard for tests. Reads return whatever was last set (or seeded),
+/// and every write is recorded so tests can assert which set call was used.
+#[derive(Debug, Default)]
+pub struct MockClipboard {
+    state: Mutex<MockState>,
+}
+
+impl MockClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seed the text the next `get_text` call returns
+    pub fn put_text(&self, text: &str) {
+        self.state.lock().unwrap().text = Some(text.to_string());
+    }
+
+    /// Seed the image the next `get_image` call returns
+    pub fn put_image(&self, width: usize, height: usize, bytes: Vec<u8>) {
+        self.state.lock().unwrap().image = Some(ImageData {
+            width,
+            height,
+            bytes: bytes.into(),
+        });
+    }
+
+    /// Seed the HTML the next `get_html` call returns
+    pub fn put_html(&self, html: &str) {
+        self.state.lock().unwrap().html = Some(html.to_string());
+    }
+
+    /// Seed the RTF the next `get_rtf` call returns
+    pub fn put_rtf(&self, rtf: &str) {
+        self.state.lock().unwrap().rtf = Some(rtf.to_string());
+    }
+
+    /// Seed the files the next `get_files` call returns
+    pub fn put_files(&self, paths: &[&str]) {
+        self.state.lock().unwrap().files = Some(paths.iter().map(PathBuf::from).collect());
+    }
+
+    /// Empty the clipboard contents (recorded writes are kept)
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.text = None;
+        state.image = None;
+        state.html = None;
+        state.rtf = None;
+        state.files = None;
+    }
+
+    /// Make every subsequent set call panic, simulating a misbehaving backend
+    pub fn set_panic_on_write(&self, panic_on_write: bool) {
+        self.state.lock().unwrap().panic_on_write = panic_on_write;
+    }
+
+    /// Make `set_files` fail, like a platform without file clipboard support
+    pub fn set_reject_files(&self, reject_files: bool) {
+        self.state.lock().unwrap().reject_files = reject_files;
+    }
+
+    fn check_panic(&self) {
+        let panic_on_write = self.state.lock().unwrap().panic_on_write;
+        if panic_on_write {
+            panic!("mock clipboard write panicked");
+        }
+    }
+
+    /// All writes made so far, oldest first
+    pub fn writes(&self) -> Vec<ClipboardWrite> {
+        self.state.lock().unwrap().writes.clone()
+    }
+
+    pub fn last_write(&self) -> Option<ClipboardWrite> {
+        self.state.lock().unwrap().writes.last().cloned()
+    }
+}
+
+impl SystemClipboard for MockClipboard {
+    fn get_text(&self) -> Result<String, ClipboardError> {
+        let state = self.state.lock().unwrap();
+        state
+            .text
+            .clone()
+            .ok_or_else(|| ClipboardError::ClipboardUnavailable("No text on clipboard".to_string()))
+    }
+
+    fn get_image(&self) -> Result<ImageData<'static>, ClipboardError> {
+        let state = self.state.lock().unwrap();
+        state.image.clone().ok_or_else(|| {
+            ClipboardError::ClipboardUnavailable("No image on clipboard".to_string())
+        })
+    }
+
+    fn set_text(&self, text: String) -> Result<(), ClipboardError> {
+        self.check_panic();
+        let mut state = self.state.lock().unwrap();
+        state.files = None;
+        state.image = None;
+        state.html = None;
+        state.rtf = None;
+        state.text = Some(text.clone());
+        state.writes.push(ClipboardWrite::Text(text));
+        Ok(())
+    }
+
+    fn set_image(&self, image: ImageData<'static>) -> Result<(), ClipboardError> {
+        self.check_panic();
+        let mut state = self.state.lock().unwrap();
+        state.files = None;
+        state.text = None;
+        state.html = None;
+        state.rtf = None;
+        state.writes.push(ClipboardWrite::Image {
+            width: image.width,
+            height: image.height,
+            bytes: image.bytes.to_vec(),
+        });
+        state.image = Some(image);
+        Ok(())
+    }
+
+    fn set_html(&self, html: &str, alt_text: Option<&str>) -> Result<(), ClipboardError> {
+        self.check_panic();
+        let mut state = self.state.lock().unwrap();
+        state.files = None;
+        state.image = None;
+        state.html = Some(html.to_string());
+        state.rtf = None;
+        state.text = alt_text.map(str::to_string);
+        state.writes.push(ClipboardWrite::Html {
+            html: html.to_string(),
+            alt_text: alt_text.map(str::to_string),
+        });
+        Ok(())
+    }
+
+    fn get_html(&self) -> Result<String, ClipboardError> {
+        let state = self.state.lock().unwrap();
+        state
+            .html
+            .clone()
+            .ok_or_else(|| ClipboardError::ClipboardUnavailable("No HTML on clipboard".to_string()))
+    }
+
+    fn get_rtf(&self) -> Result<String, ClipboardError> {
+        let state = self.state.lock().unwrap();
+        state
+            .rtf
+            .clone()
+            .ok_or_else(|| ClipboardError::ClipboardUnavailable("No RTF on clipboard".to_string()))
+    }
+
+    fn set_rtf(&self, rtf: &str, alt_text: Option<&str>) -> Result<(), ClipboardError> {
+        self.check_panic();
+        let mut state = self.state.lock().unwrap();
+        state.files = None;
+        state.image = None;
+        state.html = None;
+        state.rtf = Some(rtf.to_string());
+        state.text = alt_text.map(str::to_string);
+        state.writes.push(ClipboardWrite::Rtf {
+            rtf: rtf.to_string(),
+            alt_text: alt_text.map(str::to_string),
+        });
+        Ok(())
+    }
+
+    fn get_files(&self) -> Result<Vec<PathBuf>, ClipboardError> {
+        let state = self.state.lock().unwrap();
+        state.files.clone().ok_or_else(|| {
+            ClipboardError::ClipboardUnavailable("No files on clipboard".to_string())
+        })
+    }
+
+    fn set_files(&self, paths: &[PathBuf]) -> Result<(), ClipboardError> {
+        self.check_panic();
+        let mut state = self.state.lock().unwrap();
+        if state.reject_files {
+            return Err(ClipboardError::ClipboardUnavailable(
+                "File lists are not supported".to_string(),
+            ));
+        }
+        state.text = None;
+        state.image = None;
+        state.html = None;
+        state.rtf = None;
+        state.files = Some(paths.to_vec());
+        state.writes.push(ClipboardWrite::Files(paths.to_vec()));
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use crate::clipboard_item::{ClipboardContentType, ClipboardItem};
+use crate::monitor::ClipboardMonitor;
+use base64::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Longest edge, in pixels, of the image thumbnails shown in the popup
+pub const THUMBNAIL_SIZE: u32 = 48;
+
+/// Small RGBA rendering of an image item, ready to upload as a texture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    /// Unpremultiplied RGBA pixels, row by row
+    pub rgba: Vec<u8>,
+}
+
+impl Thumbnail {
+    /// Decode an image item and shrink it to fit `THUMBNAIL_SIZE`.
+    /// `None` for items that aren't images or whose data can't be decoded.
+    /// Uses the thumbnail stored with the item when there is one, so the full image is
+    /// only decoded for items captured before thumbnails were stored.
+    pub fn render(item: &ClipboardItem) -> Option<Self> {
+        let ClipboardContentType::Image {
+            data, thumbnail, ..
+        } = &item.content
+        else {
+            return None;
+        };
+        thumbnail
+            .as_deref()
+            .and_then(|thumbnail| Self::decode(thumbnail, THUMBNAIL_SIZE))
+            .or_else(|| Self::decode(data, THUMBNAIL_SIZE))
+    }
+
+    /// Decode an image item's full data and shrink it to fit `max_edge`, for a bigger
+    /// view than the thumbnail. Images already smaller than that keep their size.
+    pub fn render_preview(item: &ClipboardItem, max_edge: u32) -> Option<Self> {
+        let ClipboardContentType::Image {
+            data,
+            width,
+            height,
+            ..
+        } = &item.content
+        else {
+            return None;
+        };
+        Self::decode(data, max_edge.min(*width.max(height)).max(1))
+    }
+
+    fn decode(base64_image: &str, max_edge: u32) -> Option<Self> {
+        let bytes = BASE64_STANDARD.decode(base64_image).ok()?;
+        let rgba = image::load_from_memory(&bytes)
+            .ok()?
+            .thumbnail(max_edge, max_edge)
+            .to_rgba8();
+        Some(Self {
+            width: rgba.width(),
+            height: rgba.height(),
+            rgba: rgba.into_raw(),
+        })
+    }
+}
+
+/// Base64 PNG thumbnail of raw RGBA pixels, to store with a captured image.
+/// `None` if the pixels don't match the size or can't be encoded.
+pub fn stored_thumbnail(rgba: &[u8], width: u32, height: u32) -> Option<String> {
+    let pixels = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(width, height, rgba)?;
+    let (thumb_width, thumb_height) =
+        ClipboardMonitor::scaled_dimensions(width, height, THUMBNAIL_SIZE);
+    let mut png = Vec::new();
+    image::imageops::thumbnail(&pixels, thumb_width.max(1), thumb_height.max(1))
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+    Some(BASE64_STANDARD.encode(png))
+}
+
+/// Thumbnails keyed by content hash, shared by every popup opened by the same process so
+/// images are only decoded and scaled once. Images that fail to decode are remembered
+/// too, so they aren't retried on every frame.
+#[derive(Debug, Default)]
+pub struct ThumbnailCache {
+    thumbnails: Mutex<HashMap<String, Option<Arc<Thumbnail>>>>,
+}
+
+impl ThumbnailCache {
+    /// The thumbnail of an image item, rendering and caching it on first use
+    pub fn get(&self, item: &ClipboardItem) -> Option<Arc<Thumbnail>> {
+        if let Some(cached) = self.lock().get(&item.content_hash) {
+            return cached.clone();
+        }
+        // Rendered without holding the lock; a concurrent render of the same item is
+        // harmless, the second insert just replaces an identical thumbnail
+        let thumbnail = Thumbnail::render(item).map(Arc::new);
+        self.lock()
+            .insert(item.content_hash.clone(), thumbnail.clone());
+        thumbnail
+    }
+
+    /// Render an image item's thumbnail ahead of time; other items are ignored
+    pub fn precompute(&self, item: &ClipboardItem) {
+        if matches!(item.content, ClipboardContentType::Image { .. }) {
+            self.get(item);
+        }
+    }
+
+    /// Drop thumbnails of items that are no longer in `history`
+    pub fn retain_history(&self, history: &VecDeque<ClipboardItem>) {
+        let live: HashSet<&str> = history
+            .iter()
+            .map(|item| item.content_hash.as_str())
+            .collect();
+        self.lock().retain(|hash, _| live.contains(hash.as_str()));
+    }
+
+    /// Number of cached entries, including images that failed to decode
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Option<Arc<Thumbnail>>>> {
+        // A panic while holding the lock can't leave the map inconsistent
+        self.thumbnails
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,136 @@
+//! Optional system tray icon (`tray` feature) with quick actions, for users who don't
+//! remember the hotkey. Menu clicks are queued by `tray-icon` and picked up by the main
+//! loop between hotkey waits, so they drive the same `ClipboardService` as the popup.
+
+use tray_icon::menu::{CheckMenuItem, Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+const OPEN_ID: &str = "open";
+const PAUSE_ID: &str = "pause";
+const CLEAR_ID: &str = "clear";
+const QUIT_ID: &str = "quit";
+
+/// Edge length, in pixels, of the generated tray icon
+const ICON_SIZE: u32 = 32;
+
+/// Something the user picked from the tray menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    OpenPopup,
+    /// Pause monitoring if it is running, resume it if it is paused
+    TogglePause,
+    ClearHistory,
+    Quit,
+}
+
+impl TrayAction {
+    fn from_menu_id(id: &MenuId) -> Option<Self> {
+        match id.as_ref() {
+            OPEN_ID => Some(Self::OpenPopup),
+            PAUSE_ID => Some(Self::TogglePause),
+            CLEAR_ID => Some(Self::ClearHistory),
+            QUIT_ID => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// The tray icon; it disappears when this is dropped (on Linux, when the process exits)
+pub struct Tray {
+    // On Windows the icon belongs to the main thread, whose message loop
+    // (`HotkeyManager::wait_for_hotkey`) delivers its clicks
+    #[cfg(not(target_os = "linux"))]
+    _icon: TrayIcon,
+}
+
+impl Tray {
+    /// Show the tray icon. `paused` is the initial state of the "Pause monitoring" check.
+    pub fn new(paused: bool) -> Result<Self, String> {
+        #[cfg(target_os = "linux")]
+        {
+            // GTK needs its own event loop on the thread that owns the icon, and the
+            // hotkey wait blocks the main thread
+            let (ready_sender, ready_receiver) = std::sync::mpsc::channel();
+            std::thread::Builder::new()
+                .name("tray".to_string())
+                .spawn(move || {
+                    let icon = gtk::init()
+                        .map_err(|e| format!("Failed to initialise GTK: {e}"))
+                        .and_then(|()| build_icon(paused));
+                    let started = icon.is_ok();
+                    let _ = ready_sender.send(icon.map(drop));
+                    if started {
+                        gtk::main();
+                    }
+                })
+                .map_err(|e| format!("Failed to start the tray thread: {e}"))?;
+            ready_receiver
+                .recv()
+                .map_err(|_| "Tray thread exited before creating the icon".to_string())??;
+            Ok(Self {})
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            Ok(Self {
+                _icon: build_icon(paused)?,
+            })
+        }
+    }
+
+    /// The next menu action picked since the last call, without blocking
+    pub fn try_recv(&self) -> Option<TrayAction> {
+        while let Ok(event) = MenuEvent::receiver().try_recv() {
+            if let Some(action) = TrayAction::from_menu_id(&event.id) {
+                return Some(action);
+            }
+        }
+        None
+    }
+}
+
+fn build_icon(paused: bool) -> Result<TrayIcon, String> {
+    let menu = Menu::new();
+    menu.append_items(&[
+        &MenuItem::with_id(OPEN_ID, "Open clipboard history", true, None),
+        &CheckMenuItem::with_id(PAUSE_ID, "Pause monitoring", true, paused, None),
+        &PredefinedMenuItem::separator(),
+        &MenuItem::with_id(CLEAR_ID, "Clear history", true, None),
+        &PredefinedMenuItem::separator(),
+        &MenuItem::with_id(QUIT_ID, "Quit", true, None),
+    ])
+    .map_err(|e| format!("Failed to build the tray menu: {e}"))?;
+
+    TrayIconBuilder::new()
+        .with_menu(Box::new(menu))
+        .with_tooltip("Clipboard history")
+        .with_icon(clipboard_icon()?)
+        .build()
+        .map_err(|e| format!("Failed to create the tray icon: {e}"))
+}
+
+/// A plain clipboard drawn in code, so no image file has to ship with the binary
+fn clipboard_icon() -> Result<Icon, String> {
+    const BOARD: [u8; 4] = [0x8B, 0x5A, 0x2B, 0xFF];
+    const PAPER: [u8; 4] = [0xF5, 0xF5, 0xF5, 0xFF];
+    const CLIP: [u8; 4] = [0x70, 0x70, 0x70, 0xFF];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let pixel = if (11..21).contains(&x) && (1..7).contains(&y) {
+                CLIP
+            } else if (8..24).contains(&x) && (8..28).contains(&y) {
+                PAPER
+            } else if (4..28).contains(&x) && (3..31).contains(&y) {
+                BOARD
+            } else {
+                CLEAR
+            };
+            rgba.extend_from_slice(&pixel);
+        }
+    }
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE)
+        .map_err(|e| format!("Failed to create the tray icon image: {e}"))
+}
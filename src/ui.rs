@@ -0,0 +1,484 @@
+//! Line-based console interface: browse, search and copy the history from a terminal,
+//! as an alternative to the popup (`--console`).
+
+use crate::clipboard_item::{group_items, ClipboardItem, GroupMode};
+use crate::clipboard_manager::parse_time_range;
+use crate::config::{ConsoleView, PopupConfig, DEFAULT_CONSOLE_MAX_RESULTS};
+use crate::error::ClipboardError;
+use crate::monitor::ClipboardEvent;
+use crate::service::ClipboardService;
+use chrono::Local;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::sync::broadcast;
+
+/// Environment variable that picks the interface; `console` is the same as `--console`
+pub const UI_ENV_VAR: &str = "CLIPBOARD_UI";
+
+const PREVIEW_CHARS: usize = 70;
+const COMPACT_PREVIEW_CHARS: usize = 40;
+/// The compact view lists this many times `max_results` items
+const COMPACT_ROWS_FACTOR: usize = 2;
+
+pub struct ConsoleInterface {
+    service: ClipboardService,
+    events: Option<broadcast::Receiver<ClipboardEvent>>,
+    group_mode: GroupMode,
+    // Items listed at once, in the history viewer and in search mode
+    max_results: usize,
+    view: ConsoleView,
+    // Config file the view is saved to when toggled
+    config_file: Option<PathBuf>,
+}
+
+impl ConsoleInterface {
+    /// `events` is the monitor's event stream (from `start_monitoring`); new captures are
+    /// announced between prompts
+    pub fn new(
+        service: ClipboardService,
+        events: Option<broadcast::Receiver<ClipboardEvent>>,
+    ) -> Self {
+        Self {
+            service,
+            events,
+            group_mode: GroupMode::None,
+            max_results: DEFAULT_CONSOLE_MAX_RESULTS,
+            view: ConsoleView::Detailed,
+            config_file: None,
+        }
+    }
+
+    /// List at most `max_results` items at a time (at least one)
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = max_results.max(1);
+        self
+    }
+
+    /// Start in the compact or the detailed view
+    pub fn with_view(mut self, view: ConsoleView) -> Self {
+        self.view = view;
+        self
+    }
+
+    /// Save the view to the config file at `path` whenever it is toggled
+    pub fn with_config_file(mut self, path: PathBuf) -> Self {
+        self.config_file = Some(path);
+        self
+    }
+
+    /// List the history under group headers (e.g. "Today", "Images")
+    pub fn with_group_mode(mut self, group_mode: GroupMode) -> Self {
+        self.group_mode = group_mode;
+        self
+    }
+
+    /// Run the interface on stdin/stdout until the user types `exit` or input ends
+    pub async fn run(mut self) -> io::Result<()> {
+        let input = BufReader::new(tokio::io::stdin());
+        self.run_with(input, &mut io::stdout()).await
+    }
+
+    /// Run the interface over any input and output (used by tests)
+    pub async fn run_with<R, W>(&mut self, input: R, output: &mut W) -> io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: Write,
+    {
+        let mut lines = input.lines();
+        writeln!(output, "📋 Clipboard history (console)")?;
+        writeln!(
+            output,
+            "History file: {} ({} items)",
+            self.service.get_storage_path().display(),
+            self.service.get_history_count().await
+        )?;
+
+        loop {
+            self.show_events(output)?;
+            write!(
+                output,
+                "\nPress Enter to view the history, or type 'exit': "
+            )?;
+            output.flush()?;
+            let Some(line) = lines.next_line().await? else {
+                return Ok(());
+            };
+            match line.trim() {
+                "exit" | "quit" => return Ok(()),
+                _ => self.history_viewer(&mut lines, output).await?,
+            }
+        }
+    }
+
+    async fn history_viewer<R, W>(
+        &mut self,
+        lines: &mut tokio::io::Lines<R>,
+        output: &mut W,
+    ) -> io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: Write,
+    {
+        loop {
+            self.show_events(output)?;
+            let rows = self.rows();
+            let (groups, total) = if self.group_mode == GroupMode::None {
+                // Ungrouped, only the items that get listed are fetched
+                let page = self.service.get_history_page(0, rows).await;
+                let total = self.service.get_history_count().await;
+                (
+                    group_items(page, GroupMode::None, Local::now().date_naive()),
+                    total,
+                )
+            } else {
+                let groups = self.service.grouped_history(self.group_mode).await;
+                let total = groups.iter().map(|(_, items)| items.len()).sum();
+                (groups, total)
+            };
+            // Ids of the listed items, in the order they are numbered
+            let mut shown: Vec<String> = Vec::new();
+            if groups.is_empty() {
+                writeln!(output, "\nNo clipboard history yet. Copy something first.")?;
+            } else {
+                writeln!(output, "\nRecent items:")?;
+                for (header, items) in groups {
+                    let remaining = rows - shown.len();
+                    if remaining == 0 {
+                        break;
+                    }
+                    if self.group_mode != GroupMode::None {
+                        writeln!(output, "── {} ──", header.label())?;
+                    }
+                    for item in items.into_iter().take(remaining) {
+                        print_item(output, self.view, shown.len() + 1, &item)?;
+                        shown.push(item.id);
+                    }
+                }
+                print_shown_count(output, shown.len(), total)?;
+            }
+            write!(
+                output,
+                "\nNumber to copy, 'id <prefix>' to copy by id, 's' to search, 'v' to switch view, 'c' to clear, 'u' to undo a clear, 'q' to go back: "
+            )?;
+            output.flush()?;
+
+            let Some(line) = lines.next_line().await? else {
+                return Ok(());
+            };
+            match line.trim() {
+                "q" | "quit" => return Ok(()),
+                "s" | "search" => self.search_mode(lines, output).await?,
+                "c" | "clear" => {
+                    let count = self.service.get_history_count().await;
+                    self.clear(lines, output, count).await?
+                }
+                "u" | "undo" => self.undo_clear(output).await?,
+                "v" | "view" => self.toggle_view(output)?,
+                "" => {}
+                choice if choice.starts_with("id ") => {
+                    self.copy_by_prefix(output, choice["id ".len()..].trim())
+                        .await?
+                }
+                choice => match choice
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|number| shown.get(number.checked_sub(1)?))
+                {
+                    Some(id) => self.copy_by_id(output, id).await?,
+                    None => writeln!(output, "Unknown choice '{choice}'")?,
+                },
+            }
+        }
+    }
+
+    async fn search_mode<R, W>(
+        &mut self,
+        lines: &mut tokio::io::Lines<R>,
+        output: &mut W,
+    ) -> io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: Write,
+    {
+        // Ids of the results listed last, so a number picks one of them even if
+        // new captures have shifted the history since
+        let mut results: Vec<String> = Vec::new();
+        loop {
+            write!(output, "\nSearch ('h' for help, 'q' to exit): ")?;
+            output.flush()?;
+            let Some(line) = lines.next_line().await? else {
+                return Ok(());
+            };
+            match line.trim() {
+                "q" | "quit" => return Ok(()),
+                "h" | "help" => print_search_help(output)?,
+                "" => {}
+                query if query.starts_with("id ") => {
+                    self.copy_by_prefix(output, query["id ".len()..].trim())
+                        .await?
+                }
+                query if query.starts_with("range ") => {
+                    if let Some(ids) = self.range_search(output, &query["range ".len()..]).await? {
+                        results = ids;
+                    }
+                }
+                query => {
+                    if let Ok(number) = query.parse::<usize>() {
+                        if let Some(id) = number.checked_sub(1).and_then(|i| results.get(i)) {
+                            self.copy_by_id(output, id).await?;
+                            continue;
+                        }
+                    }
+                    results = self.search(output, query).await?;
+                }
+            }
+        }
+    }
+
+    /// Fuzzy search, falling back to a plain substring search when nothing matches
+    async fn search<W: Write>(&self, output: &mut W, query: &str) -> io::Result<Vec<String>> {
+        let mut matches: Vec<(usize, ClipboardItem)> = self
+            .service
+            .fuzzy_search(query)
+            .await
+            .into_iter()
+            .map(|(index, item, _)| (index, item))
+            .collect();
+        if matches.is_empty() {
+            matches = self.service.search(query).await;
+        }
+
+        if matches.is_empty() {
+            writeln!(output, "No items match '{query}'")?;
+            return Ok(Vec::new());
+        }
+        self.print_results(output, matches)
+    }
+
+    /// `range <spec> [query]`: search the items copied in a time range such as "today"
+    /// or "last 7 days". Returns `None` if the range isn't recognized.
+    async fn range_search<W: Write>(
+        &self,
+        output: &mut W,
+        args: &str,
+    ) -> io::Result<Option<Vec<String>>> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        // The longest leading run of words that reads as a range; the rest is the query
+        let parsed = (1..=words.len().min(3)).rev().find_map(|count| {
+            parse_time_range(&words[..count].join(" "), Local::now())
+                .map(|range| (range, words[count..].join(" ")))
+        });
+        let Some(((from, to), query)) = parsed else {
+            writeln!(
+                output,
+                "Unknown range '{}' (use today, yesterday, \"last N days\" or \"last N hours\")",
+                args.trim()
+            )?;
+            return Ok(None);
+        };
+
+        let matches = self.service.search_in_range(&query, from, to).await;
+        if matches.is_empty() {
+            writeln!(output, "No items in that range match '{query}'")?;
+            return Ok(Some(Vec::new()));
+        }
+        self.print_results(output, matches).map(Some)
+    }
+
+    /// List search results, returning the ids of the ones numbered
+    fn print_results<W: Write>(
+        &self,
+        output: &mut W,
+        matches: Vec<(usize, ClipboardItem)>,
+    ) -> io::Result<Vec<String>> {
+        let rows = self.rows();
+        writeln!(output, "Found {} item(s):", matches.len())?;
+        for (number, (_, item)) in matches.iter().take(rows).enumerate() {
+            print_item(output, self.view, number + 1, item)?;
+        }
+        print_shown_count(output, matches.len().min(rows), matches.len())?;
+        Ok(matches
+            .into_iter()
+            .take(rows)
+            .map(|(_, item)| item.id)
+            .collect())
+    }
+
+    /// Items listed at once in the current view
+    fn rows(&self) -> usize {
+        match self.view {
+            ConsoleView::Compact => self.max_results * COMPACT_ROWS_FACTOR,
+            ConsoleView::Detailed => self.max_results,
+        }
+    }
+
+    /// Switch between the compact and the detailed view and save the choice
+    fn toggle_view<W: Write>(&mut self, output: &mut W) -> io::Result<()> {
+        self.view = self.view.toggled();
+        writeln!(
+            output,
+            "Switched to the {} view",
+            match self.view {
+                ConsoleView::Compact => "compact",
+                ConsoleView::Detailed => "detailed",
+            }
+        )?;
+
+        let Some(path) = &self.config_file else {
+            return Ok(());
+        };
+        let saved = PopupConfig::load_from(path).and_then(|mut config| {
+            config.console_view = self.view;
+            config.save_to(path)
+        });
+        if let Err(e) = saved {
+            writeln!(output, "⚠ Failed to save the view setting: {e}")?;
+        }
+        Ok(())
+    }
+
+    /// Clear the whole history after asking for confirmation
+    async fn clear<R, W>(
+        &self,
+        lines: &mut tokio::io::Lines<R>,
+        output: &mut W,
+        count: usize,
+    ) -> io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: Write,
+    {
+        write!(
+            output,
+            "Clear all {count} item(s), including pinned ones? [y/N]: "
+        )?;
+        output.flush()?;
+        let confirmed = lines
+            .next_line()
+            .await?
+            .is_some_and(|answer| matches!(answer.trim(), "y" | "Y" | "yes"));
+        if !confirmed {
+            return writeln!(output, "History kept");
+        }
+        match self.service.clear_history().await {
+            Ok(()) => writeln!(
+                output,
+                "🗑 History cleared (type 'u' to undo until something new is copied)"
+            ),
+            Err(e) => writeln!(output, "❌ Failed to clear history: {e}"),
+        }
+    }
+
+    async fn undo_clear<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        match self.service.undo_clear().await {
+            Ok(true) => writeln!(output, "↩ History restored"),
+            Ok(false) => writeln!(output, "Nothing to undo"),
+            Err(e) => writeln!(output, "❌ Failed to restore history: {e}"),
+        }
+    }
+
+    async fn copy_by_id<W: Write>(&self, output: &mut W, id: &str) -> io::Result<()> {
+        match self.service.copy_item_by_id(id).await {
+            Ok(()) => writeln!(output, "✅ Copied to clipboard"),
+            Err(e) => writeln!(output, "❌ Failed to copy to clipboard: {e}"),
+        }
+    }
+
+    /// `id <prefix>`: copy the single item whose id starts with `prefix`
+    async fn copy_by_prefix<W: Write>(&self, output: &mut W, prefix: &str) -> io::Result<()> {
+        match self.service.find_by_id_prefix(prefix).await {
+            Ok((_, item)) => self.copy_by_id(output, &item.id).await,
+            Err(e @ ClipboardError::AmbiguousId { .. }) => {
+                writeln!(output, "❌ {e}; type more of the id")
+            }
+            Err(e) => writeln!(output, "❌ {e}"),
+        }
+    }
+
+    /// Announce clipboard captures and errors reported since the last prompt
+    fn show_events<W: Write>(&mut self, output: &mut W) -> io::Result<()> {
+        let Some(events) = &mut self.events else {
+            return Ok(());
+        };
+        loop {
+            match events.try_recv() {
+                Ok(ClipboardEvent::ItemAdded {
+                    preview,
+                    content_type,
+                    ..
+                }) => writeln!(output, "📥 New {content_type}: {preview}")?,
+                Ok(ClipboardEvent::Error { message }) => writeln!(output, "⚠ {message}")?,
+                Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) => {}
+                Err(_) => return Ok(()),
+            }
+        }
+    }
+}
+
+fn print_item<W: Write>(
+    output: &mut W,
+    view: ConsoleView,
+    number: usize,
+    item: &ClipboardItem,
+) -> io::Result<()> {
+    let pin = if item.pinned { "📌 " } else { "" };
+    if view == ConsoleView::Compact {
+        return writeln!(
+            output,
+            "{number:>3}. {pin}{}",
+            item.clean_preview(COMPACT_PREVIEW_CHARS)
+        );
+    }
+
+    writeln!(
+        output,
+        "{number:>3}. {pin}{} ({})",
+        item.clean_preview(PREVIEW_CHARS),
+        item.relative_timestamp()
+    )?;
+    let mut details = format!(
+        "     {} · {} bytes · {}",
+        item.content_type_name(),
+        item.get_size_bytes(),
+        item.timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+    );
+    if !item.tags.is_empty() {
+        let tags: Vec<String> = item.tags.iter().map(|tag| format!("#{tag}")).collect();
+        details.push_str(&format!(" · {}", tags.join(" ")));
+    }
+    writeln!(output, "{details}")
+}
+
+/// Footer for a list cut short, e.g. "(showing 20 of 312)"; nothing if all is shown
+fn print_shown_count<W: Write>(output: &mut W, shown: usize, total: usize) -> io::Result<()> {
+    if shown < total {
+        writeln!(output, "     (showing {shown} of {total})")?;
+    }
+    Ok(())
+}
+
+fn print_search_help<W: Write>(output: &mut W) -> io::Result<()> {
+    writeln!(
+        output,
+        "Type a search term to find items (fuzzy matching, typos are fine)."
+    )?;
+    writeln!(
+        output,
+        "If nothing matches fuzzily, a plain substring search is used."
+    )?;
+    writeln!(
+        output,
+        "Type 'range <range> [words]' to search a time range, e.g. 'range last 7 days invoice'"
+    )?;
+    writeln!(
+        output,
+        "(ranges: today, yesterday, \"last N days\", \"last N hours\")."
+    )?;
+    writeln!(
+        output,
+        "Type the number of a result to copy it, 'id <prefix>' to copy by id, or 'q' to exit search."
+    )
+}
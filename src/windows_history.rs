@@ -0,0 +1,81 @@
+use crate::clipboard_item::ClipboardItem;
+use crate::error::ClipboardError;
+
+/// Read the text entries of the native Windows clipboard history (Win+V), newest first.
+/// Returns an empty list when clipboard history is disabled, unavailable on this
+/// Windows version (pre-1809), when running on another platform, or when built without
+/// the `windows-history` feature.
+pub fn read_entries() -> Result<Vec<ClipboardItem>, ClipboardError> {
+    #[cfg(all(windows, feature = "windows-history"))]
+    {
+        read_windows_entries()
+    }
+
+    #[cfg(not(all(windows, feature = "windows-history")))]
+    {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(all(windows, feature = "windows-history"))]
+fn read_windows_entries() -> Result<Vec<ClipboardItem>, ClipboardError> {
+    use chrono::DateTime;
+    use windows::ApplicationModel::DataTransfer::{
+        Clipboard, ClipboardHistoryItemsResultStatus, StandardDataFormats,
+    };
+
+    // Ticks are 100ns intervals since 1601-01-01; this is the offset to the Unix epoch
+    const TICKS_TO_UNIX_EPOCH: i64 = 116_444_736_000_000_000;
+
+    let to_io = |e: windows::core::Error| ClipboardError::ClipboardUnavailable(e.to_string());
+
+    // The API is missing on older Windows 10 builds, treat that as "nothing to import"
+    match Clipboard::IsHistoryEnabled() {
+        Ok(true) => {}
+        Ok(false) | Err(_) => return Ok(Vec::new()),
+    }
+
+    let result = Clipboard::GetHistoryItemsAsync()
+        .and_then(|operation| operation.get())
+        .map_err(to_io)?;
+    if result.Status().map_err(to_io)? != ClipboardHistoryItemsResultStatus::Success {
+        return Ok(Vec::new());
+    }
+
+    let text_format = StandardDataFormats::Text().map_err(to_io)?;
+    let mut items = Vec::new();
+
+    for entry in result.Items().map_err(to_io)? {
+        let content = entry.Content().map_err(to_io)?;
+        if !content.Contains(&text_format).unwrap_or(false) {
+            continue;
+        }
+
+        // One unreadable entry (e.g. from an app that has since closed) shouldn't
+        // stop the others from being imported
+        let text = match content.GetTextAsync().and_then(|operation| operation.get()) {
+            Ok(text) => text.to_string(),
+            Err(e) => {
+                eprintln!("Skipping a Windows clipboard history entry: {e}");
+                continue;
+            }
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        let mut item = ClipboardItem::new_text(text);
+        if let Ok(timestamp) = entry.Timestamp() {
+            let unix_ticks = timestamp.UniversalTime - TICKS_TO_UNIX_EPOCH;
+            if let Some(parsed) = DateTime::from_timestamp(
+                unix_ticks.div_euclid(10_000_000),
+                (unix_ticks.rem_euclid(10_000_000) * 100) as u32,
+            ) {
+                item.timestamp = parsed;
+            }
+        }
+        items.push(item);
+    }
+
+    Ok(items)
+}
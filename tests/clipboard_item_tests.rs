@@ -1,4 +1,8 @@
-use clipboard_history::clipboard_item::{ClipboardContentType, ClipboardItem};
+use chrono::{Local, NaiveDate, TimeZone, Timelike, Utc};
+use clipboard_history::clipboard_item::{
+    group_items, html_to_text, normalize_tag, ClipboardContentType, ClipboardItem,
+    ContentTypeFilter, DedupPolicy, DetectedKind, GroupHeader, GroupMode, ImageFormat,
+};
 
 #[test]
 fn test_clipboard_item_creation() {
@@ -14,6 +18,22 @@ fn test_clipboard_item_creation() {
     assert!(item.timestamp.timestamp() > 0);
 }
 
+#[test]
+fn test_formatted_timestamp() {
+    let item = ClipboardItem::new_text("test".to_string());
+    let formatted = item.formatted_timestamp();
+
+    let parsed = chrono::NaiveDateTime::parse_from_str(&formatted, "%Y-%m-%d %H:%M:%S").unwrap();
+    assert_eq!(
+        parsed,
+        item.timestamp
+            .with_timezone(&chrono::Local)
+            .naive_local()
+            .with_nanosecond(0)
+            .unwrap()
+    );
+}
+
 #[test]
 fn test_clipboard_item_preview() {
     let long_content = "This is a very long clipboard content that should be truncated when displayed as a preview to the user".to_string();
@@ -44,6 +64,41 @@ fn test_clean_preview() {
     assert!(clean_preview.len() <= 53); // 50 + "..." = 53
 }
 
+#[test]
+fn test_clean_preview_collapses_whitespace() {
+    let code = "fn main() {\n\tprintln!(\"hi\");\r\n\n    return;\n}\n".to_string();
+    let item = ClipboardItem::new_text(code);
+
+    assert_eq!(
+        item.clean_preview(100),
+        "fn main() { println!(\"hi\"); return; }"
+    );
+}
+
+#[test]
+fn test_clean_preview_truncates_at_char_boundary() {
+    let item = ClipboardItem::new_text("héllo\twörld ünïcode".to_string());
+
+    assert_eq!(item.clean_preview(11), "héllo wörld...");
+    assert_eq!(item.clean_preview(12), "héllo wörld...");
+    assert_eq!(item.clean_preview(20), "héllo wörld ünïcode");
+}
+
+#[test]
+fn test_clean_preview_counts_multibyte_characters() {
+    // 10 characters but 30 bytes: a byte-length check would truncate it
+    let cjk = ClipboardItem::new_text("剪贴板历史记录工具箱".to_string());
+    assert_eq!(cjk.clean_preview(10), "剪贴板历史记录工具箱");
+    assert_eq!(cjk.clean_preview(4), "剪贴板历...");
+
+    let emoji = ClipboardItem::new_text("📋📎✂️🖇️📌".repeat(20));
+    for max_chars in 0..30 {
+        let preview = emoji.clean_preview(max_chars);
+        let kept = preview.trim_end_matches("...");
+        assert_eq!(kept.chars().count(), max_chars);
+    }
+}
+
 #[test]
 fn test_content_analysis() {
     // Test JSON detection with content that will be truncated
@@ -96,3 +151,259 @@ fn test_timestamp() {
     // Test that timestamp is set (non-zero)
     assert!(item.timestamp.timestamp() > 0);
 }
+
+#[test]
+fn test_metadata_summary_fields() {
+    let item = ClipboardItem::new_text("secret content".to_string());
+    let summary = item.metadata_summary();
+
+    assert!(summary.contains(&format!("id: {}", item.id)));
+    assert!(summary.contains("type: Text"));
+    assert!(summary.contains("kind: Text"));
+    assert!(summary.contains("size: 14 bytes"));
+    assert!(summary.contains(&item.timestamp.to_rfc3339()));
+    // Only metadata, never the content itself
+    assert!(!summary.contains("secret content"));
+
+    let image = ClipboardItem::new_image(vec![1, 2, 3], ImageFormat::Png, 640, 480);
+    let summary = image.metadata_summary();
+    assert!(summary.contains("type: Image"));
+    assert!(summary.contains("image: 640x480 Png"));
+}
+
+#[test]
+fn test_content_type_filter_matches() {
+    let text = ClipboardItem::new_text("hello".to_string());
+    let image = ClipboardItem::new_image(vec![1, 2, 3], ImageFormat::Png, 1, 1);
+    let files = ClipboardItem::new_files(vec!["/tmp/a.txt".to_string()]);
+    let html = ClipboardItem::new_html("<b>hi</b>".to_string(), None);
+
+    assert!(ContentTypeFilter::All.matches(&image));
+    assert!(ContentTypeFilter::Text.matches(&text));
+    assert!(!ContentTypeFilter::Text.matches(&html));
+    assert!(ContentTypeFilter::Image.matches(&image));
+    assert!(!ContentTypeFilter::Image.matches(&files));
+    assert!(ContentTypeFilter::Files.matches(&files));
+    assert!(ContentTypeFilter::Html.matches(&html));
+}
+
+#[test]
+fn test_rtf_item_display_and_hash() {
+    let rtf = r"{\rtf1\ansi hello}".to_string();
+    let with_text = ClipboardItem::new_rtf(rtf.clone(), Some("hello".to_string()));
+    let without_text = ClipboardItem::new_rtf(rtf.clone(), None);
+    let plain = ClipboardItem::new_text("hello".to_string());
+
+    assert_eq!(with_text.content_type_name(), "RTF");
+    assert_eq!(with_text.display_content(), "hello");
+    assert_eq!(
+        without_text.display_content(),
+        format!("Rich text ({} bytes)", rtf.len())
+    );
+    assert_eq!(with_text.get_size_bytes(), rtf.len() + 5);
+    // Rich text never collapses into the equivalent plain text
+    assert_ne!(with_text.content_hash, plain.content_hash);
+    assert_ne!(with_text.content_hash, without_text.content_hash);
+}
+
+#[test]
+fn test_items_without_tags_still_deserialize() {
+    let json = r#"{
+        "id": "abc",
+        "content": {"Text": "old entry"},
+        "timestamp": "2024-01-01T00:00:00Z",
+        "content_hash": "h"
+    }"#;
+    let item: ClipboardItem = serde_json::from_str(json).unwrap();
+    assert!(item.tags.is_empty());
+    assert_eq!(normalize_tag("  #Work "), Some("Work".to_string()));
+    assert_eq!(normalize_tag("#"), None);
+    assert_eq!(normalize_tag("two words"), None);
+}
+
+#[test]
+fn test_html_to_text() {
+    let html = "<html><head><style>p { color: red; }</style></head><body>\n  <h1>Title</h1>\n  <p>One &lt;two&gt;<br>three&nbsp;&#8364; &#x41; &bogus; AT&T</p>\n  <script>alert(1)</script><ul><li>a</li><li>b</li></ul></body></html>";
+    assert_eq!(
+        html_to_text(html),
+        "Title\nOne <two>\nthree \u{20ac} A &bogus; AT&T\na\nb"
+    );
+    assert_eq!(html_to_text("plain"), "plain");
+}
+
+#[test]
+fn test_relative_timestamp_boundaries() {
+    let item = ClipboardItem::new_text("test".to_string());
+    let at = |elapsed: chrono::Duration| item.relative_timestamp_at(item.timestamp + elapsed);
+
+    assert_eq!(at(chrono::Duration::seconds(-5)), "just now");
+    assert_eq!(at(chrono::Duration::seconds(59)), "just now");
+    assert_eq!(at(chrono::Duration::seconds(60)), "1 minute ago");
+    assert_eq!(at(chrono::Duration::minutes(5)), "5 minutes ago");
+    assert_eq!(at(chrono::Duration::seconds(3_599)), "59 minutes ago");
+    assert_eq!(at(chrono::Duration::minutes(60)), "1 hour ago");
+    assert_eq!(at(chrono::Duration::hours(23)), "23 hours ago");
+    assert_eq!(at(chrono::Duration::hours(24)), "yesterday");
+    assert_eq!(at(chrono::Duration::hours(47)), "yesterday");
+    assert_eq!(at(chrono::Duration::days(2)), "2 days ago");
+    assert_eq!(at(chrono::Duration::days(7)), "1 week ago");
+    assert_eq!(at(chrono::Duration::days(29)), "4 weeks ago");
+    assert_eq!(at(chrono::Duration::days(30)), "1 month ago");
+    assert_eq!(at(chrono::Duration::days(400)), "1 year ago");
+    assert_eq!(item.relative_timestamp(), "just now");
+}
+
+#[test]
+fn test_detected_kind() {
+    let kind = |text: &str| ClipboardItem::new_text(text.to_string()).detected_kind();
+
+    assert_eq!(
+        kind(r#"{"key": "value", "list": [1, 2]}"#),
+        DetectedKind::Json
+    );
+    assert_eq!(kind("[1, 2, 3]"), DetectedKind::Json);
+    assert_eq!(kind("{not json"), DetectedKind::PlainText);
+    assert_eq!(kind("https://example.com/a?b=c"), DetectedKind::Url);
+    assert_eq!(kind("  www.example.com\n"), DetectedKind::Url);
+    assert_eq!(kind("see https://example.com"), DetectedKind::PlainText);
+    assert_eq!(kind("-1,234.5"), DetectedKind::Numeric);
+    assert_eq!(kind("6.02e23"), DetectedKind::Numeric);
+    assert_eq!(kind("NaN"), DetectedKind::PlainText);
+    assert_eq!(kind("1-2-3"), DetectedKind::PlainText);
+    assert_eq!(kind("line one\nline two"), DetectedKind::MultiLine);
+    assert_eq!(kind("こんにちは 👋"), DetectedKind::Unicode);
+    assert_eq!(kind("hello world"), DetectedKind::PlainText);
+
+    let html = ClipboardItem::new_html("<a href=\"x\">https://example.com</a>".to_string(), None);
+    assert_eq!(html.detected_kind(), DetectedKind::Url);
+    let image = ClipboardItem::new_image(vec![1, 2, 3], ImageFormat::Png, 1, 1);
+    assert_eq!(image.detected_kind(), DetectedKind::NonText);
+}
+
+#[test]
+fn test_as_url() {
+    let url = |text: &str| ClipboardItem::new_text(text.to_string()).as_url();
+
+    assert_eq!(
+        url(" https://example.com/a?b=c\n").unwrap().as_str(),
+        "https://example.com/a?b=c"
+    );
+    assert_eq!(
+        url("www.example.com").unwrap().as_str(),
+        "https://www.example.com/"
+    );
+    assert!(url("ftp://files.example.com/x.zip").is_some());
+    // Text that merely contains a link isn't a link item
+    assert!(url("see https://example.com").is_none());
+    assert!(url("https://example.com\nhttps://example.org").is_none());
+    assert!(url("javascript:alert(1)").is_none());
+    assert!(url("mailto:someone@example.com").is_none());
+    assert!(url("example").is_none());
+
+    let image = ClipboardItem::new_image(vec![1, 2, 3], ImageFormat::Png, 1, 1);
+    assert!(image.as_url().is_none());
+}
+
+fn copied_on(text: &str, date: NaiveDate) -> ClipboardItem {
+    let mut item = ClipboardItem::new_text(text.to_string());
+    item.timestamp = Local
+        .from_local_datetime(&date.and_hms_opt(12, 0, 0).unwrap())
+        .unwrap()
+        .with_timezone(&Utc);
+    item
+}
+
+fn group_summary(groups: &[(GroupHeader, Vec<ClipboardItem>)]) -> Vec<(&str, Vec<String>)> {
+    groups
+        .iter()
+        .map(|(header, items)| {
+            let texts = items.iter().map(|item| item.display_content()).collect();
+            (header.label(), texts)
+        })
+        .collect()
+}
+
+#[test]
+fn test_group_items_by_date() {
+    let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let items = vec![
+        copied_on("now", today),
+        copied_on("last night", NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()),
+        copied_on("this morning", today),
+        copied_on("last year", NaiveDate::from_ymd_opt(2023, 3, 1).unwrap()),
+    ];
+
+    let groups = group_items(items.clone(), GroupMode::Date, today);
+    assert_eq!(
+        group_summary(&groups),
+        vec![
+            ("Today", vec!["now".to_string(), "this morning".to_string()]),
+            ("Yesterday", vec!["last night".to_string()]),
+            ("Earlier", vec!["last year".to_string()]),
+        ]
+    );
+
+    let flat = group_items(items, GroupMode::None, today);
+    assert_eq!(flat.len(), 1);
+    assert_eq!(flat[0].0, GroupHeader::All);
+    assert_eq!(flat[0].1.len(), 4);
+}
+
+#[test]
+fn test_group_items_by_content_type() {
+    let items = vec![
+        ClipboardItem::new_files(vec!["/tmp/a.txt".to_string()]),
+        ClipboardItem::new_html("<b>bold</b>".to_string(), Some("bold".to_string())),
+        ClipboardItem::new_image(vec![1, 2, 3], ImageFormat::Png, 1, 1),
+        ClipboardItem::new_text("plain".to_string()),
+    ];
+
+    let groups = group_items(items, GroupMode::ContentType, Local::now().date_naive());
+    let headers: Vec<GroupHeader> = groups.iter().map(|(header, _)| *header).collect();
+    assert_eq!(
+        headers,
+        vec![GroupHeader::Text, GroupHeader::Images, GroupHeader::Files]
+    );
+    assert_eq!(groups[0].1.len(), 2);
+    assert!(group_items(
+        Vec::new(),
+        GroupMode::ContentType,
+        Local::now().date_naive()
+    )
+    .is_empty());
+}
+
+#[test]
+fn test_content_hash_with_dedup_policy() {
+    let hash = |text: &str, policy| {
+        ClipboardItem::calculate_content_hash_with(
+            &ClipboardContentType::Text(text.to_string()),
+            policy,
+        )
+    };
+    assert_ne!(
+        hash("foo\n", DedupPolicy::Exact),
+        hash("foo", DedupPolicy::Exact)
+    );
+    assert_eq!(
+        hash("foo", DedupPolicy::Exact),
+        ClipboardItem::new_text("foo".to_string()).content_hash
+    );
+    assert_eq!(
+        hash("foo\n", DedupPolicy::IgnoreTrailingWhitespace),
+        hash("foo", DedupPolicy::IgnoreTrailingWhitespace)
+    );
+    assert_ne!(
+        hash("Foo", DedupPolicy::IgnoreTrailingWhitespace),
+        hash("foo", DedupPolicy::IgnoreTrailingWhitespace)
+    );
+    assert_eq!(
+        hash("Foo \t\n", DedupPolicy::IgnoreCase),
+        hash("foo", DedupPolicy::IgnoreCase)
+    );
+    // Leading whitespace still matters
+    assert_ne!(
+        hash(" foo", DedupPolicy::IgnoreCase),
+        hash("foo", DedupPolicy::IgnoreCase)
+    );
+}
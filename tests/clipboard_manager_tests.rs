@@ -1,5 +1,41 @@
-use clipboard_history::clipboard_item::{ClipboardContentType, ClipboardItem};
-use clipboard_history::clipboard_manager::ClipboardManager;
+use chrono::{Duration, TimeZone, Utc};
+use clipboard_history::clipboard_item::{
+    ClipboardContentType, ClipboardItem, DedupPolicy, ImageFormat, SUMMARY_PREVIEW_CHARS,
+};
+use clipboard_history::clipboard_manager::{
+    fuzzy_search_snapshot, fuzzy_search_snapshot_with, parse_time_range, regex_search_snapshot,
+    search_snapshot, tag_query, ClipboardManager, OversizePolicy, DEFAULT_MAX_HISTORY_SIZE,
+    TRUNCATED_TEXT_LENGTH,
+};
+use clipboard_history::error::ClipboardError;
+use clipboard_history::monitor::ClipboardEvent;
+use clipboard_history::storage::{JsonStorage, Storage};
+use clipboard_history::system_clipboard::{file_uri_list, ClipboardWrite, MockClipboard};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn temp_history_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "clipboard-history-test-{}.json",
+        uuid::Uuid::new_v4()
+    ))
+}
+
+fn manager_with_mock() -> (ClipboardManager, Arc<MockClipboard>) {
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = ClipboardManager::new_empty().with_clipboard(clipboard.clone());
+    (manager, clipboard)
+}
+
+fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let buffer = image::RgbaImage::from_raw(width, height, rgba.to_vec()).unwrap();
+    let mut png = Vec::new();
+    buffer
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    png
+}
 
 #[tokio::test]
 async fn test_clipboard_manager_creation() {
@@ -40,6 +76,75 @@ async fn test_duplicate_prevention() {
     assert_eq!(history.len(), 1);
 }
 
+#[tokio::test]
+async fn test_duplicate_anywhere_in_history_moves_to_front() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["A", "B", "C"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    // Pinning survives being moved up
+    assert!(manager.set_pinned(2, true).await.unwrap());
+
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("A".to_string()))
+        .await
+        .unwrap();
+
+    let history = manager.get_history().await;
+    let texts: Vec<String> = history.iter().map(|item| item.display_content()).collect();
+    assert_eq!(texts, vec!["A", "C", "B"]);
+    assert!(history[0].pinned);
+}
+
+#[tokio::test]
+async fn test_chronological_duplicates_when_full_dedup_disabled() {
+    let manager = ClipboardManager::new_empty().with_dedup_entire_history(false);
+    for text in ["A", "B", "A", "A"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    // Only back-to-back copies are collapsed
+    let texts: Vec<String> = manager
+        .get_history()
+        .await
+        .iter()
+        .map(|item| item.display_content())
+        .collect();
+    assert_eq!(texts, vec!["A", "B", "A"]);
+}
+
+#[tokio::test]
+async fn test_dedup_policy_ignores_trailing_whitespace_and_case() {
+    for (policy, expected) in [
+        (DedupPolicy::Exact, vec!["FOO", "foo", "foo\n"]),
+        (DedupPolicy::IgnoreTrailingWhitespace, vec!["FOO", "foo\n"]),
+        (DedupPolicy::IgnoreCase, vec!["foo\n"]),
+    ] {
+        let manager = ClipboardManager::new_empty().with_dedup_policy(policy);
+        for text in ["foo\n", "foo", "FOO"] {
+            manager
+                .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+                .await
+                .unwrap();
+        }
+
+        // A near-duplicate of the newest item is dropped, so it keeps its original text
+        let texts: Vec<String> = manager
+            .get_history()
+            .await
+            .iter()
+            .map(|item| item.display_content())
+            .collect();
+        assert_eq!(texts, expected, "{policy:?}");
+    }
+}
+
 #[tokio::test]
 async fn test_search_functionality() {
     let manager = ClipboardManager::new_empty();
@@ -86,6 +191,99 @@ async fn test_fuzzy_search() {
     }));
 }
 
+#[tokio::test]
+async fn test_fuzzy_search_breaks_ties_by_recency_then_id() {
+    let base = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let item = |text: &str, id: &str, minutes: i64| {
+        let mut item = ClipboardItem::new_text(text.to_string());
+        item.id = id.to_string();
+        item.timestamp = base + Duration::minutes(minutes);
+        item
+    };
+    // Equal scores for "alpha"; listed oldest first, and two copied at the same time
+    let items = vec![
+        item("alpha one", "c", 0),
+        item("alpha two", "b", 5),
+        item("alpha six", "a", 5),
+    ];
+
+    let matches = fuzzy_search_snapshot(&items, "alpha");
+    assert!(matches.iter().all(|&(_, score)| score == matches[0].1));
+    let order: Vec<&str> = matches
+        .iter()
+        .map(|&(index, _)| items[index].id.as_str())
+        .collect();
+    assert_eq!(order, vec!["a", "b", "c"]);
+
+    // The manager (cached or not) ranks them the same way
+    let manager = ClipboardManager::new_empty();
+    for item in items.into_iter().rev() {
+        manager.add_clipboard_item(item).await.unwrap();
+    }
+    for _ in 0..2 {
+        let ids: Vec<String> = manager
+            .fuzzy_search_history("alpha")
+            .await
+            .into_iter()
+            .map(|(_, item, _)| item.id)
+            .collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+    }
+}
+
+#[tokio::test]
+async fn test_fuzzy_search_threshold_and_limit() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["cargo check", "cargo clippy", "cargo build", "cat log"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    let all = manager.fuzzy_search_history("carg").await;
+    assert_eq!(
+        manager
+            .fuzzy_search_history_with("carg", None, None)
+            .await
+            .len(),
+        all.len()
+    );
+
+    // Cached and uncached runs apply the limit the same way
+    for _ in 0..2 {
+        let limited = manager
+            .fuzzy_search_history_with("carg", None, Some(2))
+            .await;
+        let ids: Vec<&str> = limited
+            .iter()
+            .map(|(_, item, _)| item.id.as_str())
+            .collect();
+        let best: Vec<&str> = all[..2]
+            .iter()
+            .map(|(_, item, _)| item.id.as_str())
+            .collect();
+        assert_eq!(ids, best);
+    }
+
+    let min_score = all[0].2;
+    let strong = manager
+        .fuzzy_search_history_with("carg", Some(min_score), None)
+        .await;
+    assert!(!strong.is_empty());
+    assert!(strong.iter().all(|(_, _, score)| *score >= min_score));
+    assert!(manager
+        .fuzzy_search_history_with("carg", Some(i64::MAX), None)
+        .await
+        .is_empty());
+
+    let snapshot = manager.get_history().await;
+    assert_eq!(
+        fuzzy_search_snapshot_with(&snapshot, "carg", None, Some(1)),
+        fuzzy_search_snapshot(&snapshot, "carg")[..1]
+    );
+}
+
 #[tokio::test]
 async fn test_clear_history() {
     let manager = ClipboardManager::new_empty();
@@ -99,11 +297,46 @@ async fn test_clear_history() {
         .await
         .unwrap();
 
+    manager.set_pinned(0, true).await.unwrap();
+    assert_eq!(manager.get_history().await.len(), 2);
+
+    manager.clear_history().await.unwrap();
+    assert!(manager.get_history().await.is_empty());
+    assert!(manager.search_history("Item").await.is_empty());
+}
+
+#[tokio::test]
+async fn test_undo_clear_restores_until_next_add() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["Item 1", "Item 2"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    manager.set_pinned(1, true).await.unwrap();
+
+    assert!(!manager.undo_clear().await.unwrap());
+    manager.clear_history().await.unwrap();
+    // Clearing the now empty history doesn't lose what the first clear removed
+    manager.clear_history().await.unwrap();
+    assert!(manager.undo_clear().await.unwrap());
+
     let history = manager.get_history().await;
-    assert_eq!(history.len(), 2);
+    let texts: Vec<String> = history.iter().map(|item| item.display_content()).collect();
+    assert_eq!(texts, vec!["Item 2", "Item 1"]);
+    assert!(history[1].pinned);
+    assert_eq!(manager.search_history("Item").await.len(), 2);
+    // Only one level of undo
+    assert!(!manager.undo_clear().await.unwrap());
 
-    // Note: clear_history method is not available in current implementation
-    // This test would need the clear_history method to be implemented
+    manager.clear_history().await.unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("Item 3".to_string()))
+        .await
+        .unwrap();
+    assert!(!manager.undo_clear().await.unwrap());
+    assert_eq!(manager.get_history().await.len(), 1);
 }
 
 #[tokio::test]
@@ -146,9 +379,106 @@ async fn test_content_size_limit() {
     assert!(result.is_err());
 
     let error = result.unwrap_err();
+    assert!(matches!(
+        error,
+        ClipboardError::ContentTooLarge {
+            size: 10_000_001,
+            max: 10_000_000
+        }
+    ));
     assert!(error.to_string().contains("Content too large"));
 }
 
+#[tokio::test]
+async fn test_oversized_text_is_truncated_when_configured() {
+    let manager = ClipboardManager::new_with_storage(Box::new(
+        JsonStorage::new_with_file(temp_history_path()).unwrap(),
+    ))
+    .await
+    .unwrap()
+    .with_oversize_policy(OversizePolicy::Truncate);
+
+    let oversized = ClipboardItem::new_text("x".repeat(10_000_001));
+    let hash = oversized.content_hash.clone();
+    manager.add_clipboard_item(oversized).await.unwrap();
+
+    let history = manager.get_history().await;
+    let stored = &history[0];
+    assert!(stored.truncated);
+    assert_eq!(stored.plain_text().len(), TRUNCATED_TEXT_LENGTH);
+    assert_eq!(stored.content_hash, hash);
+    assert!(stored.display_content().ends_with("[truncated]"));
+    assert!(stored.clean_preview(20).ends_with("... [truncated]"));
+
+    // Oversized binary content can't be truncated and is still rejected
+    let blob = ClipboardItem::new_other("application/x-blob".to_string(), "b".repeat(10_000_001));
+    assert!(matches!(
+        manager.add_clipboard_item(blob).await,
+        Err(ClipboardError::ContentTooLarge { .. })
+    ));
+
+    let _ = std::fs::remove_file(manager.data_file_path());
+}
+
+#[tokio::test]
+async fn test_get_history_page() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["first", "second", "third", "fourth"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    let page = |items: Vec<ClipboardItem>| -> Vec<String> {
+        items.iter().map(|item| item.display_content()).collect()
+    };
+    assert_eq!(
+        page(manager.get_history_page(1, 2).await),
+        vec!["third", "second"]
+    );
+    assert_eq!(page(manager.get_history_page(3, 10).await), vec!["first"]);
+    assert!(manager.get_history_page(4, 10).await.is_empty());
+    assert!(manager.get_history_page(0, 0).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_get_history_previews() {
+    let manager = ClipboardManager::new_empty();
+    manager
+        .add_clipboard_item(ClipboardItem::new_image(
+            vec![7; 3000],
+            ImageFormat::Png,
+            640,
+            480,
+        ))
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("word ".repeat(100)))
+        .await
+        .unwrap();
+    manager.set_pinned(1, true).await.unwrap();
+
+    let history = manager.get_history().await;
+    let previews = manager.get_history_previews(0, 10).await;
+    assert_eq!(previews.len(), 2);
+
+    assert_eq!(previews[0].id, history[0].id);
+    assert_eq!(previews[0].content_type, "Text");
+    assert_eq!(previews[0].size_bytes, 500);
+    assert!(previews[0].preview.chars().count() <= SUMMARY_PREVIEW_CHARS + 3);
+    assert!(!previews[0].pinned);
+
+    assert_eq!(previews[1].content_type, "Image");
+    assert_eq!(previews[1].preview, "640x480 Png image");
+    assert_eq!(previews[1].size_bytes, history[1].get_size_bytes());
+    assert_eq!(previews[1].timestamp, history[1].timestamp);
+    assert!(previews[1].pinned);
+
+    assert_eq!(manager.get_history_previews(1, 1).await, previews[1..]);
+}
+
 #[tokio::test]
 async fn test_usage_stats() {
     let manager = ClipboardManager::new_empty();
@@ -166,19 +496,1273 @@ async fn test_usage_stats() {
         .await
         .unwrap(); // Large content
 
-    // Note: get_usage_stats method is not available in current implementation
+    let stats = manager.get_usage_stats().await;
+    assert_eq!(stats.item_count, 3);
+    assert_eq!(manager.get_history_count().await, 3);
+    assert_eq!(stats.pinned_count, 0);
+    assert_eq!(stats.largest_item_bytes, 1000);
+    assert_eq!(stats.total_bytes, 5 + 19 + 1000);
+
+    manager.set_pinned(0, true).await.unwrap();
+    assert_eq!(manager.get_usage_stats().await.pinned_count, 1);
+}
+
+#[tokio::test]
+async fn test_content_limits() {
+    let manager = ClipboardManager::new_empty();
+    let limits = manager.get_content_limits();
+    assert_eq!(limits.max_history_size, DEFAULT_MAX_HISTORY_SIZE);
+    assert_eq!(limits.max_content_size, 10_000_000);
+
+    manager.set_max_history_size(50).await.unwrap();
+    assert_eq!(manager.get_content_limits().max_history_size, 50);
+}
+
+#[tokio::test]
+async fn test_search_cache_returns_same_results() {
+    let manager = ClipboardManager::new_empty();
+
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("cargo build".to_string()))
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("cargo test".to_string()))
+        .await
+        .unwrap();
+
+    let first = manager.search_history("Cargo").await;
+    let second = manager.search_history("cargo").await; // Same normalized query
+    assert_eq!(first.len(), 2);
+    let first_ids: Vec<_> = first.iter().map(|(idx, item)| (*idx, &item.id)).collect();
+    let second_ids: Vec<_> = second.iter().map(|(idx, item)| (*idx, &item.id)).collect();
+    assert_eq!(first_ids, second_ids);
+
+    let fuzzy_first = manager.fuzzy_search_history("crgo").await;
+    let fuzzy_second = manager.fuzzy_search_history("crgo").await;
+    assert_eq!(fuzzy_first.len(), fuzzy_second.len());
+    for (a, b) in fuzzy_first.iter().zip(fuzzy_second.iter()) {
+        assert_eq!((a.0, &a.1.id, a.2), (b.0, &b.1.id, b.2));
+    }
+}
+
+#[tokio::test]
+async fn test_search_cache_invalidated_on_mutation() {
+    let manager = ClipboardManager::new_empty();
+
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("cargo build".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(manager.search_history("cargo").await.len(), 1);
+    assert_eq!(manager.fuzzy_search_history("cargo").await.len(), 1);
+
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("cargo test".to_string()))
+        .await
+        .unwrap();
+
+    let results = manager.search_history("cargo").await;
+    assert_eq!(results.len(), 2);
+    // Indices must reflect the new ordering, newest first
+    assert_eq!(results[0].1.display_content(), "cargo test");
+    assert_eq!(manager.fuzzy_search_history("cargo").await.len(), 2);
+}
+
+#[tokio::test]
+async fn test_import_items_skips_duplicates() {
+    let manager = ClipboardManager::new_empty();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("already here".to_string()))
+        .await
+        .unwrap();
+
+    // Simulated native history, newest first, with a repeat and an existing entry
+    let source: Vec<ClipboardItem> = ["fresh one", "already here", "fresh two", "fresh one"]
+        .iter()
+        .map(|text| ClipboardItem::new_text(text.to_string()))
+        .collect();
+
+    let imported = manager.import_items(source).await.unwrap();
+    assert_eq!(imported, 2);
+
     let history = manager.get_history().await;
     assert_eq!(history.len(), 3);
+    let texts: Vec<String> = history.iter().map(|item| item.display_content()).collect();
+    assert_eq!(
+        texts.iter().filter(|text| *text == "fresh one").count(),
+        1,
+        "duplicates within the import batch should collapse"
+    );
+
+    // Importing the same source again adds nothing
+    let again = manager
+        .import_items(vec![ClipboardItem::new_text("fresh two".to_string())])
+        .await
+        .unwrap();
+    assert_eq!(again, 0);
+}
+
+#[tokio::test]
+async fn test_copy_text_uses_set_text() {
+    let (manager, clipboard) = manager_with_mock();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("plain words".to_string()))
+        .await
+        .unwrap();
+
+    manager.copy_item_to_clipboard(0).await.unwrap();
+    assert_eq!(
+        clipboard.writes(),
+        vec![ClipboardWrite::Text("plain words".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn test_copy_secret_writes_the_unmasked_value() {
+    let (manager, clipboard) = manager_with_mock();
+    manager
+        .add_clipboard_item(ClipboardItem::new_secret(
+            "password=hunter2".to_string(),
+            "Password",
+        ))
+        .await
+        .unwrap();
+
+    manager.copy_item_to_clipboard(0).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("password=hunter2".to_string()))
+    );
+    assert!(manager.search_history("hunter2").await.is_empty());
+}
+
+#[tokio::test]
+async fn test_copy_image_uses_set_image_with_rgba() {
+    let (manager, clipboard) = manager_with_mock();
+    let rgba: Vec<u8> = vec![255, 0, 0, 255, 0, 255, 0, 255]; // 2x1 red, green
+    let png = encode_png(2, 1, &rgba);
+    manager
+        .add_clipboard_item(ClipboardItem::new_image(png, ImageFormat::Png, 2, 1))
+        .await
+        .unwrap();
+
+    manager.copy_item_to_clipboard(0).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Image {
+            width: 2,
+            height: 1,
+            bytes: rgba,
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_copy_rtf_writes_rtf_with_plain_text() {
+    let (manager, clipboard) = manager_with_mock();
+    let rtf = r"{\rtf1\ansi {\b bold} text}";
+    manager
+        .add_clipboard_item(ClipboardItem::new_rtf(
+            rtf.to_string(),
+            Some("bold text".to_string()),
+        ))
+        .await
+        .unwrap();
+
+    manager.copy_item_to_clipboard(0).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Rtf {
+            rtf: rtf.to_string(),
+            alt_text: Some("bold text".to_string()),
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_copy_html_uses_set_html_or_falls_back_to_text() {
+    let (manager, clipboard) = manager_with_mock();
+    manager
+        .add_clipboard_item(ClipboardItem::new_html(
+            "<b>bold</b>".to_string(),
+            Some("bold".to_string()),
+        ))
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_html(
+            "<i>markup only</i>".to_string(),
+            None,
+        ))
+        .await
+        .unwrap();
+
+    // Index 1 is the older item with a plain-text fallback
+    manager.copy_item_to_clipboard(1).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Html {
+            html: "<b>bold</b>".to_string(),
+            alt_text: Some("bold".to_string()),
+        })
+    );
+
+    // Without plain text the raw HTML is set as text
+    manager.copy_item_to_clipboard(0).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("<i>markup only</i>".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_copy_files_as_file_references_and_other_as_text() {
+    let (manager, clipboard) = manager_with_mock();
+    manager
+        .add_clipboard_item(ClipboardItem::new_files(vec![
+            "/tmp/a.txt".to_string(),
+            "/tmp/b.txt".to_string(),
+        ]))
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_other(
+            "text/custom".to_string(),
+            "aGVsbG8=".to_string(), // "hello"
+        ))
+        .await
+        .unwrap();
+
+    manager.copy_item_to_clipboard(0).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("hello".to_string()))
+    );
+
+    manager.copy_item_to_clipboard(1).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Files(vec![
+            PathBuf::from("/tmp/a.txt"),
+            PathBuf::from("/tmp/b.txt")
+        ]))
+    );
+
+    // Where file references can't be written, the paths are copied as text
+    clipboard.set_reject_files(true);
+    manager.copy_item_to_clipboard(1).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("/tmp/a.txt\n/tmp/b.txt".to_string()))
+    );
+}
+
+#[test]
+fn test_file_uri_list_escapes_paths() {
+    let uri_list = file_uri_list(&[
+        PathBuf::from("/tmp/my notes.txt"),
+        PathBuf::from("/tmp/a#b"),
+    ])
+    .unwrap();
+    assert_eq!(
+        uri_list,
+        "file:///tmp/my%20notes.txt\r\nfile:///tmp/a%23b\r\n"
+    );
+
+    assert!(file_uri_list(&[PathBuf::from("relative.txt")]).is_err());
+}
+
+#[tokio::test]
+async fn test_copy_out_of_range_writes_nothing() {
+    let (manager, clipboard) = manager_with_mock();
+    assert!(matches!(
+        manager.copy_item_to_clipboard(3).await,
+        Err(ClipboardError::NotFound(_))
+    ));
+    assert!(clipboard.writes().is_empty());
+}
+
+#[tokio::test]
+async fn test_copy_item_metadata_sets_summary_text() {
+    let (manager, clipboard) = manager_with_mock();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("payload".to_string()))
+        .await
+        .unwrap();
+    let item = manager.get_history().await.remove(0);
+
+    manager.copy_item_metadata(0).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text(item.metadata_summary()))
+    );
+    assert!(matches!(
+        manager.copy_item_metadata(5).await,
+        Err(ClipboardError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_actions_by_id_survive_new_captures() {
+    let (manager, clipboard) = manager_with_mock();
+    for text in ["first", "second"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    let history = manager.get_history().await;
+    let (second, first) = (history[0].clone(), history[1].clone());
+
+    // A capture after the ids were taken shifts every index by one
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("third".to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        manager.toggle_pinned_by_id(&second.id).await.unwrap(),
+        Some(true)
+    );
+    assert!(manager.get_history().await[1].pinned);
+
+    manager.copy_item_metadata_by_id(&first.id).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text(first.metadata_summary()))
+    );
+
+    manager
+        .copy_combined_by_id(&[first.id.clone(), second.id.clone()], " ")
+        .await
+        .unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("first second".to_string()))
+    );
+
+    assert!(!manager.open_item_url_by_id(&first.id).await.unwrap());
+    assert_eq!(manager.toggle_pinned_by_id("missing").await.unwrap(), None);
+    assert!(matches!(
+        manager.copy_item_metadata_by_id("missing").await,
+        Err(ClipboardError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_clipboard_panic_surfaces_as_error() {
+    let (manager, clipboard) = manager_with_mock();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("will panic".to_string()))
+        .await
+        .unwrap();
+    clipboard.set_panic_on_write(true);
+
+    let error = manager.copy_item_to_clipboard(0).await.unwrap_err();
+    assert!(matches!(error, ClipboardError::ClipboardUnavailable(_)));
+    assert!(error.to_string().contains("couldn't be copied"));
+    assert!(error.to_string().contains("mock clipboard write panicked"));
+
+    // The manager keeps working once the backend recovers
+    clipboard.set_panic_on_write(false);
+    manager.copy_item_to_clipboard(0).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_copying_an_item_again_keeps_and_increments_use_count() {
+    let (manager, _clipboard) = manager_with_mock();
+    for text in ["favourite snippet", "other"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    manager.copy_item_to_clipboard(1).await.unwrap();
+    manager.copy_item_to_clipboard(1).await.unwrap();
+    assert_eq!(manager.get_history().await[1].use_count, 2);
+
+    // Captured again from outside, e.g. copied from an editor
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("favourite snippet".to_string()))
+        .await
+        .unwrap();
+
+    let history = manager.get_history().await;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].display_content(), "favourite snippet");
+    assert_eq!(history[0].use_count, 3);
+    assert_eq!(history[0].last_used, Some(history[0].timestamp));
+}
+
+#[tokio::test]
+async fn test_frequently_copied_items_rank_higher() {
+    let path = temp_history_path();
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = ClipboardManager::new_with_storage(Box::new(
+        JsonStorage::new_with_file(path.clone()).unwrap(),
+    ))
+    .await
+    .unwrap()
+    .with_clipboard(clipboard.clone());
+    for text in ["deploy alpha", "deploy bravo"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    let top_match = |results: Vec<(usize, ClipboardItem, i64)>| results[0].1.plain_text();
+    assert_eq!(
+        top_match(manager.fuzzy_search_history("deploy").await),
+        "deploy bravo"
+    );
+
+    // Copying the older item a few times moves it ahead of the newer one
+    manager.copy_item_to_clipboard(1).await.unwrap();
+    manager.copy_item_to_clipboard_as_text(1).await.unwrap();
+    assert_eq!(
+        top_match(manager.fuzzy_search_history("deploy").await),
+        "deploy alpha"
+    );
+    let snapshot = manager.get_history().await;
+    assert_eq!(snapshot[1].use_count, 2);
+    assert_eq!(fuzzy_search_snapshot(&snapshot, "deploy")[0].0, 1);
+
+    // The count is saved with the history
+    let reloaded = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    assert_eq!(reloaded[1].use_count, 2);
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_corrupt_image_reports_invalid_image_data() {
+    let (manager, clipboard) = manager_with_mock();
+    manager
+        .add_clipboard_item(ClipboardItem::new(ClipboardContentType::Image {
+            data: "not base64!".to_string(),
+            format: ImageFormat::Png,
+            width: 4,
+            height: 2,
+            thumbnail: None,
+        }))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        manager.copy_item_to_clipboard(0).await,
+        Err(ClipboardError::InvalidImageData(_))
+    ));
+    assert!(clipboard.writes().is_empty());
+}
+
+#[tokio::test]
+async fn test_copy_combined_preserves_selection_order() {
+    let (manager, clipboard) = manager_with_mock();
+    for text in ["a", "b", "c"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    manager
+        .add_clipboard_item(ClipboardItem::new_files(vec!["/tmp/skip".to_string()]))
+        .await
+        .unwrap();
+
+    // History is newest first: [files, c, b, a]; select a, files, b, c in that order
+    manager.copy_combined(&[3, 0, 2, 1], ", ").await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("a, b, c".to_string()))
+    );
+
+    // Selections without any text items copy nothing
+    assert!(matches!(
+        manager.copy_combined(&[0], "\n").await,
+        Err(ClipboardError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_oversized_history_trimmed_on_load() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone()).unwrap();
+    let oversized: VecDeque<ClipboardItem> = (0..1005)
+        .map(|i| ClipboardItem::new_text(format!("item {i}")))
+        .collect();
+    storage.save_history(&oversized).await.unwrap();
+
+    let manager = ClipboardManager::new_with_storage(Box::new(
+        JsonStorage::new_with_file(path.clone()).unwrap(),
+    ))
+    .await
+    .unwrap();
+    let history = manager.get_history().await;
+    assert_eq!(history.len(), 1000);
+    // The newest entries (front of the list) are the ones kept
+    assert_eq!(history[0].display_content(), "item 0");
+    assert_eq!(history[999].display_content(), "item 999");
+
+    // The trimmed history was written back immediately
+    let reloaded = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    assert_eq!(reloaded.len(), 1000);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_history_over_the_byte_cap_trimmed_on_load() {
+    let path = temp_history_path();
+    let mut stored: VecDeque<ClipboardItem> = (0..5)
+        .map(|i| ClipboardItem::new_text(format!("item {i} {}", "x".repeat(100))))
+        .collect();
+    stored[4].pinned = true;
+    let item_bytes = stored[0].get_size_bytes();
+    JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .save_history(&stored)
+        .await
+        .unwrap();
+
+    let manager = ClipboardManager::new_with_limits(
+        Box::new(JsonStorage::new_with_file(path.clone()).unwrap()),
+        DEFAULT_MAX_HISTORY_SIZE,
+        Some(item_bytes * 3),
+    )
+    .await
+    .unwrap();
+    let names = |history: Vec<ClipboardItem>| -> Vec<String> {
+        history
+            .iter()
+            .map(|item| item.display_content()[..6].to_string())
+            .collect()
+    };
+    // The oldest unpinned items go; the pinned one stays even though it is the oldest
+    assert_eq!(
+        names(manager.get_history().await),
+        vec!["item 0", "item 1", "item 4"]
+    );
+    let reloaded = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    assert_eq!(reloaded.len(), 3);
+
+    // The cap keeps applying as new items arrive
+    manager
+        .add_clipboard_item(ClipboardItem::new_text(format!(
+            "item 5 {}",
+            "x".repeat(100)
+        )))
+        .await
+        .unwrap();
+    assert_eq!(
+        names(manager.get_history().await),
+        vec!["item 5", "item 0", "item 4"]
+    );
 
-    // Verify we can access items
-    assert!(history.iter().any(|item| {
-        if let ClipboardContentType::Text(text) = &item.content {
-            text == "Small"
-        } else {
-            false
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_snapshot_search_matches_manager() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["Hello World", "Help wanted", "hello again", "unrelated"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    let snapshot = manager.get_history().await;
+
+    let exact: Vec<usize> = manager
+        .search_history("hello")
+        .await
+        .into_iter()
+        .map(|(idx, _)| idx)
+        .collect();
+    assert_eq!(search_snapshot(&snapshot, "hello"), exact);
+
+    let fuzzy: Vec<(usize, i64)> = manager
+        .fuzzy_search_history("helo")
+        .await
+        .into_iter()
+        .map(|(idx, _, score)| (idx, score))
+        .collect();
+    assert_eq!(fuzzy_search_snapshot(&snapshot, "helo"), fuzzy);
+    assert!(!fuzzy.is_empty());
+}
+
+#[tokio::test]
+async fn test_find_by_id_prefix() {
+    let (manager, clipboard) = manager_with_mock();
+    let mut first = ClipboardItem::new_text("first".to_string());
+    first.id = "abc123-0000".to_string();
+    let mut second = ClipboardItem::new_text("second".to_string());
+    second.id = "abc999-0000".to_string();
+    manager.add_clipboard_item(first).await.unwrap();
+    manager.add_clipboard_item(second).await.unwrap();
+
+    let (index, item) = manager.find_by_id_prefix("abc1").await.unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(item.display_content(), "first");
+
+    assert!(matches!(
+        manager.find_by_id_prefix("abc").await,
+        Err(ClipboardError::AmbiguousId { matches: 2, .. })
+    ));
+    assert!(matches!(
+        manager.find_by_id_prefix("zzz").await,
+        Err(ClipboardError::NotFound(_))
+    ));
+
+    manager.copy_item_by_id("abc999-0000").await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("second".to_string()))
+    );
+    assert!(matches!(
+        manager.copy_item_by_id("abc").await,
+        Err(ClipboardError::NotFound(_))
+    ));
+
+    // Ids keep pointing at the same item after new captures shift the indices
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("newer".to_string()))
+        .await
+        .unwrap();
+    manager
+        .copy_item_as_text_by_id("abc999-0000")
+        .await
+        .unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("second".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_plain_text_copies_are_announced() {
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = ClipboardManager::new_empty().with_clipboard(clipboard.clone());
+    for text in ["older", "newer"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    let older_id = manager.get_history().await[1].id.clone();
+    let mut events = manager.event_sender().subscribe();
+
+    manager.copy_item_to_clipboard_as_text(1).await.unwrap();
+    manager.copy_item_as_text_by_id(&older_id).await.unwrap();
+
+    for _ in 0..2 {
+        match events.try_recv() {
+            Ok(ClipboardEvent::ItemCopied { index, id }) => {
+                assert_eq!((index, id.as_str()), (1, older_id.as_str()))
+            }
+            other => panic!("expected ItemCopied, got {other:?}"),
         }
-    }));
+    }
+}
+
+#[tokio::test]
+async fn test_front_duplicate_ignored_by_default() {
+    let manager = ClipboardManager::new_empty();
+    let first = ClipboardItem::new_text("again".to_string());
+    let original_timestamp = first.timestamp;
+    manager.add_clipboard_item(first).await.unwrap();
+
+    let mut repeat = ClipboardItem::new_text("again".to_string());
+    repeat.timestamp = original_timestamp + chrono::Duration::seconds(30);
+    manager.add_clipboard_item(repeat).await.unwrap();
+
+    let history = manager.get_history().await;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].timestamp, original_timestamp);
+    assert!(history[0].last_used.is_none());
+}
+
+#[tokio::test]
+async fn test_front_duplicate_touches_when_enabled() {
+    let manager = ClipboardManager::new_empty().with_touch_on_duplicate(true);
+    let first = ClipboardItem::new_text("again".to_string());
+    let first_id = first.id.clone();
+    let original_timestamp = first.timestamp;
+    manager.add_clipboard_item(first).await.unwrap();
+
+    let mut repeat = ClipboardItem::new_text("again".to_string());
+    let touched_at = original_timestamp + chrono::Duration::seconds(30);
+    repeat.timestamp = touched_at;
+    manager.add_clipboard_item(repeat).await.unwrap();
+
+    let history = manager.get_history().await;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].id, first_id);
+    assert_eq!(history[0].timestamp, touched_at);
+    assert_eq!(history[0].last_used, Some(touched_at));
+}
+
+#[tokio::test]
+async fn test_ocr_text_makes_image_searchable() {
+    let manager = ClipboardManager::new_empty();
+    let image = ClipboardItem::new_image(vec![0u8; 16], ImageFormat::Png, 2, 2);
+    let image_id = image.id.clone();
+    manager.add_clipboard_item(image).await.unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("unrelated".to_string()))
+        .await
+        .unwrap();
+
+    assert!(manager.search_history("invoice").await.is_empty());
+
+    manager
+        .set_ocr_text(&image_id, "Invoice #4711 total due".to_string())
+        .await
+        .unwrap();
+
+    let exact = manager.search_history("invoice").await;
+    assert_eq!(exact.len(), 1);
+    assert_eq!(exact[0].1.id, image_id);
+
+    let fuzzy = manager.fuzzy_search_history("invce 4711").await;
+    assert_eq!(fuzzy.first().map(|m| m.1.id.clone()), Some(image_id));
+
+    assert!(matches!(
+        manager.set_ocr_text("missing", "x".to_string()).await,
+        Err(ClipboardError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_pinned_items_survive_the_size_limit() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone()).unwrap();
+    let history: VecDeque<ClipboardItem> = (0..1000)
+        .map(|i| ClipboardItem::new_text(format!("item {i}")))
+        .collect();
+    storage.save_history(&history).await.unwrap();
+
+    let manager = ClipboardManager::new_with_storage(Box::new(storage))
+        .await
+        .unwrap();
+    // Pin the oldest item, which would be evicted next
+    assert!(manager.set_pinned(999, true).await.unwrap());
+    assert!(!manager.set_pinned(1000, true).await.unwrap());
+
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("newest".to_string()))
+        .await
+        .unwrap();
+
+    let history = manager.get_history().await;
+    assert_eq!(history.len(), 1000);
+    assert_eq!(history[0].display_content(), "newest");
+    // The pinned item stayed; the oldest unpinned one went instead
+    assert_eq!(history[999].display_content(), "item 999");
+    assert!(history[999].pinned);
+    assert!(!history
+        .iter()
+        .any(|item| item.display_content() == "item 998"));
+
+    assert_eq!(manager.toggle_pinned(999).await.unwrap(), Some(false));
+    assert_eq!(manager.toggle_pinned(5000).await.unwrap(), None);
+
+    let _ = std::fs::remove_file(
+        JsonStorage::new_with_file(path.clone())
+            .unwrap()
+            .backup_file_path(),
+    );
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_delete_item() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["first", "second", "third"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    // Populate the search cache so a stale hit would show up
+    assert_eq!(manager.search_history("second").await.len(), 1);
+
+    assert!(manager.delete_item(1).await.unwrap());
+    assert!(!manager.delete_item(5).await.unwrap());
+
+    let contents: Vec<String> = manager
+        .get_history()
+        .await
+        .iter()
+        .map(|item| item.display_content())
+        .collect();
+    assert_eq!(contents, vec!["third", "first"]);
+    assert!(manager.search_history("second").await.is_empty());
+}
+
+#[tokio::test]
+async fn test_delete_item_by_id_after_new_capture() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["keep", "delete me"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    let id = manager.get_history().await[0].id.clone();
+    // Index 0 now holds the new capture, not the item picked above
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("newer".to_string()))
+        .await
+        .unwrap();
+
+    assert!(manager.delete_item_by_id(&id).await.unwrap());
+    assert!(!manager.delete_item_by_id(&id).await.unwrap());
+    let contents: Vec<String> = manager
+        .get_history()
+        .await
+        .iter()
+        .map(|item| item.display_content())
+        .collect();
+    assert_eq!(contents, vec!["newer", "keep"]);
+}
+
+#[tokio::test]
+async fn test_promote_to_front_leaves_clipboard_alone() {
+    let (manager, clipboard) = manager_with_mock();
+    for text in ["first", "second", "third"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    let before = manager.get_history().await[2].timestamp;
+    // Populate the search cache so a stale hit would show up
+    assert_eq!(manager.search_history("first").await[0].0, 2);
+
+    assert!(manager.promote_to_front(2).await.unwrap());
+    assert!(!manager.promote_to_front(9).await.unwrap());
+
+    let history = manager.get_history().await;
+    let contents: Vec<String> = history.iter().map(|item| item.display_content()).collect();
+    assert_eq!(contents, vec!["first", "third", "second"]);
+    assert!(history[0].timestamp >= before);
+    assert_eq!(manager.search_history("first").await[0].0, 0);
+    assert!(clipboard.writes().is_empty());
+}
+
+#[tokio::test]
+async fn test_promote_to_front_by_id_after_new_capture() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["old", "recent"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    let id = manager.get_history().await[1].id.clone();
+    // Shifts "old" from index 1 to index 2
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("newest".to_string()))
+        .await
+        .unwrap();
+
+    assert!(manager.promote_to_front_by_id(&id).await.unwrap());
+    assert!(!manager.promote_to_front_by_id("missing").await.unwrap());
+    let contents: Vec<String> = manager
+        .get_history()
+        .await
+        .iter()
+        .map(|item| item.display_content())
+        .collect();
+    assert_eq!(contents, vec!["old", "newest", "recent"]);
 }
 
-// Note: get_content_limits method is not available in current implementation
-// This test would need the get_content_limits method to be implemented
+#[tokio::test]
+async fn test_regex_search_history() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["ticket ABCD1234 opened", "no ticket here", "see XY12Z789"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    let results = manager
+        .regex_search_history(r"\b[A-Z0-9]{8}\b")
+        .await
+        .unwrap();
+    let texts: Vec<String> = results
+        .iter()
+        .map(|(_, item)| item.display_content())
+        .collect();
+    assert_eq!(texts, vec!["see XY12Z789", "ticket ABCD1234 opened"]);
+
+    // The snapshot helper agrees with the manager
+    let snapshot = manager.get_history().await;
+    let pattern = regex::Regex::new(r"\b[A-Z0-9]{8}\b").unwrap();
+    let indices: Vec<usize> = results.iter().map(|(idx, _)| *idx).collect();
+    assert_eq!(regex_search_snapshot(&snapshot, &pattern), indices);
+
+    // Invalid patterns surface the compile error
+    assert!(manager.regex_search_history("(unclosed").await.is_err());
+}
+
+#[tokio::test]
+async fn test_set_max_history_size_trims_and_persists() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone()).unwrap();
+    let history: VecDeque<ClipboardItem> = (0..10)
+        .map(|i| ClipboardItem::new_text(format!("item {i}")))
+        .collect();
+    storage.save_history(&history).await.unwrap();
+
+    // Loading with a smaller limit trims right away
+    let manager = ClipboardManager::new_with_limit(Box::new(storage), 8)
+        .await
+        .unwrap();
+    assert_eq!(manager.max_history_size(), 8);
+    assert_eq!(manager.get_history().await.len(), 8);
+
+    // Lowering the limit at runtime keeps pinned items
+    assert!(manager.set_pinned(7, true).await.unwrap());
+    assert!(manager.set_max_history_size(3).await.unwrap());
+    let history = manager.get_history().await;
+    let texts: Vec<String> = history.iter().map(|item| item.display_content()).collect();
+    assert_eq!(texts, vec!["item 0", "item 1", "item 7"]);
+
+    // Raising it removes nothing, and a zero limit is rejected
+    assert!(!manager.set_max_history_size(50).await.unwrap());
+    assert!(matches!(
+        manager.set_max_history_size(0).await,
+        Err(ClipboardError::Config(_))
+    ));
+    assert_eq!(manager.max_history_size(), 50);
+
+    // The trimmed history was saved
+    let reloaded = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    assert_eq!(reloaded.len(), 3);
+
+    let _ = std::fs::remove_file(
+        JsonStorage::new_with_file(path.clone())
+            .unwrap()
+            .backup_file_path(),
+    );
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_tags_add_remove_and_search() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["deploy script", "lunch order", "api token"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    // Newest first: 0 = "api token", 2 = "deploy script"
+    assert!(manager.add_tag(0, "#work").await.unwrap());
+    assert!(manager.add_tag(2, "Work").await.unwrap());
+    assert!(!manager.add_tag(0, "WORK").await.unwrap()); // already tagged
+    assert!(!manager.add_tag(1, "two words").await.unwrap());
+    assert!(!manager.add_tag(99, "work").await.unwrap());
+
+    let tagged = manager.search_by_tag("work").await;
+    let indices: Vec<usize> = tagged.iter().map(|(idx, _)| *idx).collect();
+    assert_eq!(indices, vec![0, 2]);
+    assert_eq!(tagged[0].1.tags_label(), "#work");
+
+    assert!(manager.remove_tag(0, "work").await.unwrap());
+    assert!(!manager.remove_tag(0, "work").await.unwrap());
+    assert_eq!(manager.search_by_tag("work").await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_tags_survive_copying_the_item_again() {
+    let manager = ClipboardManager::new_empty();
+    for text in ["SELECT * FROM users", "something else"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    let id = manager.get_history().await[1].id.clone();
+    // By id, after another capture has shifted the item to index 2
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("newest".to_string()))
+        .await
+        .unwrap();
+    assert!(manager.add_tag_by_id(&id, "sql").await.unwrap());
+    assert!(manager.add_tag_by_id(&id, "db").await.unwrap());
+    assert!(manager.remove_tag_by_id(&id, "db").await.unwrap());
+    assert!(!manager.add_tag_by_id("missing", "sql").await.unwrap());
+
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("SELECT * FROM users".to_string()))
+        .await
+        .unwrap();
+
+    let history = manager.get_history().await;
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].display_content(), "SELECT * FROM users");
+    assert_eq!(history[0].tags, vec!["sql"]);
+}
+
+#[test]
+fn test_tag_query_prefix() {
+    assert_eq!(tag_query("#work"), Some("work"));
+    assert_eq!(tag_query("#"), None);
+    assert_eq!(tag_query("#two words"), None);
+    assert_eq!(tag_query("work"), None);
+}
+
+#[tokio::test]
+async fn test_indexed_search_matches_snapshot_search() {
+    let manager = ClipboardManager::new_empty();
+    for text in [
+        "Release Notes v2",
+        "grocery list: eggs, milk",
+        "release-candidate build 42",
+        "NOTES from standup",
+    ] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    manager.search_history("notes").await; // Populate the index
+
+    // Changes after the first search must be reflected in later ones
+    manager.delete_item(0).await.unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("notes on release 3".to_string()))
+        .await
+        .unwrap();
+
+    let snapshot = manager.get_history().await;
+    for query in ["notes", "RELEASE", "rls", "nts 42", "zzz"] {
+        let exact: Vec<usize> = manager
+            .search_history(query)
+            .await
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(exact, search_snapshot(&snapshot, query), "exact {query}");
+
+        let fuzzy: Vec<(usize, i64)> = manager
+            .fuzzy_search_history(query)
+            .await
+            .into_iter()
+            .map(|(idx, _, score)| (idx, score))
+            .collect();
+        assert_eq!(
+            fuzzy,
+            fuzzy_search_snapshot(&snapshot, query),
+            "fuzzy {query}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_search_in_range_filters_by_timestamp() {
+    let manager = ClipboardManager::new_empty();
+    let now = Utc::now();
+    for (text, age_hours) in [
+        ("invoice from last month", 24 * 30),
+        ("invoice from yesterday", 20),
+        ("lunch menu", 2),
+        ("invoice just now", 0),
+    ] {
+        let mut item = ClipboardItem::new_text(text.to_string());
+        item.timestamp = now - Duration::hours(age_hours);
+        manager.add_clipboard_item(item).await.unwrap();
+    }
+
+    let from = now - Duration::days(2);
+    let to = now + Duration::seconds(1);
+    let texts = |results: Vec<(usize, ClipboardItem)>| -> Vec<String> {
+        results
+            .into_iter()
+            .map(|(_, item)| item.display_content())
+            .collect()
+    };
+
+    let mut invoices = texts(manager.search_in_range("invoice", from, to).await);
+    invoices.sort();
+    assert_eq!(invoices, vec!["invoice from yesterday", "invoice just now"]);
+
+    // An empty query lists everything in the range, newest first
+    assert_eq!(
+        texts(
+            manager
+                .search_in_range("", from, now - Duration::hours(1))
+                .await
+        ),
+        vec!["lunch menu", "invoice from yesterday"]
+    );
+}
+
+#[test]
+fn test_parse_time_range() {
+    let now = Utc.with_ymd_and_hms(2024, 5, 10, 15, 30, 0).unwrap();
+    let midnight = Utc.with_ymd_and_hms(2024, 5, 10, 0, 0, 0).unwrap();
+
+    assert_eq!(parse_time_range("today", now), Some((midnight, now)));
+    assert_eq!(
+        parse_time_range(" Yesterday ", now),
+        Some((midnight - Duration::days(1), midnight))
+    );
+    assert_eq!(
+        parse_time_range("last 7 days", now),
+        Some((now - Duration::days(7), now))
+    );
+    assert_eq!(
+        parse_time_range("last hour", now),
+        Some((now - Duration::hours(1), now))
+    );
+    assert_eq!(
+        parse_time_range("last week", now),
+        Some((now - Duration::days(7), now))
+    );
+    assert_eq!(parse_time_range("last 0 days", now), None);
+    assert_eq!(parse_time_range("last fortnight", now), None);
+    assert_eq!(parse_time_range("sometime", now), None);
+}
+
+#[tokio::test]
+async fn test_copy_as_plain_text_drops_formatting() {
+    let (manager, clipboard) = manager_with_mock();
+    manager
+        .add_clipboard_item(ClipboardItem::new_image(
+            vec![0u8; 8],
+            ImageFormat::Png,
+            4,
+            2,
+        ))
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_rtf(
+            r"{\rtf1 {\b bold}}".to_string(),
+            Some("bold".to_string()),
+        ))
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_html(
+            "<p>Total: <b>5 &amp; 6</b></p>".to_string(),
+            None,
+        ))
+        .await
+        .unwrap();
+
+    for index in 0..3 {
+        manager.copy_item_to_clipboard_as_text(index).await.unwrap();
+    }
+    assert_eq!(
+        clipboard.writes(),
+        vec![
+            ClipboardWrite::Text("Total: 5 & 6".to_string()),
+            ClipboardWrite::Text("bold".to_string()),
+            ClipboardWrite::Text("4x2 Png image".to_string()),
+        ]
+    );
+    assert!(matches!(
+        manager.copy_item_to_clipboard_as_text(9).await,
+        Err(ClipboardError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_open_item_url_skips_non_links() {
+    let manager = ClipboardManager::new_empty();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("not a link".to_string()))
+        .await
+        .unwrap();
+
+    assert!(!manager.open_item_url(0).await.unwrap());
+    assert!(!manager.open_item_url(5).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_prune_expired_keeps_recent_and_pinned_items() {
+    let manager = ClipboardManager::new_empty();
+    let now = Utc::now();
+    for (text, age_days) in [
+        ("ancient", 90),
+        ("old pinned", 60),
+        ("old", 31),
+        ("fresh", 1),
+    ] {
+        let mut item = ClipboardItem::new_text(text.to_string());
+        item.timestamp = now - Duration::days(age_days);
+        manager.add_clipboard_item(item).await.unwrap();
+    }
+    manager.set_pinned(2, true).await.unwrap();
+
+    // Without a maximum age nothing expires
+    assert_eq!(manager.prune_expired().await.unwrap(), 0);
+
+    let manager = manager.with_max_age(Some(Duration::days(30)));
+    assert_eq!(manager.prune_expired().await.unwrap(), 2);
+    let remaining: Vec<String> = manager
+        .get_history()
+        .await
+        .iter()
+        .map(|item| item.display_content())
+        .collect();
+    assert_eq!(remaining, vec!["fresh", "old pinned"]);
+    assert_eq!(manager.prune_expired().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_import_items_keeps_existing_order_and_dedup_policy() {
+    let manager = ClipboardManager::new_empty().with_dedup_policy(DedupPolicy::IgnoreCase);
+    let days_ago = |days| Utc::now() - Duration::days(days);
+    // "Moved" was moved to the top, so the history is out of timestamp order
+    for (text, age) in [("older", 5), ("newer", 1), ("Moved", 9)] {
+        let mut item = ClipboardItem::new_text(text.to_string());
+        item.timestamp = days_ago(age);
+        manager.add_clipboard_item(item).await.unwrap();
+    }
+
+    let mut imported_item = ClipboardItem::new_text("imported".to_string());
+    imported_item.timestamp = days_ago(3);
+    let imported = manager
+        .import_items(vec![
+            imported_item,
+            ClipboardItem::new_text("moved".to_string()),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(
+        imported, 1,
+        "\"moved\" is the same as \"Moved\" under IgnoreCase"
+    );
+
+    let texts: Vec<String> = manager
+        .get_history()
+        .await
+        .iter()
+        .map(|item| item.display_content())
+        .collect();
+    assert_eq!(texts, vec!["Moved", "newer", "imported", "older"]);
+}
@@ -0,0 +1,135 @@
+use clipboard_history::clipboard_item::{ClipboardContentType, ClipboardItem, ImageFormat};
+use clipboard_history::clipboard_manager::ClipboardManager;
+use clipboard_history::export::{self, ExportFormat};
+use clipboard_history::service::ClipboardService;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+fn temp_export_path(extension: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "clipboard-history-export-{}.{extension}",
+        uuid::Uuid::new_v4()
+    ))
+}
+
+async fn service_with(items: Vec<ClipboardItem>) -> ClipboardService {
+    let manager = Arc::new(ClipboardManager::new_empty());
+    for item in items {
+        manager.add_clipboard_item(item).await.unwrap();
+    }
+    ClipboardService::new_with_manager(manager)
+}
+
+fn sample_items() -> Vec<ClipboardItem> {
+    vec![
+        ClipboardItem::new_text("exported text".to_string()),
+        ClipboardItem::new_image(vec![137, 80, 78, 71, 1, 2, 3], ImageFormat::Png, 1, 1),
+    ]
+}
+
+#[test]
+fn test_format_from_path() {
+    assert_eq!(
+        ExportFormat::from_path(Path::new("backup.ZIP")),
+        ExportFormat::Zip
+    );
+    assert_eq!(
+        ExportFormat::from_path(Path::new("backup.json")),
+        ExportFormat::Json
+    );
+    assert_eq!(
+        ExportFormat::from_path(Path::new("backup")),
+        ExportFormat::Json
+    );
+}
+
+#[test]
+fn test_zip_export_stores_images_as_files() {
+    let path = temp_export_path("zip");
+    let items = sample_items();
+    export::export_items(&items, &path, ExportFormat::Zip).unwrap();
+
+    let read = export::read_export(&path).unwrap();
+    assert_eq!(read.len(), 2);
+    assert_eq!(read[0].content_hash, items[0].content_hash);
+    // Image bytes come back from their own archive entry
+    match (&read[1].content, &items[1].content) {
+        (
+            ClipboardContentType::Image {
+                data: read_data, ..
+            },
+            ClipboardContentType::Image { data, .. },
+        ) => assert_eq!(read_data, data),
+        _ => panic!("expected an image item"),
+    }
+
+    // The manifest only references the image, which is stored as a real file
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+    let image_entry = format!("images/{}.png", items[1].id);
+    assert_eq!(archive.by_name(&image_entry).unwrap().size(), 7);
+    let mut manifest = String::new();
+    std::io::Read::read_to_string(&mut archive.by_name("history.json").unwrap(), &mut manifest)
+        .unwrap();
+    let ClipboardContentType::Image { data, .. } = &items[1].content else {
+        unreachable!()
+    };
+    assert!(!manifest.contains(data.as_str()));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_export_then_import_skips_existing_items() {
+    for (extension, format) in [("json", ExportFormat::Json), ("zip", ExportFormat::Zip)] {
+        let path = temp_export_path(extension);
+        let source = service_with(sample_items()).await;
+        source.export_history(&path, format).await.unwrap();
+
+        // The target already has the text item, so only the image is new
+        let target = service_with(vec![ClipboardItem::new_text("exported text".to_string())]).await;
+        assert_eq!(target.import_history(&path).await.unwrap(), 1);
+        assert_eq!(target.get_history().await.len(), 2);
+
+        // Importing again adds nothing
+        assert_eq!(target.import_history(&path).await.unwrap(), 0);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[tokio::test]
+async fn test_dump_text_jsonl_writes_one_text_item_per_line() {
+    let manager = Arc::new(ClipboardManager::new_empty());
+    for item in [
+        ClipboardItem::new_text("first\nline two".to_string()),
+        ClipboardItem::new_image(vec![1, 2, 3], ImageFormat::Png, 1, 1),
+        ClipboardItem::new_html("<b>x</b>".to_string(), Some("x".to_string())),
+        ClipboardItem::new_text("say \"hi\"".to_string()),
+    ] {
+        manager.add_clipboard_item(item).await.unwrap();
+    }
+    let service = ClipboardService::new_with_manager(manager.clone());
+
+    let mut output = Vec::new();
+    assert_eq!(service.dump_text_jsonl(&mut output).await.unwrap(), 2);
+
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<serde_json::Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let history = manager.get_history().await;
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["text"], "say \"hi\"");
+    assert_eq!(lines[0]["id"], history[0].id.as_str());
+    assert_eq!(lines[1]["text"], "first\nline two");
+    assert!(lines[1]["timestamp"].is_string());
+    // Only the text items are copied out of the history for the dump
+    let texts: Vec<String> = manager
+        .get_text_items()
+        .await
+        .iter()
+        .map(|item| item.id.clone())
+        .collect();
+    assert_eq!(texts, vec![history[0].id.clone(), history[3].id.clone()]);
+}
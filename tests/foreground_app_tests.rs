@@ -0,0 +1,25 @@
+use clipboard_history::foreground_app::ForegroundApp;
+
+fn app(process_name: &str, window_title: &str) -> ForegroundApp {
+    ForegroundApp {
+        process_name: process_name.to_string(),
+        window_title: window_title.to_string(),
+    }
+}
+
+#[test]
+fn test_ignore_list_matches_process_names() {
+    let ignored = vec!["keepassxc".to_string(), "1Password.exe".to_string()];
+    assert!(app("KeePassXC.exe", "Passwords.kdbx").is_ignored(&ignored));
+    assert!(app("1password.exe", "1Password").is_ignored(&ignored));
+    // Only whole process names count, not prefixes
+    assert!(!app("keepassxc-helper.exe", "Helper").is_ignored(&ignored));
+}
+
+#[test]
+fn test_ignore_list_matches_window_title_substrings() {
+    let ignored = vec!["InPrivate".to_string(), "  ".to_string()];
+    assert!(app("msedge.exe", "New tab - [InPrivate] - Microsoft Edge").is_ignored(&ignored));
+    assert!(!app("msedge.exe", "New tab - Microsoft Edge").is_ignored(&ignored));
+    assert!(!app("notepad.exe", "notes.txt").is_ignored(&[]));
+}
@@ -0,0 +1,77 @@
+use clipboard_history::hotkey::{Hotkey, MOD_ALT, MOD_CONTROL, MOD_SHIFT, MOD_WIN};
+
+#[test]
+fn test_parse_modifiers_and_letter() {
+    assert_eq!(
+        Hotkey::parse("Ctrl+Shift+V"),
+        Ok(Hotkey {
+            modifiers: MOD_CONTROL | MOD_SHIFT,
+            key_code: 0x56,
+        })
+    );
+    assert_eq!(
+        Hotkey::parse(" alt + ctrl + c "),
+        Ok(Hotkey {
+            modifiers: MOD_ALT | MOD_CONTROL,
+            key_code: 0x43,
+        })
+    );
+}
+
+#[test]
+fn test_parse_function_and_named_keys() {
+    assert_eq!(
+        Hotkey::parse("Win+F9"),
+        Ok(Hotkey {
+            modifiers: MOD_WIN,
+            key_code: 0x78,
+        })
+    );
+    assert_eq!(Hotkey::parse("F24").unwrap().key_code, 0x87);
+    assert_eq!(Hotkey::parse("Ctrl+Space").unwrap().key_code, 0x20);
+    assert_eq!(Hotkey::parse("Ctrl+7").unwrap().key_code, 0x37);
+}
+
+#[test]
+fn test_parse_errors_are_descriptive() {
+    let unknown = Hotkey::parse("Ctrl+Hyper+V").unwrap_err();
+    assert!(unknown.contains("unknown key 'Hyper'"), "{unknown}");
+
+    assert!(Hotkey::parse("Ctrl+Shift")
+        .unwrap_err()
+        .contains("missing a key"));
+    assert!(Hotkey::parse("Ctrl+A+B")
+        .unwrap_err()
+        .contains("only one non-modifier key"));
+    assert!(Hotkey::parse("Ctrl++V")
+        .unwrap_err()
+        .contains("empty key name"));
+    assert!(Hotkey::parse("Ctrl+Ctrl+V")
+        .unwrap_err()
+        .contains("given twice"));
+    assert!(Hotkey::parse("F25").unwrap_err().contains("unknown key"));
+}
+
+#[cfg(all(target_os = "linux", feature = "gui"))]
+#[test]
+fn test_convert_to_global_hotkey() {
+    use global_hotkey::hotkey::{Code, HotKey, Modifiers};
+
+    let hotkey = Hotkey::parse("Ctrl+Shift+V").unwrap().to_global_hotkey();
+    assert_eq!(
+        hotkey,
+        Some(HotKey::new(
+            Some(Modifiers::CONTROL | Modifiers::SHIFT),
+            Code::KeyV
+        ))
+    );
+
+    let hotkey = Hotkey::parse("Win+F12").unwrap().to_global_hotkey();
+    assert_eq!(hotkey, Some(HotKey::new(Some(Modifiers::SUPER), Code::F12)));
+
+    let hotkey = Hotkey::parse("Alt+PageDown").unwrap().to_global_hotkey();
+    assert_eq!(
+        hotkey,
+        Some(HotKey::new(Some(Modifiers::ALT), Code::PageDown))
+    );
+}
@@ -0,0 +1,238 @@
+#![cfg(feature = "gui")]
+
+use chrono::{Duration, Utc};
+use clipboard_history::clipboard_item::ClipboardItem;
+use clipboard_history::clipboard_manager::ClipboardManager;
+use clipboard_history::popup_ui::{
+    clamp_selection, clamp_to_screen, filter_new_since, font_definitions, position_on_screen,
+    preview_pane_text, regex_query, rows_per_page, PopupClipboardUI, PopupConfig, PopupPalette,
+    ScreenBounds, Theme, PREVIEW_PANE_MAX_CHARS,
+};
+use clipboard_history::service::{ClipboardService, SearchResult};
+use eframe::egui;
+use std::sync::Arc;
+
+fn popup_with(config: PopupConfig) -> PopupClipboardUI {
+    let service = ClipboardService::new_with_manager(Arc::new(ClipboardManager::new_empty()));
+    PopupClipboardUI::new(service, config)
+}
+
+#[test]
+fn test_default_viewport_keeps_current_behavior() {
+    let viewport = popup_with(PopupConfig::default()).viewport_builder();
+
+    assert_eq!(viewport.decorations, Some(true));
+    assert_eq!(viewport.close_button, Some(true));
+    assert_eq!(viewport.window_level, Some(egui::WindowLevel::AlwaysOnTop));
+}
+
+#[test]
+fn test_window_config_flows_into_viewport() {
+    let config = PopupConfig {
+        always_on_top: false,
+        decorations: false,
+        show_close_button: false,
+        ..PopupConfig::default()
+    };
+    let viewport = popup_with(config).viewport_builder();
+
+    assert_eq!(viewport.decorations, Some(false));
+    assert_eq!(viewport.close_button, Some(false));
+    assert_eq!(viewport.window_level, Some(egui::WindowLevel::Normal));
+}
+
+#[test]
+fn test_filter_new_since_last_close() {
+    let last_closed = Utc::now() - Duration::minutes(10);
+    let results: Vec<SearchResult> = [
+        ("copied just now", Duration::seconds(5)),
+        ("copied after close", Duration::minutes(9)),
+        ("copied before close", Duration::minutes(11)),
+        ("copied yesterday", Duration::days(1)),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(index, (text, age))| {
+        let mut item = ClipboardItem::new_text(text.to_string());
+        item.timestamp = Utc::now() - age;
+        SearchResult {
+            index,
+            item,
+            score: None,
+        }
+    })
+    .collect();
+
+    let filtered = filter_new_since(results, last_closed);
+    let previews: Vec<String> = filtered.iter().map(|r| r.item.display_content()).collect();
+    assert_eq!(previews, vec!["copied just now", "copied after close"]);
+    // Original history indices are preserved for copy-back
+    assert_eq!(filtered[1].index, 1);
+}
+
+#[test]
+fn test_clamp_to_screen_keeps_popup_on_monitor() {
+    let screen = ScreenBounds {
+        left: 0.0,
+        top: 0.0,
+        right: 2560.0,
+        bottom: 1440.0,
+    };
+
+    // Inside the screen the position is left alone
+    assert_eq!(
+        clamp_to_screen((100.0, 200.0), (400.0, 300.0), screen),
+        (100.0, 200.0)
+    );
+    // Near the bottom-right corner the popup is pulled back on screen
+    assert_eq!(
+        clamp_to_screen((2500.0, 1400.0), (400.0, 300.0), screen),
+        (2160.0, 1140.0)
+    );
+}
+
+#[test]
+fn test_clamp_to_screen_negative_origin_monitor() {
+    // Secondary monitor left of the primary one
+    let screen = ScreenBounds {
+        left: -1920.0,
+        top: 0.0,
+        right: 0.0,
+        bottom: 1080.0,
+    };
+
+    assert_eq!(
+        clamp_to_screen((-1000.0, 500.0), (400.0, 300.0), screen),
+        (-1000.0, 500.0)
+    );
+    assert_eq!(
+        clamp_to_screen((-100.0, 1000.0), (400.0, 300.0), screen),
+        (-400.0, 780.0)
+    );
+    assert_eq!(
+        clamp_to_screen((-2000.0, -50.0), (400.0, 300.0), screen),
+        (-1920.0, 0.0)
+    );
+}
+
+#[test]
+fn test_position_on_screen_detects_disconnected_monitor() {
+    let screen = ScreenBounds {
+        left: -1920.0,
+        top: 0.0,
+        right: 1920.0,
+        bottom: 1080.0,
+    };
+
+    assert!(position_on_screen((100.0, 200.0), screen));
+    assert!(position_on_screen((-1800.0, 50.0), screen));
+    // Saved on a monitor that is no longer there
+    assert!(!position_on_screen((2500.0, 200.0), screen));
+    assert!(!position_on_screen((100.0, -400.0), screen));
+}
+
+#[test]
+fn test_remember_position_is_off_by_default() {
+    assert!(!PopupConfig::default().remember_position);
+}
+
+#[test]
+fn test_regex_query_prefix() {
+    assert_eq!(regex_query("/[0-9]+/"), Some("[0-9]+"));
+    assert_eq!(regex_query("/a/b/"), Some("a/b"));
+    assert_eq!(regex_query("plain text"), None);
+    assert_eq!(regex_query("/unterminated"), None);
+    assert_eq!(regex_query("//"), None);
+    assert_eq!(regex_query("/"), None);
+}
+
+#[test]
+fn test_remembered_selection_is_clamped() {
+    assert_eq!(clamp_selection(3, 10), 3);
+    assert_eq!(clamp_selection(12, 10), 9); // History shrank since the popup closed
+    assert_eq!(clamp_selection(5, 0), 0);
+}
+
+#[test]
+fn test_palette_follows_theme() {
+    assert_eq!(
+        PopupPalette::for_theme(Theme::Light, Some(true)),
+        PopupPalette::LIGHT
+    );
+    assert_eq!(
+        PopupPalette::for_theme(Theme::Dark, Some(false)),
+        PopupPalette::DARK
+    );
+    assert_eq!(
+        PopupPalette::for_theme(Theme::SystemAuto, Some(true)),
+        PopupPalette::DARK
+    );
+    // Unknown OS preference falls back to light
+    assert_eq!(
+        PopupPalette::for_theme(Theme::SystemAuto, None),
+        PopupPalette::LIGHT
+    );
+
+    let dark = PopupPalette::DARK;
+    assert!(dark.visuals().dark_mode);
+    assert_eq!(dark.visuals().panel_fill, dark.background);
+    assert_eq!(dark.row_fill(0, false, false), dark.background);
+    assert_eq!(dark.row_fill(1, false, false), dark.row_stripe);
+    assert_eq!(dark.row_fill(1, true, true), dark.selected_row);
+    assert_eq!(dark.row_fill(2, false, true), dark.multi_selected_row);
+}
+
+#[test]
+fn test_rows_per_page() {
+    assert_eq!(rows_per_page(PopupConfig::default().popup_height), 3);
+    assert_eq!(rows_per_page(700.0), 10);
+    // A tiny popup still pages by one row
+    assert_eq!(rows_per_page(50.0), 1);
+}
+
+#[test]
+fn test_font_definitions_add_valid_fonts_and_skip_others() {
+    let dir =
+        std::env::temp_dir().join(format!("clipboard-history-fonts-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let defaults = egui::FontDefinitions::default();
+
+    // One of egui's own fonts stands in for a CJK font file
+    let valid = dir.join("fallback.ttf");
+    let font_bytes = defaults.font_data.values().next().unwrap().font.to_vec();
+    std::fs::write(&valid, font_bytes).unwrap();
+    let garbage = dir.join("garbage.ttf");
+    std::fs::write(&garbage, b"not a font").unwrap();
+    let missing = dir.join("missing.ttf");
+
+    let fonts = font_definitions(&[missing, garbage, valid.clone()]);
+    let name = valid.display().to_string();
+    assert_eq!(fonts.font_data.len(), defaults.font_data.len() + 1);
+    assert!(fonts.font_data.contains_key(&name));
+    // Used after the built-in fonts, only for characters they lack
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        assert_eq!(fonts.families[&family].last(), Some(&name));
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_preview_pane_text() {
+    let multi_line = ClipboardItem::new_text("fn main() {\n    println!(\"hi\");\n}".to_string());
+    assert_eq!(
+        preview_pane_text(&multi_line),
+        "fn main() {\n    println!(\"hi\");\n}"
+    );
+
+    let long = ClipboardItem::new_text("日".repeat(PREVIEW_PANE_MAX_CHARS + 5));
+    let preview = preview_pane_text(&long);
+    assert_eq!(preview.chars().count(), PREVIEW_PANE_MAX_CHARS + 3);
+    assert!(preview.ends_with("..."));
+
+    let files = ClipboardItem::new_files(vec!["/tmp/a.txt".to_string(), "/tmp/b.txt".to_string()]);
+    assert_eq!(preview_pane_text(&files), "/tmp/a.txt\n/tmp/b.txt");
+
+    let secret = ClipboardItem::new_secret("hunter2".to_string(), "Password");
+    assert!(!preview_pane_text(&secret).contains("hunter2"));
+}
@@ -1,6 +1,9 @@
-use clipboard_history::clipboard_item::ClipboardItem;
+use clipboard_history::clipboard_item::{ClipboardItem, ContentTypeFilter, ImageFormat};
 use clipboard_history::clipboard_manager::ClipboardManager;
+use clipboard_history::error::ClipboardError;
+use clipboard_history::monitor::ClipboardEvent;
 use clipboard_history::service::ClipboardService;
+use clipboard_history::system_clipboard::{ClipboardWrite, MockClipboard};
 use std::sync::Arc;
 
 #[tokio::test]
@@ -41,3 +44,249 @@ async fn test_service_operations() {
     let (exact, fuzzy) = service.search_unified("Test").await;
     assert!(!exact.is_empty() || !fuzzy.is_empty());
 }
+
+#[tokio::test]
+async fn test_service_delete_item() {
+    let manager = Arc::new(ClipboardManager::new_empty());
+    let service = ClipboardService::new_with_manager(manager.clone());
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("sensitive".to_string()))
+        .await
+        .unwrap();
+
+    assert!(service.delete_item(0).await.unwrap());
+    assert!(service.get_history().await.is_empty());
+    assert!(!service.delete_item(0).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_service_counts_and_limits() {
+    let manager = Arc::new(ClipboardManager::new_empty());
+    let service = ClipboardService::new_with_manager(manager.clone());
+    for text in ["one", "three"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(service.get_history_count().await, 2);
+    let stats = service.get_usage_stats().await;
+    assert_eq!((stats.item_count, stats.total_bytes), (2, 8));
+    assert_eq!(
+        service.get_content_limits().max_history_size,
+        service.max_history_size()
+    );
+    assert_eq!(service.get_storage_path(), manager.data_file_path());
+}
+
+#[tokio::test]
+async fn test_service_copy_concatenated() {
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = Arc::new(ClipboardManager::new_empty().with_clipboard(clipboard.clone()));
+    let service = ClipboardService::new_with_manager(manager.clone());
+    for text in ["first", "second"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    service.copy_concatenated(&[1, 0]).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("first\nsecond".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_service_copy_and_remove() {
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = Arc::new(ClipboardManager::new_empty().with_clipboard(clipboard.clone()));
+    let service = ClipboardService::new_with_manager(manager.clone());
+    for text in ["first", "second", "third"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    service.copy_and_remove(1).await.unwrap();
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("second".to_string()))
+    );
+    let contents: Vec<String> = service
+        .get_history()
+        .await
+        .iter()
+        .map(|item| item.display_content())
+        .collect();
+    assert_eq!(contents, vec!["third", "first"]);
+
+    assert!(matches!(
+        service.copy_and_remove(5).await,
+        Err(ClipboardError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_service_copy_and_remove_keeps_item_when_copy_fails() {
+    let clipboard = Arc::new(MockClipboard::new());
+    clipboard.set_panic_on_write(true);
+    let manager = Arc::new(ClipboardManager::new_empty().with_clipboard(clipboard.clone()));
+    let service = ClipboardService::new_with_manager(manager.clone());
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("keep me".to_string()))
+        .await
+        .unwrap();
+    let id = service.get_history().await[0].id.clone();
+
+    assert!(service.copy_and_remove_by_id(&id).await.is_err());
+    assert_eq!(service.get_history_count().await, 1);
+}
+
+#[tokio::test]
+async fn test_service_search_filtered() {
+    let manager = Arc::new(ClipboardManager::new_empty());
+    let service = ClipboardService::new_with_manager(manager.clone());
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("report.txt contents".to_string()))
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_files(vec!["report.txt".to_string()]))
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_image(
+            vec![1, 2, 3],
+            ImageFormat::Png,
+            2,
+            2,
+        ))
+        .await
+        .unwrap();
+
+    let files = service
+        .search_filtered("report", ContentTypeFilter::Files)
+        .await;
+    assert_eq!(files.len(), 1);
+    assert!(matches!(
+        files[0].1.content,
+        clipboard_history::clipboard_item::ClipboardContentType::Files(_)
+    ));
+
+    // An empty query lists every item of the selected type
+    let images = service.search_filtered("", ContentTypeFilter::Image).await;
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].0, 0);
+
+    let all = service.search_filtered("", ContentTypeFilter::All).await;
+    assert_eq!(all.len(), 3);
+}
+
+#[tokio::test]
+async fn test_service_search_unified_by_tag() {
+    let manager = Arc::new(ClipboardManager::new_empty());
+    let service = ClipboardService::new_with_manager(manager.clone());
+    for text in ["work notes", "holiday plans"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    assert!(service.add_tag(1, "work").await.unwrap());
+
+    // "#work" matches the tag, not the text "work notes"
+    let (exact, fuzzy) = service.search_unified("#work").await;
+    assert!(fuzzy.is_empty());
+    assert_eq!(exact.len(), 1);
+    assert_eq!(exact[0].item.display_content(), "work notes");
+    assert!(exact[0].item.has_tag("WORK"));
+}
+
+#[tokio::test]
+async fn test_ephemeral_service_keeps_history_in_memory() {
+    let service = ClipboardService::new_ephemeral().await.unwrap();
+    assert!(service.get_history().await.is_empty());
+    assert_eq!(service.get_storage_path(), std::path::Path::new(":memory:"));
+
+    service.clear_history().await.unwrap();
+    assert!(!std::path::Path::new(":memory:").exists());
+}
+
+#[tokio::test]
+async fn test_subscribe_gives_independent_receivers() {
+    let service = ClipboardService::new_ephemeral().await.unwrap();
+    let mut first = service.subscribe().unwrap();
+    let mut second = service.subscribe().unwrap();
+
+    service.pause_monitoring();
+    assert!(matches!(first.try_recv(), Ok(ClipboardEvent::Paused)));
+    assert!(matches!(second.try_recv(), Ok(ClipboardEvent::Paused)));
+
+    let without_monitor =
+        ClipboardService::new_with_manager(Arc::new(ClipboardManager::new_empty()));
+    assert!(without_monitor.subscribe().is_none());
+}
+
+#[tokio::test]
+async fn test_stop_monitoring_ends_the_monitor_task() {
+    let mut service = ClipboardService::new_ephemeral().await.unwrap();
+    let mut events = service.start_monitoring().unwrap();
+    // Starting again while running doesn't spawn a second monitor
+    let _second = service.start_monitoring().unwrap();
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), service.stop_monitoring())
+        .await
+        .expect("monitoring did not stop");
+    let mut stopped = 0;
+    while let Ok(event) = events.try_recv() {
+        if matches!(event, ClipboardEvent::Stopped) {
+            stopped += 1;
+        }
+    }
+    assert_eq!(stopped, 1);
+
+    // Stopping again, or a service without a monitor, is a no-op
+    service.stop_monitoring().await;
+    ClipboardService::new_with_manager(Arc::new(ClipboardManager::new_empty()))
+        .stop_monitoring()
+        .await;
+}
+
+#[tokio::test]
+async fn test_add_items_without_the_clipboard() {
+    let manager = Arc::new(ClipboardManager::new_empty());
+    let service = ClipboardService::new_with_manager(Arc::clone(&manager));
+    let mut events = manager.event_sender().subscribe();
+
+    service.add_text("snippet one".to_string()).await.unwrap();
+    service
+        .add_item(ClipboardItem::new_text("snippet two".to_string()))
+        .await
+        .unwrap();
+    // Deduplicated like a copy: moved to the top instead of added twice
+    service.add_text("snippet one".to_string()).await.unwrap();
+
+    let texts: Vec<String> = service
+        .get_history()
+        .await
+        .iter()
+        .map(|item| item.display_content())
+        .collect();
+    assert_eq!(texts, vec!["snippet one", "snippet two"]);
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClipboardEvent::ItemAdded { ref preview, .. }) if preview == "snippet one"
+    ));
+
+    // The size limit applies too
+    let too_large = "x".repeat(service.get_content_limits().max_content_size + 1);
+    assert!(matches!(
+        service.add_text(too_large).await,
+        Err(ClipboardError::ContentTooLarge { .. })
+    ));
+    assert_eq!(service.get_history_count().await, 2);
+}
@@ -0,0 +1,703 @@
+use clipboard_history::clipboard_item::{ClipboardItem, ImageFormat};
+use clipboard_history::clipboard_manager::{ClipboardManager, LOAD_BATCH_SIZE};
+use clipboard_history::error::ClipboardError;
+use clipboard_history::sqlite_storage::SqliteStorage;
+use clipboard_history::storage::{self, JsonStorage, SaveStrategy, Storage};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+fn temp_history_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "clipboard-history-storage-test-{}.json",
+        uuid::Uuid::new_v4()
+    ))
+}
+
+fn sample_history() -> VecDeque<ClipboardItem> {
+    VecDeque::from(vec![
+        ClipboardItem::new_text("my secret token".to_string()),
+        ClipboardItem::new_text("second".to_string()),
+    ])
+}
+
+#[tokio::test]
+async fn test_encrypted_round_trip() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_passphrase("correct horse")
+        .unwrap();
+    storage.save_history(&sample_history()).await.unwrap();
+
+    let raw = std::fs::read(&path).unwrap();
+    assert!(!String::from_utf8_lossy(&raw).contains("my secret token"));
+
+    let reopened = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_passphrase("correct horse")
+        .unwrap();
+    let loaded = reopened.load_history().await.unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded[0].display_content(), "my secret token");
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_wrong_passphrase_is_an_error() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_passphrase("correct horse")
+        .unwrap();
+    storage.save_history(&sample_history()).await.unwrap();
+
+    let wrong = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_passphrase("battery staple")
+        .unwrap();
+    assert!(matches!(
+        wrong.load_history().await,
+        Err(ClipboardError::Encryption(_))
+    ));
+
+    let no_passphrase = JsonStorage::new_with_file(path.clone()).unwrap();
+    assert!(matches!(
+        no_passphrase.load_history().await,
+        Err(ClipboardError::Encryption(_))
+    ));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_plaintext_history_still_loads_with_passphrase() {
+    let path = temp_history_path();
+    JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .save_history(&sample_history())
+        .await
+        .unwrap();
+
+    let storage = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_passphrase("correct horse")
+        .unwrap();
+    let loaded = storage.load_history().await.unwrap();
+    assert_eq!(loaded.len(), 2);
+
+    // The next save encrypts the migrated history
+    storage.save_history(&loaded).await.unwrap();
+    let raw = std::fs::read(&path).unwrap();
+    assert!(!String::from_utf8_lossy(&raw).contains("second"));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_compressed_round_trip_with_large_image() {
+    let path = temp_history_path();
+    let image_bytes: Vec<u8> = (0..4_000_000u32).map(|i| (i / 4000) as u8).collect();
+    let mut history = sample_history();
+    history.push_front(ClipboardItem::new_image(
+        image_bytes.clone(),
+        ImageFormat::Png,
+        2000,
+        500,
+    ));
+
+    let storage = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_compression(true);
+    storage.save_history(&history).await.unwrap();
+
+    let raw = std::fs::read(&path).unwrap();
+    assert!(raw.starts_with(&[0x1f, 0x8b]));
+    assert!(raw.len() < image_bytes.len() / 10);
+
+    // Loading doesn't depend on the compression setting
+    let loaded = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    assert_eq!(loaded.len(), 3);
+    assert_eq!(loaded[0].content_hash, history[0].content_hash);
+    assert_eq!(loaded[1].display_content(), "my secret token");
+
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(path.with_extension("json.bak"));
+}
+
+fn temp_db_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "clipboard-history-storage-test-{}.db",
+        uuid::Uuid::new_v4()
+    ))
+}
+
+#[tokio::test]
+async fn test_sqlite_round_trip_keeps_order() {
+    let path = temp_db_path();
+    let storage = SqliteStorage::new_with_file(path.clone(), None).unwrap();
+    let mut history = sample_history();
+    storage.save_history(&history).await.unwrap();
+
+    // Adding at the front and trimming at the back touch only those rows
+    history.push_front(ClipboardItem::new_text("newest".to_string()));
+    history.pop_back();
+    storage.save_history(&history).await.unwrap();
+
+    let reopened = SqliteStorage::new_with_file(path.clone(), None).unwrap();
+    let loaded = reopened.load_history().await.unwrap();
+    let contents: Vec<String> = loaded.iter().map(|item| item.display_content()).collect();
+    assert_eq!(contents, vec!["newest", "my secret token"]);
+    assert_eq!(reopened.get_data_file_path(), path.as_path());
+
+    // Changed items are rewritten in place
+    let mut loaded = loaded;
+    loaded[1].last_used = Some(chrono::Utc::now());
+    reopened.save_history(&loaded).await.unwrap();
+    let again = SqliteStorage::new_with_file(path.clone(), None)
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    assert_eq!(again.len(), 2);
+    assert!(again[1].last_used.is_some());
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_sqlite_migrates_existing_json_history() {
+    let json_path = temp_history_path();
+    let db_path = temp_db_path();
+    JsonStorage::new_with_file(json_path.clone())
+        .unwrap()
+        .save_history(&sample_history())
+        .await
+        .unwrap();
+
+    let storage = SqliteStorage::new_with_file(db_path.clone(), Some(json_path.clone())).unwrap();
+    let migrated = storage.load_history().await.unwrap();
+    assert_eq!(migrated.len(), 2);
+    assert_eq!(migrated[0].display_content(), "my secret token");
+    assert!(!json_path.exists());
+
+    // The rows were written, so a fresh open reads them from the database
+    let reopened = SqliteStorage::new_with_file(db_path.clone(), Some(json_path.clone())).unwrap();
+    assert_eq!(reopened.load_history().await.unwrap().len(), 2);
+
+    let _ = std::fs::remove_file(json_path.with_extension("json.migrated"));
+    let _ = std::fs::remove_file(db_path);
+}
+
+#[tokio::test]
+async fn test_sqlite_refuses_to_migrate_an_encrypted_history() {
+    let json_path = temp_history_path();
+    let db_path = temp_db_path();
+    JsonStorage::new_with_file(json_path.clone())
+        .unwrap()
+        .with_passphrase("correct horse")
+        .unwrap()
+        .save_history(&sample_history())
+        .await
+        .unwrap();
+
+    let storage = SqliteStorage::new_with_file(db_path.clone(), Some(json_path.clone())).unwrap();
+    assert!(matches!(
+        storage.load_history().await,
+        Err(ClipboardError::Encryption(_))
+    ));
+    // The encrypted file stays where it was and nothing reached the database
+    assert!(json_path.exists());
+    let db = std::fs::read(&db_path).unwrap();
+    assert!(!db.windows(b"secret".len()).any(|w| w == b"secret"));
+    let reopened = SqliteStorage::new_with_file(db_path.clone(), None).unwrap();
+    assert!(reopened.load_history().await.unwrap().is_empty());
+
+    let _ = std::fs::remove_file(json_path);
+    let _ = std::fs::remove_file(db_path);
+}
+
+#[tokio::test]
+async fn test_manager_with_sqlite_storage() {
+    let path = temp_db_path();
+    let manager = ClipboardManager::new_with_storage(Box::new(
+        SqliteStorage::new_with_file(path.clone(), None).unwrap(),
+    ))
+    .await
+    .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("first".to_string()))
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("second".to_string()))
+        .await
+        .unwrap();
+
+    let reloaded = SqliteStorage::new_with_file(path.clone(), None)
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    assert_eq!(reloaded.len(), 2);
+    assert_eq!(reloaded[0].display_content(), "second");
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_incremental_save_appends_and_compacts() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_save_strategy(SaveStrategy::Incremental { compact_every: 3 });
+    let mut history = storage.load_history().await.unwrap();
+
+    history.push_front(ClipboardItem::new_text("one".to_string()));
+    storage.save_history(&history).await.unwrap();
+    history.push_front(ClipboardItem::new_text("two".to_string()));
+    storage.save_history(&history).await.unwrap();
+    history[1].last_used = Some(chrono::Utc::now());
+    storage.save_history(&history).await.unwrap();
+
+    // Only the log has been written so far; loading replays it
+    assert!(storage.log_file_path().exists());
+    assert!(!path.exists());
+    let reopened = JsonStorage::new_with_file(path.clone()).unwrap();
+    let loaded = reopened.load_history().await.unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded[0].display_content(), "two");
+    assert!(loaded[1].last_used.is_some());
+
+    // Crossing the threshold compacts into the main file
+    history.pop_back();
+    history.push_front(ClipboardItem::new_text("three".to_string()));
+    storage.save_history(&history).await.unwrap();
+    assert!(!storage.log_file_path().exists());
+    let on_disk: Vec<ClipboardItem> =
+        serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(on_disk.len(), 2);
+    assert_eq!(on_disk[0].display_content(), "three");
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_torn_log_line_and_flush() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_save_strategy(SaveStrategy::Incremental { compact_every: 100 });
+    let mut history = storage.load_history().await.unwrap();
+    history.push_front(ClipboardItem::new_text("kept".to_string()));
+    storage.save_history(&history).await.unwrap();
+    history.push_front(ClipboardItem::new_text("logged".to_string()));
+    storage.save_history(&history).await.unwrap();
+
+    // Simulate a crash in the middle of appending the next record
+    let mut log = std::fs::OpenOptions::new()
+        .append(true)
+        .open(storage.log_file_path())
+        .unwrap();
+    std::io::Write::write_all(&mut log, b"{\"op\":\"push\",\"item\":{\"id\":").unwrap();
+
+    let loaded = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    let contents: Vec<String> = loaded.iter().map(|item| item.display_content()).collect();
+    assert_eq!(contents, vec!["logged", "kept"]);
+
+    storage.flush(&history).await.unwrap();
+    assert!(!storage.log_file_path().exists());
+    let on_disk: Vec<ClipboardItem> =
+        serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(on_disk.len(), 2);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_encrypted_incremental_log() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_passphrase("correct horse")
+        .unwrap()
+        .with_save_strategy(SaveStrategy::Incremental { compact_every: 10 });
+    let mut history = storage.load_history().await.unwrap();
+    history.push_front(ClipboardItem::new_text("first".to_string()));
+    storage.save_history(&history).await.unwrap();
+    history.push_front(ClipboardItem::new_text("my secret token".to_string()));
+    storage.save_history(&history).await.unwrap();
+
+    let log = std::fs::read_to_string(storage.log_file_path()).unwrap();
+    assert!(!log.contains("my secret token"));
+
+    let loaded = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_passphrase("correct horse")
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    assert_eq!(loaded[0].display_content(), "my secret token");
+
+    let _ = std::fs::remove_file(storage.log_file_path());
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_save_keeps_backup_and_leaves_no_temp_file() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone()).unwrap();
+    let mut history = sample_history();
+    storage.save_history(&history).await.unwrap();
+    history.push_front(ClipboardItem::new_text("third".to_string()));
+    storage.save_history(&history).await.unwrap();
+
+    assert!(!path.with_extension("json.tmp").exists());
+    let backup: Vec<ClipboardItem> =
+        serde_json::from_slice(&std::fs::read(storage.backup_file_path()).unwrap()).unwrap();
+    assert_eq!(backup.len(), 2);
+
+    let _ = std::fs::remove_file(storage.backup_file_path());
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_corrupt_history_falls_back_to_backup() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone()).unwrap();
+    storage.save_history(&sample_history()).await.unwrap();
+    storage.save_history(&sample_history()).await.unwrap();
+
+    // Simulate a file truncated by a crash outside our control
+    std::fs::write(&path, "[{\"id\": \"trunc").unwrap();
+
+    let reopened = JsonStorage::new_with_file(path.clone()).unwrap();
+    let loaded = reopened.load_history().await.unwrap();
+    assert_eq!(loaded.len(), 2);
+
+    // Saving the recovered history must not replace the good backup with the broken file
+    reopened.save_history(&loaded).await.unwrap();
+    let backup: Vec<ClipboardItem> =
+        serde_json::from_slice(&std::fs::read(reopened.backup_file_path()).unwrap()).unwrap();
+    assert_eq!(backup.len(), 2);
+
+    let _ = std::fs::remove_file(reopened.backup_file_path());
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_corrupt_history_without_backup_loads_empty() {
+    let path = temp_history_path();
+    std::fs::write(&path, "not json").unwrap();
+
+    let storage = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_backup(false);
+    assert!(storage.load_history().await.unwrap().is_empty());
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_two_managers_on_one_file_merge_instead_of_overwriting() {
+    let path = temp_history_path();
+    let open = || async {
+        ClipboardManager::new_with_storage(Box::new(
+            JsonStorage::new_with_file(path.clone()).unwrap(),
+        ))
+        .await
+        .unwrap()
+    };
+    let first = open().await;
+    let second = open().await;
+    assert!(!second.history_changed_externally());
+
+    first
+        .add_clipboard_item(ClipboardItem::new_text("from first".to_string()))
+        .await
+        .unwrap();
+    assert!(second.history_changed_externally());
+    second
+        .add_clipboard_item(ClipboardItem::new_text("from second".to_string()))
+        .await
+        .unwrap();
+    assert!(!second.history_changed_externally());
+
+    // The second save kept the first instance's item
+    let saved = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    let contents: Vec<String> = saved.iter().map(|item| item.display_content()).collect();
+    assert_eq!(contents, vec!["from second", "from first"]);
+
+    // And the first instance picks up the second one's item on reload
+    assert_eq!(first.reload().await.unwrap(), 1);
+    assert_eq!(first.reload().await.unwrap(), 0);
+    assert_eq!(first.get_history().await.len(), 2);
+
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(path.with_extension("json.bak"));
+}
+
+async fn saved_contents(path: &std::path::Path) -> Vec<String> {
+    JsonStorage::new_with_file(path.to_path_buf())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap()
+        .iter()
+        .map(|item| item.display_content())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_deleting_on_one_instance_while_the_other_saves() {
+    // Either instance may save first
+    for deleter_saves_first in [true, false] {
+        let path = temp_history_path();
+        JsonStorage::new_with_file(path.clone())
+            .unwrap()
+            .save_history(&VecDeque::from(vec![
+                ClipboardItem::new_text("kept".to_string()),
+                ClipboardItem::new_text("deleted".to_string()),
+            ]))
+            .await
+            .unwrap();
+        let open = || async {
+            ClipboardManager::new_with_storage(Box::new(
+                JsonStorage::new_with_file(path.clone()).unwrap(),
+            ))
+            .await
+            .unwrap()
+        };
+        let deleter = open().await;
+        let saver = open().await;
+
+        let save = || async {
+            saver
+                .add_clipboard_item(ClipboardItem::new_text("new".to_string()))
+                .await
+                .unwrap()
+        };
+        if !deleter_saves_first {
+            save().await;
+        }
+        assert!(deleter.delete_item(1).await.unwrap());
+        if deleter_saves_first {
+            save().await;
+        }
+
+        // Neither the merge on save nor the one on reload brings the item back
+        assert_eq!(saved_contents(&path).await, vec!["new", "kept"]);
+        deleter.reload().await.unwrap();
+        saver.reload().await.unwrap();
+        for manager in [&deleter, &saver] {
+            let contents: Vec<String> = manager
+                .get_history()
+                .await
+                .iter()
+                .map(|item| item.display_content())
+                .collect();
+            assert_eq!(
+                contents,
+                vec!["new", "kept"],
+                "deleter saves first: {deleter_saves_first}"
+            );
+        }
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(path.with_extension("json.bak"));
+    }
+}
+
+#[tokio::test]
+async fn test_loads_pretty_and_compact_history_arrays() {
+    // Older versions wrote a pretty-printed array without the newer item fields
+    let legacy = r#"[
+  {
+    "id": "new",
+    "content": { "Text": "newest" },
+    "timestamp": "2024-01-02T00:00:00Z",
+    "content_hash": "h2"
+  },
+  {
+    "id": "old",
+    "content": { "Text": "oldest" },
+    "timestamp": "2024-01-01T00:00:00Z",
+    "content_hash": "h1"
+  }
+]
+"#;
+    for content in [legacy.to_string(), legacy.split_whitespace().collect()] {
+        let path = temp_history_path();
+        std::fs::write(&path, content).unwrap();
+
+        let loaded = JsonStorage::new_with_file(path.clone())
+            .unwrap()
+            .load_history()
+            .await
+            .unwrap();
+        let ids: Vec<&str> = loaded.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["new", "old"]);
+        assert!(loaded.iter().all(|item| item.use_count == 0));
+
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[tokio::test]
+async fn test_compressed_and_encrypted_round_trip() {
+    let path = temp_history_path();
+    let storage = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_compression(true)
+        .with_passphrase("correct horse")
+        .unwrap();
+    storage.save_history(&sample_history()).await.unwrap();
+
+    let reopened = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .with_passphrase("correct horse")
+        .unwrap();
+    let loaded = reopened.load_history().await.unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded[0].display_content(), "my secret token");
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_in_memory_storage_never_persists() {
+    let manager = ClipboardManager::new_with_storage(storage::in_memory())
+        .await
+        .unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("incognito".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(manager.get_history().await.len(), 1);
+    assert!(!manager.data_file_path().exists());
+
+    // A fresh in-memory storage starts empty; nothing was written anywhere
+    let storage = storage::in_memory();
+    storage.save_history(&sample_history()).await.unwrap();
+    assert!(storage.load_history().await.unwrap().is_empty());
+}
+
+#[test]
+fn test_resolve_data_dir_overrides() {
+    let default_dir = storage::resolve_data_dir(None);
+    assert!(default_dir.ends_with("clipboard-history"));
+    assert_eq!(storage::resolve_data_dir(Some(PathBuf::new())), default_dir);
+
+    let absolute = std::env::temp_dir().join("clips");
+    assert_eq!(storage::resolve_data_dir(Some(absolute.clone())), absolute);
+
+    // Relative paths are kept next to the executable
+    let exe_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    assert_eq!(
+        storage::resolve_data_dir(Some(PathBuf::from("data"))),
+        exe_dir.join("data")
+    );
+}
+
+fn numbered_history(count: usize) -> VecDeque<ClipboardItem> {
+    (0..count)
+        .map(|n| ClipboardItem::new_text(format!("item {n}")))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_batched_load_passes_the_history_on_in_order() {
+    for compress in [false, true] {
+        let path = temp_history_path();
+        let storage = JsonStorage::new_with_file(path.clone())
+            .unwrap()
+            .with_compression(compress);
+        storage.save_history(&numbered_history(25)).await.unwrap();
+
+        let mut batches = Vec::new();
+        storage
+            .load_history_batched(10, &mut |batch| batches.push(batch))
+            .await
+            .unwrap();
+        let sizes: Vec<usize> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![10, 10, 5], "compressed: {compress}");
+        assert_eq!(batches[0][0].display_content(), "item 0");
+        assert_eq!(batches[2][4].display_content(), "item 24");
+
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[tokio::test]
+async fn test_streaming_manager_holds_back_saves_until_loaded() {
+    let path = temp_history_path();
+    let total = LOAD_BATCH_SIZE * 2 + 50;
+    let mut history = numbered_history(total);
+    // Also in the part read last, so the copy made while loading must be merged with it
+    history.push_back(ClipboardItem::new_text("copied again".to_string()));
+    history.back_mut().unwrap().use_count = 4;
+    JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .save_history(&history)
+        .await
+        .unwrap();
+
+    let manager = ClipboardManager::new_streaming_with_limit(
+        Box::new(JsonStorage::new_with_file(path.clone()).unwrap()),
+        10_000,
+    )
+    .await
+    .unwrap();
+    assert!(manager.is_loading());
+    assert_eq!(manager.get_history_count().await, LOAD_BATCH_SIZE);
+
+    // Saving now would drop the items not read yet
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("copied again".to_string()))
+        .await
+        .unwrap();
+    let on_disk = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    assert_eq!(on_disk.len(), total + 1);
+
+    manager.finish_loading().await.unwrap();
+    assert!(!manager.is_loading());
+    let loaded = manager.get_history().await;
+    assert_eq!(loaded.len(), total + 1);
+    assert_eq!(loaded[0].display_content(), "copied again");
+    assert_eq!(loaded[0].use_count, 5);
+    assert_eq!(loaded[1].display_content(), "item 0");
+
+    // The save held back while loading was made once the whole history was there
+    let on_disk = JsonStorage::new_with_file(path.clone())
+        .unwrap()
+        .load_history()
+        .await
+        .unwrap();
+    let ids: Vec<&str> = on_disk.iter().map(|item| item.id.as_str()).collect();
+    let expected: Vec<&str> = loaded.iter().map(|item| item.id.as_str()).collect();
+    assert_eq!(ids, expected);
+
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(path.with_extension("json.bak"));
+}
@@ -0,0 +1,81 @@
+use clipboard_history::clipboard_item::{ClipboardContentType, ClipboardItem, ImageFormat};
+use clipboard_history::clipboard_manager::ClipboardManager;
+use clipboard_history::thumbnail::{stored_thumbnail, Thumbnail, THUMBNAIL_SIZE};
+
+fn png_item(width: u32, height: u32) -> ClipboardItem {
+    let buffer = image::RgbaImage::from_pixel(width, height, image::Rgba([10, 20, 30, 255]));
+    let mut png = Vec::new();
+    buffer
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    ClipboardItem::new_image(png, ImageFormat::Png, width, height)
+}
+
+#[test]
+fn test_thumbnail_fits_size_and_keeps_aspect_ratio() {
+    let thumbnail = Thumbnail::render(&png_item(200, 100)).unwrap();
+    assert_eq!(thumbnail.width, THUMBNAIL_SIZE);
+    assert_eq!(thumbnail.height, THUMBNAIL_SIZE / 2);
+    assert_eq!(
+        thumbnail.rgba.len(),
+        (thumbnail.width * thumbnail.height * 4) as usize
+    );
+    assert_eq!(&thumbnail.rgba[..4], &[10, 20, 30, 255]);
+
+    assert!(Thumbnail::render(&ClipboardItem::new_text("not an image".to_string())).is_none());
+}
+
+#[test]
+fn test_stored_thumbnail_used_instead_of_full_image() {
+    let rgba = image::RgbaImage::from_pixel(300, 150, image::Rgba([40, 50, 60, 255])).into_raw();
+    let stored = stored_thumbnail(&rgba, 300, 150).unwrap();
+    assert!(stored_thumbnail(&rgba, 301, 150).is_none());
+
+    // The full image data is unusable, so only the stored thumbnail can be rendered
+    let item = ClipboardItem::new(ClipboardContentType::Image {
+        data: "not base64!".to_string(),
+        format: ImageFormat::Png,
+        width: 300,
+        height: 150,
+        thumbnail: Some(stored),
+    });
+    let thumbnail = Thumbnail::render(&item).unwrap();
+    assert_eq!(thumbnail.width, THUMBNAIL_SIZE);
+    assert_eq!(thumbnail.height, THUMBNAIL_SIZE / 2);
+    assert_eq!(&thumbnail.rgba[..4], &[40, 50, 60, 255]);
+}
+
+#[test]
+fn test_preview_is_larger_than_thumbnail_but_never_upscaled() {
+    let preview = Thumbnail::render_preview(&png_item(1000, 500), 400).unwrap();
+    assert_eq!((preview.width, preview.height), (400, 200));
+
+    let small = Thumbnail::render_preview(&png_item(60, 30), 400).unwrap();
+    assert_eq!((small.width, small.height), (60, 30));
+
+    assert!(Thumbnail::render_preview(&ClipboardItem::new_text("text".to_string()), 400).is_none());
+}
+
+#[tokio::test]
+async fn test_thumbnails_precomputed_on_add_and_dropped_on_delete() {
+    let manager = ClipboardManager::new_empty();
+    let thumbnails = manager.thumbnails();
+
+    manager.add_clipboard_item(png_item(64, 64)).await.unwrap();
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("text".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(thumbnails.len(), 1);
+
+    // Asking again returns the cached thumbnail rather than a fresh render
+    let image = manager.get_history().await.remove(1);
+    let first = thumbnails.get(&image).unwrap();
+    assert!(std::sync::Arc::ptr_eq(
+        &first,
+        &thumbnails.get(&image).unwrap()
+    ));
+
+    manager.delete_item(1).await.unwrap();
+    assert!(thumbnails.is_empty());
+}
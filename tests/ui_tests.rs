@@ -0,0 +1,326 @@
+use clipboard_history::clipboard_item::{ClipboardItem, GroupMode};
+use clipboard_history::clipboard_manager::ClipboardManager;
+use clipboard_history::config::{ConsoleView, PopupConfig};
+use clipboard_history::monitor::ClipboardEvent;
+use clipboard_history::service::ClipboardService;
+use clipboard_history::system_clipboard::{ClipboardWrite, MockClipboard};
+use clipboard_history::ui::ConsoleInterface;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+
+async fn service_with(texts: &[&str]) -> (ClipboardService, Arc<MockClipboard>) {
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = Arc::new(ClipboardManager::new_empty().with_clipboard(clipboard.clone()));
+    for text in texts {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    (ClipboardService::new_with_manager(manager), clipboard)
+}
+
+async fn run_console(console: &mut ConsoleInterface, input: &str) -> String {
+    let mut output = Vec::new();
+    console
+        .run_with(input.as_bytes(), &mut output)
+        .await
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[tokio::test]
+async fn test_console_lists_and_copies_history() {
+    let (service, clipboard) = service_with(&["first", "second"]).await;
+    let mut console = ConsoleInterface::new(service, None);
+
+    let output = run_console(&mut console, "\n2\n9\nq\nexit\nnever read\n").await;
+
+    assert!(output.contains("  1. second"));
+    assert!(output.contains("  2. first"));
+    assert!(output.contains("✅ Copied to clipboard"));
+    assert!(output.contains("Unknown choice '9'"));
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("first".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_console_search_copies_a_result() {
+    let (service, clipboard) =
+        service_with(&["cargo build --release", "grocery list", "cargo test"]).await;
+    let mut console = ConsoleInterface::new(service, None);
+
+    let output = run_console(&mut console, "\ns\nrelease\n1\nzzzz\nq\nq\n").await;
+
+    assert!(output.contains("Found 1 item(s):"));
+    assert!(output.contains("No items match 'zzzz'"));
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("cargo build --release".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_console_announces_new_items() {
+    let (service, _clipboard) = service_with(&[]).await;
+    let (sender, receiver) = broadcast::channel(10);
+    let mut console = ConsoleInterface::new(service, Some(receiver));
+    sender
+        .send(ClipboardEvent::ItemAdded {
+            id: "abc".to_string(),
+            preview: "fresh copy".to_string(),
+            content_type: "Text",
+        })
+        .unwrap();
+
+    // Input ending early quits cleanly
+    let output = run_console(&mut console, "\n").await;
+
+    assert!(output.contains("📥 New Text: fresh copy"));
+    assert!(output.contains("No clipboard history yet"));
+}
+
+#[tokio::test]
+async fn test_console_clears_history_after_confirmation() {
+    let (service, _) = service_with(&["keep me", "and me"]).await;
+    let mut console = ConsoleInterface::new(service.clone(), None);
+
+    let output = run_console(&mut console, "\nc\nn\nc\ny\nq\nexit\n").await;
+
+    assert!(output.contains("(2 items)"));
+    assert!(output.contains("History kept"));
+    assert!(output.contains("🗑 History cleared"));
+    assert!(output.contains("No clipboard history yet"));
+    assert_eq!(service.get_history_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_console_undoes_a_clear() {
+    let (service, _) = service_with(&["precious"]).await;
+    let mut console = ConsoleInterface::new(service.clone(), None);
+
+    let output = run_console(&mut console, "\nc\ny\nu\nu\nq\nexit\n").await;
+
+    assert!(output.contains("↩ History restored"));
+    assert!(output.contains("Nothing to undo"));
+    assert_eq!(service.get_history_count().await, 1);
+}
+
+#[tokio::test]
+async fn test_console_reports_why_a_copy_failed() {
+    let (service, clipboard) = service_with(&["fragile"]).await;
+    clipboard.set_panic_on_write(true);
+    let mut console = ConsoleInterface::new(service, None);
+
+    let output = run_console(&mut console, "\n1\nq\nexit\n").await;
+
+    assert!(output.contains("❌ Failed to copy to clipboard: Clipboard unavailable"));
+    assert!(output.contains("mock clipboard write panicked"));
+}
+
+#[tokio::test]
+async fn test_console_groups_history_by_content_type() {
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = Arc::new(ClipboardManager::new_empty().with_clipboard(clipboard.clone()));
+    for item in [
+        ClipboardItem::new_text("older text".to_string()),
+        ClipboardItem::new_files(vec!["/tmp/report.pdf".to_string()]),
+        ClipboardItem::new_text("newest text".to_string()),
+    ] {
+        manager.add_clipboard_item(item).await.unwrap();
+    }
+    let service = ClipboardService::new_with_manager(manager);
+    let mut console = ConsoleInterface::new(service, None).with_group_mode(GroupMode::ContentType);
+
+    let output = run_console(&mut console, "\n3\nq\nexit\n").await;
+
+    let text_header = output.find("── Text ──").unwrap();
+    let files_header = output.find("── Files ──").unwrap();
+    assert!(text_header < output.find("  2. older text").unwrap());
+    assert!(files_header > output.find("  2. older text").unwrap());
+    assert!(output.contains("  3. File: /tmp/report.pdf"));
+    // Numbers follow the grouped listing
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Files(vec!["/tmp/report.pdf".into()]))
+    );
+}
+
+#[tokio::test]
+async fn test_console_lists_at_most_max_results() {
+    let texts: Vec<String> = (1..=5).map(|n| format!("entry {n}")).collect();
+    let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let (service, _) = service_with(&texts).await;
+    let mut console = ConsoleInterface::new(service, None).with_max_results(2);
+
+    let output = run_console(&mut console, "\n3\ns\nentry\nq\nq\nexit\n").await;
+
+    assert!(output.contains("  2. entry 4"));
+    assert!(!output.contains("  3. entry 3"));
+    assert!(output.contains("(showing 2 of 5)"));
+    assert!(output.contains("Unknown choice '3'"));
+    assert!(output.contains("Found 5 item(s):"));
+}
+
+/// Console output shared with the test while the console runs in its own task
+#[derive(Clone, Default)]
+struct SharedOutput(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedOutput {
+    async fn wait_for(&self, text: &str) {
+        for _ in 0..500 {
+            if String::from_utf8_lossy(&self.0.lock().unwrap()).contains(text) {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("console never printed '{text}'");
+    }
+}
+
+#[tokio::test]
+async fn test_console_search_copies_the_listed_item_after_a_new_capture() {
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = Arc::new(ClipboardManager::new_empty().with_clipboard(clipboard.clone()));
+    for text in ["cargo build --release", "grocery list"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    let mut console =
+        ConsoleInterface::new(ClipboardService::new_with_manager(manager.clone()), None);
+    let (mut input, console_input) = tokio::io::duplex(1024);
+    let output = SharedOutput::default();
+    let mut console_output = output.clone();
+    let console_task = tokio::spawn(async move {
+        console
+            .run_with(
+                tokio::io::BufReader::new(console_input),
+                &mut console_output,
+            )
+            .await
+            .unwrap();
+    });
+
+    input.write_all(b"\ns\nrelease\n").await.unwrap();
+    output.wait_for("Found 1 item(s):").await;
+    // Captured while the results are on screen, shifting every history index
+    manager
+        .add_clipboard_item(ClipboardItem::new_text("just copied".to_string()))
+        .await
+        .unwrap();
+    input.write_all(b"1\nq\nq\nexit\n").await.unwrap();
+    console_task.await.unwrap();
+
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("cargo build --release".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_console_copies_by_id_prefix() {
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = Arc::new(ClipboardManager::new_empty().with_clipboard(clipboard.clone()));
+    for (id, text) in [("ab12", "alpha"), ("ab34", "beta")] {
+        let mut item = ClipboardItem::new_text(text.to_string());
+        item.id = id.to_string();
+        manager.add_clipboard_item(item).await.unwrap();
+    }
+    let mut console = ConsoleInterface::new(ClipboardService::new_with_manager(manager), None);
+
+    let output = run_console(&mut console, "\nid ab3\nid zzzz\nid ab\nq\nexit\n").await;
+
+    assert!(output.contains("✅ Copied to clipboard"));
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("beta".to_string()))
+    );
+    assert!(output.contains("❌ Item not found: id prefix zzzz"));
+    assert!(output.contains("❌ Id prefix 'ab' matches 2 items; type more of the id"));
+}
+
+#[tokio::test]
+async fn test_console_searches_a_time_range() {
+    let clipboard = Arc::new(MockClipboard::new());
+    let manager = Arc::new(ClipboardManager::new_empty().with_clipboard(clipboard.clone()));
+    let mut old = ClipboardItem::new_text("old invoice".to_string());
+    old.timestamp = chrono::Utc::now() - chrono::Duration::days(30);
+    manager.add_clipboard_item(old).await.unwrap();
+    for text in ["new invoice", "new notes"] {
+        manager
+            .add_clipboard_item(ClipboardItem::new_text(text.to_string()))
+            .await
+            .unwrap();
+    }
+    let mut console = ConsoleInterface::new(ClipboardService::new_with_manager(manager), None);
+
+    let output = run_console(
+        &mut console,
+        "\ns\nrange last 7 days invoice\n1\nrange today\nrange next week\nq\nq\nexit\n",
+    )
+    .await;
+
+    assert!(output.contains("Found 1 item(s):\n  1. new invoice"));
+    assert_eq!(
+        clipboard.last_write(),
+        Some(ClipboardWrite::Text("new invoice".to_string()))
+    );
+    assert!(output.contains("Found 2 item(s):"));
+    assert!(output.contains("Unknown range 'next week'"));
+}
+
+#[tokio::test]
+async fn test_console_compact_and_detailed_views_differ() {
+    let texts: Vec<String> = (1..=5).map(|n| format!("entry {n}")).collect();
+    let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let (service, _) = service_with(&texts).await;
+    let config_file = std::env::temp_dir().join(format!(
+        "clipboard-history-console-view-{}.toml",
+        uuid::Uuid::new_v4()
+    ));
+    let mut console = ConsoleInterface::new(service.clone(), None)
+        .with_max_results(2)
+        .with_config_file(config_file.clone());
+
+    let detailed = run_console(&mut console, "\nq\nexit\n").await;
+    let compact = run_console(&mut console, "\nv\nq\nexit\n").await;
+    let compact = &compact[compact.find("Switched to the compact view").unwrap()..];
+
+    assert!(detailed.contains("  1. entry 5 ("));
+    assert!(detailed.contains("     Text · 7 bytes · "));
+    assert!(detailed.contains("(showing 2 of 5)"));
+    assert!(compact.contains("  1. entry 5\n"));
+    assert!(compact.contains("  4. entry 2\n"));
+    assert!(!compact.contains("bytes"));
+    assert!(compact.contains("(showing 4 of 5)"));
+    assert_eq!(
+        PopupConfig::load_from(&config_file).unwrap().console_view,
+        ConsoleView::Compact
+    );
+
+    // A console started from the saved setting opens in the compact view
+    let mut reopened = ConsoleInterface::new(service, None)
+        .with_max_results(2)
+        .with_view(PopupConfig::load_from(&config_file).unwrap().console_view);
+    assert!(run_console(&mut reopened, "\nq\nexit\n")
+        .await
+        .contains("(showing 4 of 5)"));
+
+    let _ = std::fs::remove_file(config_file);
+}